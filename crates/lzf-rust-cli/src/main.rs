@@ -4,9 +4,14 @@ use std::env;
 use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::Instant;
 
-use lzf_rust::{CompressionMode, LzfWriter, encode_blocks_with_mode};
-use lzf_rust::{LzfReader, decode_blocks};
+use lzf_rust::starts_with_block_header;
+use lzf_rust::{BlockJob, CompressionMode, LzfWriter, encode_blocks_with_mode};
+use lzf_rust::{BlockStats, BlockType, FrameIter, LzfReader, analyze_blocks, decode_blocks};
+use lzf_rust::{compress_with_mode, decompress_to_vec_with_limit, max_compressed_size};
 
 #[cfg(unix)]
 use rustix::termios;
@@ -17,44 +22,173 @@ use std::os::unix::fs::PermissionsExt;
 
 const BLOCKSIZE: usize = 1024 * 64 - 1;
 const MAX_BLOCKSIZE: usize = BLOCKSIZE;
+const SUFFIX: &str = ".lzf";
+/// Level used without `-1` .. `-9`; only level 9 selects best compression.
+const DEFAULT_LEVEL: u8 = 1;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Mode {
     Compress,
     Uncompress,
     Lzcat,
+    Test,
+}
+
+/// Headerless formats selected by `--raw`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RawFormat {
+    /// Bare liblzf tokens; the decoded size travels out of band.
+    Bare,
+    /// Decoded size as 4 big-endian bytes, then the tokens.
+    Prefixed,
+}
+
+/// Outcome of processing an input, ordered from best to worst, following
+/// `gzip`: errors outrank warnings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    Ok,
+    /// Usage problem, or an input skipped without touching it.
+    Warning,
+    /// I/O error or corrupt input.
+    Error,
+}
+
+impl Status {
+    fn code(self) -> i32 {
+        match self {
+            Status::Ok => 0,
+            Status::Error => 1,
+            Status::Warning => 2,
+        }
+    }
+}
+
+/// Prints a diagnostic that `-q` hides: a skipped input or a setting that
+/// was ignored. Errors always use plain `eprintln!`.
+macro_rules! warning {
+    ($cfg:expr, $($arg:tt)*) => {
+        if !$cfg.quiet {
+            eprintln!($($arg)*);
+        }
+    };
 }
 
 #[derive(Debug)]
 struct Config {
     mode: Mode,
     force: bool,
-    verbose: bool,
-    best: bool,
+    /// Number of `-v` flags; cleared by `-q`.
+    verbose: u8,
+    /// Set by `-q`: hides warnings, never errors.
+    quiet: bool,
+    to_stdout: bool,
+    list: bool,
+    info: bool,
+    selftest: bool,
+    level: u8,
     blocksize: usize,
+    threads: usize,
+    suffix: String,
+    output: Option<PathBuf>,
+    parents: bool,
+    raw: Option<RawFormat>,
+    size: Option<usize>,
     files: Vec<String>,
 }
 
+impl Config {
+    fn compression_mode(&self) -> CompressionMode {
+        if self.level >= 9 { CompressionMode::Best } else { CompressionMode::Normal }
+    }
+}
+
 fn usage(rc: i32) -> ! {
     eprintln!();
     eprintln!("lzf-rust: LZF compression/decompression utility implemented in Rust.");
     eprintln!("Repository: https://github.com/xorgy/lzf-rust");
     eprintln!();
-    eprintln!("usage: lzf [-dufhvb9] [file ...]");
+    eprintln!(
+        "usage: lzf [-dutlfhqvVOb] [-1 .. -9] [-S suffix] [-T threads] [-o output] [file ...]"
+    );
     eprintln!("       unlzf [file ...]");
     eprintln!("       lzcat [file ...]");
     eprintln!();
     eprintln!("-c --compress    compress");
     eprintln!("-d --decompress  decompress");
+    eprintln!("-t --test        test compressed file integrity");
+    eprintln!("-l --list        list compressed file sizes and ratios");
+    eprintln!("   --info        show the block layout of compressed files");
+    eprintln!("-1 --fast        fast compression (levels below 9 use it)");
     eprintln!("-9 --best        best compression");
     eprintln!("-f --force       force overwrite of output file");
+    eprintln!("-O --to-stdout   write output to standard output, keep input files");
+    eprintln!("-o # --output #  write output to # (one input file only; - for stdout)");
+    eprintln!("   --parents     create missing parent directories of the -o output");
     eprintln!("-h --help        give this help");
-    eprintln!("-v --verbose     verbose mode");
-    eprintln!("-b # --blocksize # set blocksize");
+    eprintln!("-V --version     print the version and exit");
+    eprintln!("   --selftest    run built-in round-trip checks and exit");
+    eprintln!("-q --quiet       suppress warnings");
+    eprintln!("-v --verbose     verbose mode (repeat for block details)");
+    eprintln!("-b # --blocksize # set blocksize (1 to 65535; k suffix for KiB)");
+    eprintln!("-S .suf --suffix .suf use suffix .suf instead of .lzf");
+    eprintln!("-T # --threads # compress on # threads (0: one per core)");
+    eprintln!("   --raw[=prefixed] headerless liblzf data, optionally length-prefixed");
+    eprintln!("   --size #      decoded size of bare --raw input");
     eprintln!();
     std::process::exit(rc);
 }
 
+fn version() -> ! {
+    println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    std::process::exit(0);
+}
+
+/// Long options that take no argument, so `--name=value` is an error.
+const LONG_FLAGS: [&str; 16] = [
+    "compress",
+    "decompress",
+    "uncompress",
+    "test",
+    "list",
+    "info",
+    "selftest",
+    "fast",
+    "best",
+    "force",
+    "help",
+    "version",
+    "verbose",
+    "quiet",
+    "to-stdout",
+    "parents",
+];
+
+/// Reports a malformed command line in one line, without the full usage.
+fn arg_error(imagename: &str, msg: &str) -> ! {
+    eprintln!("{imagename}: {msg}");
+    eprintln!("Try '{imagename} --help' for more information.");
+    std::process::exit(Status::Warning.code());
+}
+
+/// Returns the argument of `option`: `inline` if present, or else the next
+/// command-line argument, which `*i` is advanced to.
+fn option_arg(
+    imagename: &str,
+    option: &str,
+    inline: Option<String>,
+    args: &[String],
+    i: &mut usize,
+) -> String {
+    if let Some(v) = inline {
+        return v;
+    }
+    *i += 1;
+    args.get(*i)
+        .cloned()
+        .unwrap_or_else(|| arg_error(imagename, &format!("option {option} requires an argument")))
+}
+
 fn parse_u64_auto_radix(s: &str) -> Option<u64> {
     let s = s.trim();
     if s.is_empty() {
@@ -72,11 +206,33 @@ fn parse_u64_auto_radix(s: &str) -> Option<u64> {
     s.parse::<u64>().ok()
 }
 
-fn parse_block_size_compat(s: &str) -> usize {
-    let Some(v) = parse_u64_auto_radix(s) else {
-        return BLOCKSIZE;
+/// Parses a block size as the original utility does (decimal, `0x` hex or
+/// leading-`0` octal), optionally scaled by a `k`/`K` or `m`/`M` suffix.
+/// Returns `None` unless the result is in `1..=MAX_BLOCKSIZE`.
+fn parse_block_size(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let (digits, scale) = match s.as_bytes().last()? {
+        b'k' | b'K' => (&s[..s.len() - 1], 1 << 10),
+        b'm' | b'M' => (&s[..s.len() - 1], 1 << 20),
+        _ => (s, 1),
+    };
+    let v = parse_u64_auto_radix(digits)?.checked_mul(scale)?;
+    if v == 0 || v > MAX_BLOCKSIZE as u64 { None } else { Some(v as usize) }
+}
+
+/// Parses a `-b` argument, exiting with a usage error if it is invalid.
+fn block_size_arg(imagename: &str, s: &str) -> usize {
+    parse_block_size(s).unwrap_or_else(|| {
+        arg_error(imagename, &format!("invalid block size '{s}' -- expected 1 to {MAX_BLOCKSIZE}"))
+    })
+}
+
+/// Parses a `-T` argument; 0 selects one thread per available core.
+fn parse_threads(imagename: &str, s: &str) -> usize {
+    let Ok(n) = s.trim().parse::<usize>() else {
+        arg_error(imagename, &format!("invalid thread count '{s}'"));
     };
-    if v == 0 || v > MAX_BLOCKSIZE as u64 { BLOCKSIZE } else { v as usize }
+    if n == 0 { thread::available_parallelism().map_or(1, |n| n.get()) } else { n }
 }
 
 fn program_name(args0: Option<&str>) -> &str {
@@ -96,10 +252,23 @@ fn parse_args(args: &[String]) -> Config {
     }
 
     let mut force = false;
-    let mut verbose = false;
-    let mut best = false;
-    let mut blocksize =
-        env::var("LZF_BLOCKSIZE").ok().map_or(BLOCKSIZE, |v| parse_block_size_compat(&v));
+    let mut verbose = 0u8;
+    let mut quiet = false;
+    let mut to_stdout = false;
+    let mut list = false;
+    let mut info = false;
+    let mut selftest = false;
+    let mut level = DEFAULT_LEVEL;
+    let env_blocksize = env::var("LZF_BLOCKSIZE").ok();
+    let mut blocksize = env_blocksize.as_deref().and_then(parse_block_size).unwrap_or(BLOCKSIZE);
+
+    let mut suffix = String::from(SUFFIX);
+    let mut threads = 1usize;
+    let mut output = None;
+    let mut parents = false;
+    let mut blocksize_set = false;
+    let mut raw = None;
+    let mut size = None;
 
     let mut i = 1usize;
     let mut files = Vec::new();
@@ -119,61 +288,172 @@ fn parse_args(args: &[String]) -> Config {
 
         if let Some(long) = arg.strip_prefix("--") {
             let (name, value) = long.split_once('=').map_or((long, None), |(n, v)| (n, Some(v)));
+            if value.is_some() && LONG_FLAGS.contains(&name) {
+                arg_error(imagename, &format!("option '--{name}' doesn't allow an argument"));
+            }
+            let option = format!("'--{name}'");
+            let value = value.map(str::to_string);
             match name {
                 "compress" => mode = Mode::Compress,
                 "decompress" | "uncompress" => mode = Mode::Uncompress,
-                "best" => best = true,
+                "test" => mode = Mode::Test,
+                "list" => list = true,
+                "info" => info = true,
+                "selftest" => selftest = true,
+                "fast" => level = 1,
+                "best" => level = 9,
                 "force" => force = true,
                 "help" => usage(0),
-                "verbose" => verbose = true,
+                "version" => version(),
+                "verbose" => {
+                    verbose = verbose.saturating_add(1);
+                    quiet = false;
+                }
+                "quiet" => {
+                    quiet = true;
+                    verbose = 0;
+                }
+                "to-stdout" => to_stdout = true,
+                "parents" => parents = true,
                 "blocksize" => {
-                    let val = if let Some(v) = value {
-                        v
-                    } else {
-                        if i + 1 >= args.len() {
-                            usage(1);
-                        }
-                        i += 1;
-                        &args[i]
+                    let val = option_arg(imagename, &option, value, args, &mut i);
+                    blocksize = block_size_arg(imagename, &val);
+                    blocksize_set = true;
+                }
+                "suffix" => suffix = option_arg(imagename, &option, value, args, &mut i),
+                "output" => output = Some(option_arg(imagename, &option, value, args, &mut i)),
+                "raw" => {
+                    raw = match value.as_deref() {
+                        None => Some(RawFormat::Bare),
+                        Some("prefixed") => Some(RawFormat::Prefixed),
+                        Some(v) => arg_error(
+                            imagename,
+                            &format!("invalid argument '{v}' for '--raw' -- expected 'prefixed'"),
+                        ),
                     };
-                    blocksize = parse_block_size_compat(val);
                 }
-                _ => usage(1),
+                "size" => {
+                    let val = option_arg(imagename, &option, value, args, &mut i);
+                    let Ok(n) = val.trim().parse::<usize>() else {
+                        arg_error(imagename, &format!("invalid size '{val}'"));
+                    };
+                    size = Some(n);
+                }
+                "threads" => {
+                    let val = option_arg(imagename, &option, value, args, &mut i);
+                    threads = parse_threads(imagename, &val);
+                }
+                _ => arg_error(imagename, &format!("unrecognized option '--{name}'")),
             }
             i += 1;
             continue;
         }
 
-        let mut chars = arg[1..].chars().peekable();
+        let mut chars = arg[1..].chars();
         while let Some(c) = chars.next() {
+            let option = format!("'-{c}'");
             match c {
                 'c' => mode = Mode::Compress,
                 'd' => mode = Mode::Uncompress,
-                '9' => best = true,
+                't' => mode = Mode::Test,
+                'l' => list = true,
+                '1'..='9' => level = c as u8 - b'0',
                 'f' => force = true,
                 'h' => usage(0),
-                'v' => verbose = true,
-                'b' => {
-                    let inline: String = chars.collect();
-                    if inline.is_empty() {
-                        if i + 1 >= args.len() {
-                            usage(1);
+                'V' => version(),
+                'v' => {
+                    verbose = verbose.saturating_add(1);
+                    quiet = false;
+                }
+                'q' => {
+                    quiet = true;
+                    verbose = 0;
+                }
+                'O' => to_stdout = true,
+                // The rest of the argument, if any, is the option's value.
+                'b' | 'S' | 'o' | 'T' => {
+                    let inline = Some(chars.as_str().to_string()).filter(|v| !v.is_empty());
+                    let val = option_arg(imagename, &option, inline, args, &mut i);
+                    match c {
+                        'b' => {
+                            blocksize = block_size_arg(imagename, &val);
+                            blocksize_set = true;
                         }
-                        i += 1;
-                        blocksize = parse_block_size_compat(&args[i]);
-                    } else {
-                        blocksize = parse_block_size_compat(&inline);
+                        'S' => suffix = val,
+                        'o' => output = Some(val),
+                        _ => threads = parse_threads(imagename, &val),
                     }
                     break;
                 }
-                _ => usage(1),
+                _ => arg_error(imagename, &format!("invalid option -- '{c}'")),
             }
         }
 
         i += 1;
     }
 
-    Config { mode, force, verbose, best, blocksize, files }
+    // A bad LZF_BLOCKSIZE only warns, as scripts may set it far from the call.
+    if let Some(v) = env_blocksize.filter(|v| !quiet && parse_block_size(v).is_none()) {
+        eprintln!(
+            "{imagename}: ignoring invalid LZF_BLOCKSIZE '{v}' -- expected 1 to {MAX_BLOCKSIZE}"
+        );
+    }
+
+    if mode == Mode::Compress && !list && !info && suffix.is_empty() {
+        eprintln!("{imagename}: empty suffix");
+        std::process::exit(Status::Warning.code());
+    }
+    if output.is_some() && files.len() != 1 {
+        eprintln!("{imagename}: -o needs exactly one input file");
+        std::process::exit(Status::Warning.code());
+    }
+    let output = match output.as_deref() {
+        Some("-") => {
+            to_stdout = true;
+            None
+        }
+        other => other.map(PathBuf::from),
+    };
+    if output.is_some() && files[0] == "-" {
+        eprintln!("{imagename}: -o cannot name the output of standard input");
+        std::process::exit(Status::Warning.code());
+    }
+    let raw_conflict = match raw {
+        None if size.is_some() => Some("--size needs --raw"),
+        None => None,
+        Some(_) if blocksize_set || threads > 1 => Some("--raw has no blocks; drop -b and -T"),
+        Some(_) if list || info || mode == Mode::Test => {
+            Some("--raw data cannot be listed, inspected or tested")
+        }
+        Some(RawFormat::Bare) if mode != Mode::Compress && size.is_none() => {
+            Some("decompressing --raw needs --size or --raw=prefixed")
+        }
+        Some(_) => None,
+    };
+    if let Some(msg) = raw_conflict {
+        eprintln!("{imagename}: {msg}");
+        std::process::exit(Status::Warning.code());
+    }
+
+    Config {
+        mode,
+        force,
+        verbose,
+        quiet,
+        to_stdout,
+        list,
+        info,
+        selftest,
+        level,
+        blocksize,
+        threads,
+        suffix,
+        output,
+        parents,
+        raw,
+        size,
+        files,
+    }
 }
 
 #[cfg(unix)]
@@ -196,18 +476,28 @@ fn stdout_is_tty() -> bool {
     false
 }
 
-fn compose_name(mode: Mode, input: &Path) -> Result<PathBuf, String> {
+/// Derives the output name: `suffix` is appended on compress, and stripped,
+/// as is the default `.lzf`, on decompress. A `framed` input without either
+/// suffix decompresses to its name plus `.out`.
+fn compose_name(mode: Mode, input: &Path, suffix: &str, framed: bool) -> Result<PathBuf, String> {
     let s = input.to_str().ok_or_else(|| format!("{}: invalid path", input.display()))?;
     match mode {
-        Mode::Compress => Ok(PathBuf::from(format!("{s}.lzf"))),
+        Mode::Compress => Ok(PathBuf::from(format!("{s}{suffix}"))),
         Mode::Uncompress => {
-            if let Some(stripped) = s.strip_suffix(".lzf") {
-                Ok(PathBuf::from(stripped))
-            } else {
-                Err(format!("{s}: unknown suffix"))
+            let stripped = Some(suffix)
+                .filter(|suffix| !suffix.is_empty())
+                .and_then(|suffix| s.strip_suffix(suffix))
+                .or_else(|| s.strip_suffix(SUFFIX));
+            match stripped {
+                Some(stripped) => Ok(PathBuf::from(stripped)),
+                None if framed => Ok(PathBuf::from(format!("{s}.out"))),
+                None if suffix.is_empty() || suffix == SUFFIX => {
+                    Err(format!("{s}: unknown suffix -- expected {SUFFIX}"))
+                }
+                None => Err(format!("{s}: unknown suffix -- expected {suffix} or {SUFFIX}")),
             }
         }
-        Mode::Lzcat => Ok(PathBuf::new()),
+        Mode::Lzcat | Mode::Test => Ok(PathBuf::new()),
     }
 }
 
@@ -215,108 +505,476 @@ fn read_all(path: &Path) -> io::Result<Vec<u8>> {
     fs::read(path)
 }
 
-fn write_all(path: &Path, data: &[u8], force: bool) -> io::Result<()> {
-    let mut opts = OpenOptions::new();
-    opts.write(true).create(true).truncate(true);
-    if !force {
-        opts.create_new(true);
+/// Writes what `fill` produces to a temporary file beside `path`, syncs it
+/// and renames it into place, so `path` never holds partial output. Without
+/// `force`, an existing `path` is left alone.
+fn write_atomically<T>(
+    path: &Path,
+    force: bool,
+    fill: impl FnOnce(&mut fs::File) -> io::Result<T>,
+) -> io::Result<T> {
+    if !force && fs::symlink_metadata(path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "already exists; not overwritten",
+        ));
+    }
+
+    let tmp = temp_name(path);
+    let mut f = OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+    let res = fill(&mut f).and_then(|v| f.sync_all().map(|()| v));
+    drop(f);
+    let res = res.and_then(|v| fs::rename(&tmp, path).map(|()| v));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    res
+}
+
+/// Counts the bytes passed through `inner` and keeps the kind of its last
+/// failure: the crate's errors do not say which side of a copy went wrong,
+/// nor whether a pipe was closed.
+struct Counted<T> {
+    inner: T,
+    count: u64,
+    failure: Option<io::ErrorKind>,
+}
+
+impl<T> Counted<T> {
+    fn new(inner: T) -> Self {
+        Self { inner, count: 0, failure: None }
+    }
+
+    fn broken_pipe(&self) -> bool {
+        self.failure == Some(io::ErrorKind::BrokenPipe)
+    }
+
+    fn note(&mut self, res: &io::Result<usize>) {
+        match res {
+            Ok(n) => self.count += *n as u64,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => self.failure = Some(e.kind()),
+        }
+    }
+}
+
+impl<R: io::Read> io::Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let res = self.inner.read(buf);
+        self.note(&res);
+        res
+    }
+}
+
+impl<W: io::Write> io::Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.inner.write(buf);
+        self.note(&res);
+        if matches!(res, Ok(0)) && !buf.is_empty() {
+            self.failure = Some(io::ErrorKind::WriteZero);
+        }
+        res
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let res = self.inner.flush();
+        if let Err(e) = &res {
+            self.failure = Some(e.kind());
+        }
+        res
     }
-    let mut f = opts.open(path)?;
-    f.write_all(data)
+}
+
+/// Compresses `input` into `out` on `cfg.threads` threads, holding only a
+/// few blocks per thread in memory.
+fn compress_stream<R: io::Read, W: io::Write>(
+    cfg: &Config,
+    input: &mut Counted<R>,
+    out: &mut Counted<W>,
+) -> lzf_rust::Result<()> {
+    let mut writer = LzfWriter::new_with_mode(out, cfg.blocksize, cfg.compression_mode())?;
+    compress_threaded(input, &mut writer, cfg.blocksize, cfg.threads)?;
+    writer.finish().map(drop)
+}
+
+/// `dir/name` becomes `dir/.name.<pid>.tmp`.
+fn temp_name(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
 }
 
 fn encode_bytes(imagename: &str, input: &[u8], cfg: &Config) -> Result<Vec<u8>, ()> {
-    let mode = if cfg.best { CompressionMode::Best } else { CompressionMode::Normal };
-    encode_blocks_with_mode(input, cfg.blocksize, mode).map_err(|_| {
+    let mode = cfg.compression_mode();
+    let encoded = if let Some(format) = cfg.raw {
+        encode_raw(input, format, mode)
+    } else if cfg.threads > 1 {
+        LzfWriter::new_with_mode(Vec::new(), cfg.blocksize, mode).and_then(|mut writer| {
+            compress_threaded(&mut &input[..], &mut writer, cfg.blocksize, cfg.threads)?;
+            writer.finish()
+        })
+    } else {
+        encode_blocks_with_mode(input, cfg.blocksize, mode)
+    };
+    encoded.map_err(|_| {
         eprintln!("{imagename}: compress failed");
     })
 }
 
-fn decode_bytes(imagename: &str, input: &[u8]) -> Result<Vec<u8>, ()> {
-    decode_blocks(input).map_err(|_| {
+/// Feeds `input` into `writer` in `blocksize` pieces, compressing the blocks
+/// on `threads` worker threads.
+///
+/// Frames reach the underlying writer in block order, so the output is the
+/// same as with a single thread. At most two blocks per thread are in
+/// flight. The final partial block is left buffered for `finish`.
+fn compress_threaded<R: lzf_rust::Read + ?Sized, W: lzf_rust::Write>(
+    input: &mut R,
+    writer: &mut LzfWriter<W>,
+    blocksize: usize,
+    threads: usize,
+) -> lzf_rust::Result<()> {
+    writer.enable_block_jobs();
+    let max_in_flight = threads * 2;
+    let mut block = vec![0u8; blocksize];
+    let (job_tx, job_rx) = mpsc::sync_channel::<BlockJob>(threads);
+    let job_rx = Mutex::new(job_rx);
+    let (frame_tx, frame_rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let frame_tx = frame_tx.clone();
+            let job_rx = &job_rx;
+            scope.spawn(move || {
+                let next_job = || job_rx.lock().ok()?.recv().ok();
+                while let Some(job) = next_job() {
+                    if frame_tx.send(job.run()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(frame_tx);
+
+        let accept_next = |writer: &mut LzfWriter<W>| {
+            let frame = frame_rx.recv().map_err(|_| lzf_rust::Error::Other)?;
+            writer.accept_frame(frame)
+        };
+        let mut in_flight = 0usize;
+        let res = (|| {
+            loop {
+                let n = input.read_exact_or_eof(&mut block)?;
+                lzf_rust::Write::write_all(writer, &block[..n])?;
+                while let Some(job) = writer.take_block_job() {
+                    if in_flight == max_in_flight {
+                        accept_next(writer)?;
+                        in_flight -= 1;
+                    }
+                    job_tx.send(job).map_err(|_| lzf_rust::Error::Other)?;
+                    in_flight += 1;
+                }
+                if n < block.len() {
+                    break;
+                }
+            }
+            while in_flight > 0 {
+                accept_next(writer)?;
+                in_flight -= 1;
+            }
+            Ok(())
+        })();
+        // Lets the workers run out of jobs and exit.
+        drop(job_tx);
+        res
+    })
+}
+
+fn decode_bytes(imagename: &str, input: &[u8], cfg: &Config) -> Result<Vec<u8>, ()> {
+    let decoded = match cfg.raw {
+        Some(format) => decode_raw(input, format, cfg.size),
+        None => decode_blocks(input),
+    };
+    decoded.map_err(|_| {
         eprintln!("{imagename}: decompress: invalid stream - data corrupted");
     })
 }
 
-fn print_verbose(mode: Mode, src: &Path, dst: &Path, nr_read: usize, nr_written: usize) {
-    let pct = match mode {
-        Mode::Compress => {
-            if nr_read == 0 {
-                0.0
-            } else {
-                100.0 - (nr_written as f64 / (nr_read as f64 / 100.0))
-            }
-        }
-        Mode::Uncompress | Mode::Lzcat => {
-            if nr_written == 0 {
-                0.0
-            } else {
-                100.0 - (nr_read as f64 / (nr_written as f64 / 100.0))
-            }
+/// Compresses `input` into a bare token stream, behind its length for
+/// `RawFormat::Prefixed`.
+fn encode_raw(input: &[u8], format: RawFormat, mode: CompressionMode) -> lzf_rust::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    if format == RawFormat::Prefixed {
+        let len = u32::try_from(input.len()).map_err(|_| lzf_rust::Error::LimitExceeded)?;
+        out.extend_from_slice(&len.to_be_bytes());
+    }
+    let start = out.len();
+    out.resize(start + max_compressed_size(input.len()), 0);
+    let written = compress_with_mode(input, &mut out[start..], mode)?;
+    out.truncate(start + written);
+    Ok(out)
+}
+
+/// Decodes what `encode_raw` produced; bare input must decode to exactly
+/// `size` bytes, and `size`, if given, must match a prefix.
+fn decode_raw(input: &[u8], format: RawFormat, size: Option<usize>) -> lzf_rust::Result<Vec<u8>> {
+    let (expected, tokens) = match format {
+        RawFormat::Bare => (size.ok_or(lzf_rust::Error::InvalidParameter)?, input),
+        RawFormat::Prefixed => {
+            let (len, tokens) = input.split_first_chunk::<4>().ok_or(lzf_rust::Error::Eof)?;
+            (u32::from_be_bytes(*len) as usize, tokens)
         }
     };
+    if size.is_some_and(|size| size != expected) {
+        return Err(lzf_rust::Error::InvalidData);
+    }
+    let decoded = decompress_to_vec_with_limit(tokens, expected)?;
+    if decoded.len() != expected {
+        return Err(lzf_rust::Error::InvalidData);
+    }
+    Ok(decoded)
+}
 
-    eprintln!("{}:  {:5.1}% -- replaced with {}", src.display(), pct, dst.display());
+/// Percentage saved by compressing `decoded` bytes into `compressed` bytes.
+fn saved_pct(compressed: u64, decoded: u64) -> f64 {
+    if decoded == 0 { 0.0 } else { 100.0 - (compressed as f64 / (decoded as f64 / 100.0)) }
 }
 
-fn run_file(imagename: &str, cfg: &Config, file: &str) -> i32 {
+/// Percentage saved when `mode` turned `nr_read` bytes into `nr_written`.
+fn mode_pct(mode: Mode, nr_read: u64, nr_written: u64) -> f64 {
+    match mode {
+        Mode::Compress => saved_pct(nr_written, nr_read),
+        Mode::Uncompress | Mode::Lzcat | Mode::Test => saved_pct(nr_read, nr_written),
+    }
+}
+
+/// Byte counts over the inputs `run_file` converted, for the summary of a
+/// verbose run over several files. `-` operands are not counted.
+#[derive(Default)]
+struct RunTotals {
+    read: u64,
+    written: u64,
+}
+
+impl RunTotals {
+    fn add(&mut self, nr_read: u64, nr_written: u64) {
+        self.read += nr_read;
+        self.written += nr_written;
+    }
+}
+
+/// Reports the ratio for `src`; `dst` is `None` when output went to stdout
+/// or `src` was kept.
+fn print_verbose(cfg: &Config, src: &Path, dst: Option<&Path>, nr_read: u64, nr_written: u64) {
+    let pct = mode_pct(cfg.mode, nr_read, nr_written);
+    let level = match cfg.mode {
+        Mode::Compress => format!(" (level {})", cfg.level),
+        Mode::Uncompress | Mode::Lzcat | Mode::Test => String::new(),
+    };
+
+    match dst {
+        Some(dst) => {
+            eprintln!("{}:  {:5.1}%{level} -- replaced with {}", src.display(), pct, dst.display());
+        }
+        None => eprintln!("{}:  {:5.1}%{level}", src.display(), pct),
+    }
+}
+
+/// With `-vv`, summarizes the blocks of `framed`, the compressed side of
+/// `src`; with `-vvv`, describes each block as well.
+fn print_blocks(cfg: &Config, src: &Path, framed: &[u8]) {
+    let mode = match (cfg.mode, cfg.compression_mode()) {
+        (Mode::Compress, CompressionMode::Best) => ", best mode",
+        (Mode::Compress, CompressionMode::Normal) => ", normal mode",
+        _ => "",
+    };
+    if cfg.raw.is_some() {
+        eprintln!("{}: raw tokens{mode}", src.display());
+        return;
+    }
+    let Ok(stats) = analyze_blocks(framed) else {
+        return;
+    };
+    eprintln!(
+        "{}: {} blocks ({} stored){mode}",
+        src.display(),
+        stats.blocks,
+        stats.uncompressed_blocks
+    );
+    if cfg.verbose >= 3 {
+        for (i, frame) in FrameIter::new(framed).map_while(Result::ok).enumerate() {
+            eprintln!(
+                "{}: block {i} at {}: {:?}, {} bytes in {}",
+                src.display(),
+                frame.offset,
+                frame.block_type,
+                frame.uncompressed_len,
+                frame.compressed_len
+            );
+        }
+    }
+}
+
+fn run_file(imagename: &str, cfg: &Config, file: &str, totals: &mut RunTotals) -> Status {
     let input = Path::new(file);
 
-    let in_meta = match fs::symlink_metadata(input) {
+    // Follows symlinks, so a link to a regular file counts as one.
+    let in_meta = match fs::metadata(input) {
         Ok(m) => m,
         Err(e) => {
             eprintln!("{imagename}: {}: {e}", input.display());
-            return 1;
+            return Status::Error;
+        }
+    };
+
+    // With -f, pipes and devices are read to the end like gzip does, but
+    // their mode is not copied and they are never removed.
+    let regular = in_meta.file_type().is_file();
+    if !regular && (!cfg.force || in_meta.is_dir()) {
+        warning!(cfg, "{imagename}: {}: not a regular file.", input.display());
+        return Status::Warning;
+    }
+
+    if cfg.mode == Mode::Test {
+        return test_file(imagename, cfg, input);
+    }
+
+    // Compressing on several threads streams the file, so memory use
+    // depends on the thread count and block size, not the input size. Only
+    // the header is read ahead. The -vv block listing needs the whole output
+    // in memory, so it turns streaming off.
+    let streaming =
+        cfg.mode == Mode::Compress && cfg.raw.is_none() && cfg.threads > 1 && cfg.verbose < 2;
+    let read = fs::File::open(input).and_then(|mut file| {
+        let mut head = Vec::new();
+        if streaming {
+            io::Read::read_to_end(&mut io::Read::take(&mut file, MAX_HEADER), &mut head)?;
+        } else {
+            io::Read::read_to_end(&mut file, &mut head)?;
+        }
+        Ok((head, file))
+    });
+    let (in_bytes, file) = match read {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{imagename}: {}: {e}", input.display());
+            return Status::Error;
         }
     };
 
-    if !in_meta.file_type().is_file() {
-        eprintln!("{imagename}: {}: not a regular file.", input.display());
-        return 1;
+    // Framed data is recognized by content, whatever its name.
+    let framed = cfg.raw.is_none() && starts_with_block_header(&in_bytes);
+    if cfg.mode == Mode::Compress && framed && !cfg.force {
+        warning!(cfg, "{imagename}: {}: already lzf-compressed -- unchanged", input.display());
+        return Status::Warning;
     }
 
-    let out_path = if cfg.mode == Mode::Lzcat {
+    let to_stdout = cfg.to_stdout || cfg.mode == Mode::Lzcat;
+    let out_path = if to_stdout {
         PathBuf::new()
+    } else if let Some(output) = &cfg.output {
+        if fs::canonicalize(output).is_ok_and(|out| fs::canonicalize(input).is_ok_and(|i| i == out))
+        {
+            warning!(cfg, "{imagename}: {}: input and output are the same file", input.display());
+            return Status::Warning;
+        }
+        output.clone()
     } else {
-        match compose_name(cfg.mode, input) {
+        match compose_name(cfg.mode, input, &cfg.suffix, framed) {
             Ok(p) => p,
             Err(msg) => {
-                eprintln!("{imagename}: {msg}");
-                return 1;
+                warning!(cfg, "{imagename}: {msg}");
+                return Status::Warning;
             }
         }
     };
+    // Garbage fails here rather than partway through decoding.
+    if cfg.mode != Mode::Compress
+        && cfg.raw.is_none()
+        && !framed
+        && in_bytes.first().is_some_and(|&b| b != 0)
+    {
+        eprintln!("{imagename}: {}: not in lzf format", input.display());
+        return Status::Error;
+    }
 
-    let in_bytes = match read_all(input) {
-        Ok(b) => b,
-        Err(e) => {
-            eprintln!("{imagename}: {}: {e}", input.display());
-            return 1;
+    let out_bytes = if streaming {
+        Vec::new()
+    } else {
+        let converted = match cfg.mode {
+            Mode::Compress => encode_bytes(imagename, &in_bytes, cfg),
+            Mode::Uncompress | Mode::Lzcat | Mode::Test => decode_bytes(imagename, &in_bytes, cfg),
+        };
+        let Ok(out_bytes) = converted else {
+            return Status::Error;
+        };
+        if cfg.verbose >= 2 {
+            let framed = if cfg.mode == Mode::Compress { &out_bytes } else { &in_bytes };
+            print_blocks(cfg, input, framed);
         }
+        out_bytes
     };
+    let mut source = Counted::new(io::Read::chain(&in_bytes[..], file));
 
-    let out_bytes = match cfg.mode {
-        Mode::Compress => match encode_bytes(imagename, &in_bytes, cfg) {
-            Ok(o) => o,
-            Err(()) => return 1,
-        },
-        Mode::Uncompress | Mode::Lzcat => match decode_bytes(imagename, &in_bytes) {
-            Ok(o) => o,
-            Err(()) => return 1,
-        },
-    };
+    if to_stdout {
+        let mut out = Counted::new(io::stdout().lock());
+        let res = if streaming {
+            compress_stream(cfg, &mut source, &mut out).map_err(io::Error::from)
+        } else {
+            out.write_all(&out_bytes)
+        };
+        if let Err(e) = res {
+            if source.failure.is_some() {
+                eprintln!("{imagename}: {}: {e}", input.display());
+                return Status::Error;
+            }
+            return write_failed(imagename, out.broken_pipe());
+        }
+        let nr_read = if streaming { source.count } else { in_bytes.len() as u64 };
+        totals.add(nr_read, out.count);
+        if cfg.verbose > 0 {
+            print_verbose(cfg, input, None, nr_read, out.count);
+        }
+        return Status::Ok;
+    }
 
-    if cfg.mode == Mode::Lzcat {
-        if io::stdout().write_all(&out_bytes).is_err() {
-            eprintln!("{imagename}: write error");
-            return 1;
+    if cfg.parents && cfg.output.is_some() {
+        if let Some(parent) = out_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("{imagename}: {}: {e}", parent.display());
+                return Status::Error;
+            }
         }
-        return 0;
     }
+    let written = write_atomically(&out_path, cfg.force, |f| {
+        let mut out = Counted::new(f);
+        if streaming {
+            compress_stream(cfg, &mut source, &mut out)?;
+        } else {
+            out.write_all(&out_bytes)?;
+        }
+        Ok(out.count)
+    });
+    let nr_written = match written {
+        Ok(n) => n,
+        Err(e) if source.failure.is_some() => {
+            eprintln!("{imagename}: {}: {e}", input.display());
+            return Status::Error;
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            warning!(cfg, "{imagename}: {}: {e}", out_path.display());
+            return Status::Warning;
+        }
+        Err(e) => {
+            eprintln!("{imagename}: {}: {e}", out_path.display());
+            return Status::Error;
+        }
+    };
+    let nr_read = if streaming { source.count } else { in_bytes.len() as u64 };
 
-    if let Err(e) = write_all(&out_path, &out_bytes, cfg.force) {
-        eprintln!("{imagename}: {}: {e}", out_path.display());
-        return 1;
+    totals.add(nr_read, nr_written);
+    if !regular {
+        if cfg.verbose > 0 {
+            print_verbose(cfg, input, None, nr_read, nr_written);
+        }
+        return Status::Ok;
     }
 
     #[cfg(unix)]
@@ -325,97 +983,511 @@ fn run_file(imagename: &str, cfg: &Config, file: &str) -> i32 {
         let _ = fs::set_permissions(&out_path, fs::Permissions::from_mode(mode));
     }
 
-    if cfg.verbose {
-        print_verbose(cfg.mode, input, &out_path, in_bytes.len(), out_bytes.len());
+    if cfg.verbose > 0 {
+        print_verbose(cfg, input, Some(&out_path), nr_read, nr_written);
     }
 
     if let Err(e) = fs::remove_file(input) {
         eprintln!("{imagename}: {}: {e}", input.display());
-        return 1;
+        return Status::Error;
+    }
+
+    Status::Ok
+}
+
+/// Decodes `input` without keeping the output, reporting whether it is intact.
+fn test_file(imagename: &str, cfg: &Config, input: &Path) -> Status {
+    let file = match fs::File::open(input) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{imagename}: {}: {e}", input.display());
+            return Status::Error;
+        }
+    };
+    match lzf_rust::copy(&mut LzfReader::new(file), &mut lzf_rust::sink()) {
+        Ok(_) => {
+            if cfg.verbose > 0 {
+                eprintln!("{}: OK", input.display());
+            }
+            Status::Ok
+        }
+        Err(e) => {
+            eprintln!("{imagename}: {}: {e}", input.display());
+            Status::Error
+        }
     }
+}
 
-    0
+/// Reports a failed write to stdout. A closed pipe, as when `lzcat x.lzf |
+/// head` has read enough, is how the reader says it is done: the run ends
+/// there with status 0 and no message, like `zcat`. Rust ignores `SIGPIPE`,
+/// so that failure always arrives here rather than killing the process.
+fn write_failed(imagename: &str, broken_pipe: bool) -> Status {
+    if broken_pipe {
+        std::process::exit(Status::Ok.code());
+    }
+    eprintln!("{imagename}: write error");
+    Status::Error
 }
 
-fn run_stdio(imagename: &str, cfg: &Config) -> i32 {
+/// Processes stdin to stdout, when there are no file operands and for each
+/// `-` operand.
+fn run_stdio(imagename: &str, cfg: &Config) -> Status {
     if !cfg.force {
-        if matches!(cfg.mode, Mode::Uncompress | Mode::Lzcat) && stdin_is_tty() {
+        if matches!(cfg.mode, Mode::Uncompress | Mode::Lzcat | Mode::Test) && stdin_is_tty() {
             eprintln!(
                 "{imagename}: compressed data not read from a terminal. Use -f to force decompression."
             );
-            return 1;
+            return Status::Warning;
         }
         if cfg.mode == Mode::Compress && stdout_is_tty() {
             eprintln!(
                 "{imagename}: compressed data not written to a terminal. Use -f to force compression."
             );
-            return 1;
+            return Status::Warning;
         }
     }
 
-    let mut in_lock = io::stdin().lock();
-    let mut out_lock = io::stdout().lock();
+    let mut in_lock = Counted::new(io::stdin().lock());
+    let mut out_lock = Counted::new(io::stdout().lock());
+    // Read failures come back from the crate as `Error::Other`, like write
+    // failures; the side that failed tells them apart.
+    let read_failed = |in_lock: &Counted<_>| {
+        let kind = in_lock.failure.unwrap_or(io::ErrorKind::Other);
+        eprintln!("{imagename}: stdin: {}", io::Error::from(kind));
+        Status::Error
+    };
+
+    if cfg.raw.is_some() {
+        let mut input = Vec::new();
+        if io::Read::read_to_end(&mut in_lock, &mut input).is_err() {
+            eprintln!("{imagename}: read error");
+            return Status::Error;
+        }
+        let output = match cfg.mode {
+            Mode::Compress => encode_bytes(imagename, &input, cfg),
+            Mode::Uncompress | Mode::Lzcat | Mode::Test => decode_bytes(imagename, &input, cfg),
+        };
+        let Ok(output) = output else {
+            return Status::Error;
+        };
+        if out_lock.write_all(&output).and_then(|()| out_lock.flush()).is_err() {
+            return write_failed(imagename, out_lock.broken_pipe());
+        }
+        return Status::Ok;
+    }
 
     match cfg.mode {
         Mode::Compress => {
-            let mode = if cfg.best { CompressionMode::Best } else { CompressionMode::Normal };
+            let mode = cfg.compression_mode();
             let mut writer = match LzfWriter::new_with_mode(&mut out_lock, cfg.blocksize, mode) {
                 Ok(w) => w,
                 Err(_) => {
                     eprintln!("{imagename}: compress failed");
-                    return 1;
+                    return Status::Error;
                 }
             };
 
-            let read_chunk = cfg.blocksize.saturating_mul(16).clamp(1, 1 << 20);
-            let mut buf = vec![0u8; read_chunk];
-            loop {
-                let n = match io::Read::read(&mut in_lock, &mut buf[..]) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        eprintln!("{imagename}: read error");
-                        return 1;
-                    }
-                };
-                if n == 0 {
-                    break;
-                }
-                if lzf_rust::Write::write_all(&mut writer, &buf[..n]).is_err() {
-                    eprintln!("{imagename}: write error");
-                    return 1;
+            let res = if cfg.threads > 1 {
+                compress_threaded(&mut in_lock, &mut writer, cfg.blocksize, cfg.threads)
+            } else {
+                let read_chunk = cfg.blocksize.saturating_mul(16).clamp(1, 1 << 20);
+                let mut buf = vec![0u8; read_chunk];
+                lzf_rust::copy_with_buffer(&mut in_lock, &mut writer, &mut buf).map(drop)
+            };
+            if res.is_err() {
+                drop(writer);
+                if out_lock.failure.is_some() {
+                    return write_failed(imagename, out_lock.broken_pipe());
                 }
+                return read_failed(&in_lock);
             }
 
             if writer.finish().is_err() {
-                eprintln!("{imagename}: write error");
-                return 1;
+                return write_failed(imagename, out_lock.broken_pipe());
+            }
+            Status::Ok
+        }
+        Mode::Test => {
+            let mut reader = LzfReader::new(&mut in_lock);
+            match lzf_rust::copy(&mut reader, &mut lzf_rust::sink()) {
+                Ok(_) => {
+                    if cfg.verbose > 0 {
+                        eprintln!("stdin: OK");
+                    }
+                    Status::Ok
+                }
+                Err(e) => {
+                    eprintln!("{imagename}: stdin: {e}");
+                    Status::Error
+                }
             }
-            0
         }
         Mode::Uncompress | Mode::Lzcat => {
             let mut reader = LzfReader::new(&mut in_lock);
             let mut buf = vec![0u8; 1024 * 1024];
+            match lzf_rust::copy_with_buffer(&mut reader, &mut out_lock, &mut buf) {
+                Ok(_) => {}
+                Err(_) if out_lock.failure.is_some() => {
+                    return write_failed(imagename, out_lock.broken_pipe());
+                }
+                Err(_) if in_lock.failure.is_some() => return read_failed(&in_lock),
+                Err(_) => {
+                    eprintln!("{imagename}: decompress: invalid stream - data corrupted");
+                    return Status::Error;
+                }
+            }
 
-            loop {
-                let n = match lzf_rust::Read::read(&mut reader, &mut buf[..]) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        eprintln!("{imagename}: decompress: invalid stream - data corrupted");
-                        return 1;
+            Status::Ok
+        }
+    }
+}
+
+fn print_list_line(cfg: &Config, compressed: u64, stats: &BlockStats, name: &str) {
+    let pct = saved_pct(compressed, stats.decoded_len);
+    if cfg.verbose > 0 {
+        println!(
+            "{compressed:>19} {:>19} {pct:5.1}% {:>8} {:>8} {name}",
+            stats.decoded_len, stats.blocks, stats.uncompressed_blocks
+        );
+    } else {
+        println!("{compressed:>19} {:>19} {pct:5.1}% {name}", stats.decoded_len);
+    }
+}
+
+/// Lists sizes and ratios of the input files, or of stdin, from their block
+/// headers, without decompressing.
+fn run_list(imagename: &str, cfg: &Config) -> Status {
+    let inputs: Vec<Option<&str>> = if cfg.files.is_empty() {
+        vec![None]
+    } else {
+        cfg.files.iter().map(|f| Some(f.as_str()).filter(|f| *f != "-")).collect()
+    };
+
+    if inputs.contains(&None) && !cfg.force && stdin_is_tty() {
+        eprintln!(
+            "{imagename}: compressed data not read from a terminal. Use -f to force decompression."
+        );
+        return Status::Warning;
+    }
+
+    if cfg.verbose > 0 {
+        println!("         compressed        uncompressed  ratio   blocks   stored name");
+    } else {
+        println!("         compressed        uncompressed  ratio name");
+    }
+
+    let mut rc = Status::Ok;
+    let mut listed = 0usize;
+    let mut totals = BlockStats::default();
+    let mut total_compressed = 0u64;
+    for input in inputs {
+        let name = input.unwrap_or("stdin");
+        let bytes = match input {
+            Some(file) => read_all(Path::new(file)),
+            None => {
+                let mut bytes = Vec::new();
+                io::Read::read_to_end(&mut io::stdin().lock(), &mut bytes).map(|_| bytes)
+            }
+        };
+        let bytes = match bytes {
+            Ok(b) => b,
+            Err(e) => {
+                eprintln!("{imagename}: {name}: {e}");
+                rc = Status::Error;
+                continue;
+            }
+        };
+        let Ok(stats) = analyze_blocks(&bytes) else {
+            eprintln!("{imagename}: {name}: not in lzf format");
+            rc = Status::Error;
+            continue;
+        };
+
+        print_list_line(cfg, bytes.len() as u64, &stats, name);
+        listed += 1;
+        total_compressed += bytes.len() as u64;
+        totals.decoded_len += stats.decoded_len;
+        totals.blocks += stats.blocks;
+        totals.uncompressed_blocks += stats.uncompressed_blocks;
+    }
+
+    if listed > 1 {
+        print_list_line(cfg, total_compressed, &totals, "(totals)");
+    }
+    rc
+}
+
+/// In-memory inputs for `--selftest`.
+fn selftest_corpora() -> Vec<(&'static str, Vec<u8>)> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut random = |len: usize| -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    };
+    let noise = random(100_000);
+    let mut mixed = Vec::with_capacity(1 << 20);
+    while mixed.len() < 1 << 20 {
+        mixed.extend_from_slice(b"GET /index.html HTTP/1.1\r\nHost: example.org\r\n\r\n");
+        mixed.extend_from_slice(&[0; 300]);
+        mixed.extend_from_slice(&random(700));
+    }
+    mixed.truncate(1 << 20);
+    vec![
+        ("empty", Vec::new()),
+        ("tiny", b"lzf".to_vec()),
+        ("periodic", b"0123456789abcdef".repeat(8192)),
+        ("random", noise),
+        ("mixed", mixed),
+    ]
+}
+
+/// Compresses and decompresses `data` through the framed or streaming API,
+/// flipping a bit in the compressed form if `damage` is set.
+fn selftest_round_trip(
+    data: &[u8],
+    streaming: bool,
+    mode: CompressionMode,
+    damage: bool,
+) -> lzf_rust::Result<Vec<u8>> {
+    let mut compressed = if streaming {
+        let mut writer = LzfWriter::new_with_mode(Vec::new(), BLOCKSIZE, mode)?;
+        lzf_rust::copy(&mut &data[..], &mut writer)?;
+        writer.finish()?
+    } else {
+        encode_blocks_with_mode(data, BLOCKSIZE, mode)?
+    };
+    if damage {
+        let at = compressed.len() / 2;
+        compressed[at] ^= 0x10;
+    }
+    if streaming {
+        let mut decoded = Vec::new();
+        lzf_rust::copy(&mut LzfReader::new(compressed.as_slice()), &mut decoded)?;
+        Ok(decoded)
+    } else {
+        decode_blocks(&compressed)
+    }
+}
+
+/// Round-trips built-in corpora through the framed and streaming paths in
+/// both modes, without touching files. The undocumented
+/// `LZF_SELFTEST_DAMAGE` variable corrupts the periodic corpus, to exercise
+/// the failure report.
+fn run_selftest(imagename: &str, cfg: &Config) -> Status {
+    let damage = env::var_os("LZF_SELFTEST_DAMAGE").is_some();
+    let (mut checks, mut failed) = (0usize, 0usize);
+    for (name, data) in selftest_corpora() {
+        for streaming in [false, true] {
+            for mode in [CompressionMode::Normal, CompressionMode::Best] {
+                let path = if streaming { "streaming" } else { "framed" };
+                let mode_name = if mode == CompressionMode::Best { "best" } else { "normal" };
+                let check = format!("{name}, {path}, {mode_name}");
+                checks += 1;
+                match selftest_round_trip(&data, streaming, mode, damage && name == "periodic") {
+                    Ok(decoded) if decoded == data => {
+                        if cfg.verbose > 0 {
+                            eprintln!("{imagename}: selftest: {check}: ok");
+                        }
                     }
-                };
-                if n == 0 {
-                    break;
+                    Ok(_) => {
+                        failed += 1;
+                        eprintln!("{imagename}: selftest: {check}: output differs");
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        eprintln!("{imagename}: selftest: {check}: {e}");
+                    }
+                }
+            }
+        }
+    }
+    if failed == 0 {
+        println!("selftest: PASS ({checks} checks)");
+        Status::Ok
+    } else {
+        println!("selftest: FAIL ({failed} of {checks} checks failed)");
+        Status::Error
+    }
+}
+
+/// Length of the longest block header, enough to recognize a framed stream.
+const MAX_HEADER: u64 = 11;
+
+/// Size of the largest block: a checked header and a full payload.
+const MAX_FRAME: usize = MAX_HEADER as usize + 0xffff;
+
+/// Totals over the blocks reported by `print_info`.
+#[derive(Default)]
+struct InfoTotals {
+    blocks: u64,
+    compressed: u64,
+    decoded: u64,
+}
+
+fn print_info_line(offset: u64, kind: &str, compressed: u64, decoded: Option<u64>) {
+    match decoded {
+        Some(decoded) => println!(
+            "{offset:>12} {kind:<14} {compressed:>12} {decoded:>12} {:5.1}%",
+            saved_pct(compressed, decoded)
+        ),
+        None => println!("{offset:>12} {kind:<14} {compressed:>12} {:>12}", "-"),
+    }
+}
+
+/// Prints the offset, type and sizes of every block in `input`, then totals
+/// and whether the end-of-stream marker is present. At most two blocks are
+/// buffered at a time. Returns `false` if the layout is broken; the report
+/// then ends with the offset and nature of the problem.
+fn print_info(input: &mut dyn io::Read) -> io::Result<bool> {
+    let mut buf = Vec::with_capacity(2 * MAX_FRAME);
+    let mut base = 0u64;
+    let mut eof = false;
+    let mut totals = InfoTotals::default();
+    let mut problem = None;
+    let mut marker = false;
+    let mut trailing = 0u64;
+
+    println!("{:>12} {:<14} {:>12} {:>12}  ratio", "offset", "type", "compressed", "uncompressed");
+    loop {
+        while !eof && buf.len() < 2 * MAX_FRAME {
+            let start = buf.len();
+            buf.resize(2 * MAX_FRAME, 0);
+            match input.read(&mut buf[start..]) {
+                Ok(n) => {
+                    buf.truncate(start + n);
+                    eof = n == 0;
                 }
-                if io::Write::write_all(&mut out_lock, &buf[..n]).is_err() {
-                    eprintln!("{imagename}: write error");
-                    return 1;
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => buf.truncate(start),
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut frames = FrameIter::new(&buf);
+        let mut failure = None;
+        for frame in frames.by_ref() {
+            match frame {
+                Ok(frame) => {
+                    let kind = match frame.block_type {
+                        BlockType::Uncompressed => "uncompressed",
+                        BlockType::Compressed => "compressed",
+                        BlockType::Checked => "checked",
+                    };
+                    let (compressed, decoded) =
+                        (frame.compressed_len as u64, frame.uncompressed_len as u64);
+                    print_info_line(base + frame.offset as u64, kind, compressed, Some(decoded));
+                    totals.blocks += 1;
+                    totals.compressed += compressed;
+                    totals.decoded += decoded;
                 }
+                Err(e) => failure = Some(e),
             }
+        }
+        let pos = frames.position();
+        let rest = &buf[pos..];
 
-            0
+        let Some(err) = failure else {
+            if rest.is_empty() && !eof {
+                buf.drain(..pos);
+                base += pos as u64;
+                continue;
+            }
+            // Anything left starts with the end-of-stream marker.
+            if let Some((_, after)) = rest.split_first() {
+                marker = true;
+                trailing = after.len() as u64 + io::copy(input, &mut io::sink())?;
+            }
+            break;
+        };
+        // The block may just not be fully buffered yet.
+        if !eof && rest.len() < MAX_FRAME {
+            buf.drain(..pos);
+            base += pos as u64;
+            continue;
+        }
+        // Blocks of unknown type are skipped by their `ZV\0` style length.
+        if let lzf_rust::Error::UnknownBlockType(kind) = err {
+            let len = rest.get(3..5).map(|l| usize::from(u16::from_be_bytes([l[0], l[1]])));
+            if let Some(len) = len.filter(|len| rest.len() >= 5 + len) {
+                print_info_line(base + pos as u64, &format!("unknown ({kind})"), len as u64, None);
+                buf.drain(..pos + 5 + len);
+                base += (pos + 5 + len) as u64;
+                continue;
+            }
         }
+        let what = match err {
+            lzf_rust::Error::InvalidHeader if b"ZV".starts_with(&rest[..rest.len().min(2)]) => {
+                "truncated block header".to_string()
+            }
+            lzf_rust::Error::InvalidHeader => "not a block header".to_string(),
+            lzf_rust::Error::InvalidData => "truncated payload".to_string(),
+            lzf_rust::Error::UnknownBlockType(kind) => format!("truncated block of type {kind}"),
+            other => other.to_string(),
+        };
+        problem = Some((base + pos as u64, what));
+        break;
+    }
+
+    println!(
+        "{:>12} {:<14} {:>12} {:>12} {:5.1}%",
+        "total",
+        format!("{} blocks", totals.blocks),
+        totals.compressed,
+        totals.decoded,
+        saved_pct(totals.compressed, totals.decoded)
+    );
+    if let Some((offset, what)) = &problem {
+        println!("error at offset {offset}: {what}");
+    } else if marker {
+        println!("end marker: present, {trailing} bytes after it");
+    } else {
+        println!("end marker: absent");
     }
+    Ok(problem.is_none())
+}
+
+/// Shows the block layout of each input file, or of stdin.
+fn run_info(imagename: &str, cfg: &Config) -> Status {
+    let inputs: Vec<Option<&str>> = if cfg.files.is_empty() {
+        vec![None]
+    } else {
+        cfg.files.iter().map(|f| Some(f.as_str()).filter(|f| *f != "-")).collect()
+    };
+
+    if inputs.contains(&None) && !cfg.force && stdin_is_tty() {
+        eprintln!(
+            "{imagename}: compressed data not read from a terminal. Use -f to force decompression."
+        );
+        return Status::Warning;
+    }
+
+    let mut rc = Status::Ok;
+    for (i, input) in inputs.into_iter().enumerate() {
+        let name = input.unwrap_or("stdin");
+        if i > 0 {
+            println!();
+        }
+        println!("{name}:");
+        let res = match input {
+            Some(file) => fs::File::open(file).and_then(|mut f| print_info(&mut f)),
+            None => print_info(&mut io::stdin().lock()),
+        };
+        match res {
+            Ok(true) => {}
+            Ok(false) => rc = Status::Error,
+            Err(e) => {
+                eprintln!("{imagename}: {name}: {e}");
+                rc = Status::Error;
+            }
+        }
+    }
+    rc
 }
 
 fn main() {
@@ -423,14 +1495,50 @@ fn main() {
     let cfg = parse_args(&args);
     let imagename = program_name(args.first().map(String::as_str));
 
-    let mut rc = 0i32;
-    if cfg.files.is_empty() {
-        rc |= run_stdio(imagename, &cfg);
+    let rc = if cfg.selftest {
+        run_selftest(imagename, &cfg)
+    } else if cfg.info {
+        run_info(imagename, &cfg)
+    } else if cfg.list {
+        run_list(imagename, &cfg)
+    } else if cfg.files.is_empty() {
+        run_stdio(imagename, &cfg)
+    } else if cfg.to_stdout && cfg.mode == Mode::Compress && !cfg.force && stdout_is_tty() {
+        eprintln!(
+            "{imagename}: compressed data not written to a terminal. Use -f to force compression."
+        );
+        Status::Warning
     } else {
+        let mut rc = Status::Ok;
+        let mut failed = 0usize;
+        let mut totals = RunTotals::default();
+        let start = Instant::now();
         for f in &cfg.files {
-            rc |= run_file(imagename, &cfg, f);
+            let status = if f == "-" {
+                run_stdio(imagename, &cfg)
+            } else {
+                run_file(imagename, &cfg, f, &mut totals)
+            };
+            if status != Status::Ok {
+                failed += 1;
+            }
+            rc = rc.max(status);
         }
-    }
+        if cfg.files.len() > 1 {
+            warning!(cfg, "{imagename}: {} succeeded, {failed} failed", cfg.files.len() - failed);
+            if cfg.verbose > 0 && cfg.mode != Mode::Test {
+                eprintln!(
+                    "{imagename}: total: {} files, {failed} failed, {} bytes in, {} bytes out, {:5.1}%, {:.3}s",
+                    cfg.files.len(),
+                    totals.read,
+                    totals.written,
+                    mode_pct(cfg.mode, totals.read, totals.written),
+                    start.elapsed().as_secs_f64()
+                );
+            }
+        }
+        rc
+    };
 
-    std::process::exit(if rc == 0 { 0 } else { 1 });
+    std::process::exit(rc.code());
 }
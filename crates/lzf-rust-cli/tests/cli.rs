@@ -0,0 +1,1073 @@
+// SPDX-License-Identifier: BSD-2-Clause
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use lzf_rust::CompressionMode;
+
+/// Creates an empty scratch directory unique to `name`.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lzf-cli-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("create scratch dir");
+    dir
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .expect("read_dir")
+        .map(|e| e.expect("entry").file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn to_stdout_concatenates_compressed_files_and_keeps_inputs() {
+    let dir = scratch_dir("compress");
+    let first = b"first file first file first file".repeat(100);
+    let second = b"second".repeat(3000);
+    fs::write(dir.join("a"), &first).unwrap();
+    fs::write(dir.join("b"), &second).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-O", "-v", "a", "b"])
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let mut expected = first.clone();
+    expected.extend_from_slice(&second);
+    assert_eq!(lzf_rust::decode_blocks(&out.stdout).unwrap(), expected);
+    assert_eq!(file_names(&dir), ["a", "b"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.starts_with("a:") && stderr.contains("\nb:"), "{stderr}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn to_stdout_concatenates_decompressed_files_and_keeps_inputs() {
+    let dir = scratch_dir("decompress");
+    let first = b"one two three ".repeat(500);
+    let second = (0..20_000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+    fs::write(dir.join("a.lzf"), lzf_rust::encode_blocks(&first, 4096).unwrap()).unwrap();
+    // Any name is accepted, since no output file is derived from it.
+    fs::write(dir.join("b.bin"), lzf_rust::encode_blocks(&second, 4096).unwrap()).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-d", "--to-stdout", "a.lzf", "b.bin"])
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let mut expected = first.clone();
+    expected.extend_from_slice(&second);
+    assert_eq!(out.stdout, expected);
+    assert_eq!(file_names(&dir), ["a.lzf", "b.bin"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Runs the binary as `program` in `dir`, feeding `stdin` to it.
+fn run_with_stdin(program: &Path, dir: &Path, args: &[&str], stdin: &[u8]) -> Output {
+    use std::io::Write;
+
+    let mut child = Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run lzf");
+    let mut pipe = child.stdin.take().unwrap();
+    std::thread::scope(|scope| {
+        scope.spawn(move || pipe.write_all(stdin).expect("feed stdin"));
+        child.wait_with_output().expect("wait")
+    })
+}
+
+#[test]
+fn dash_operand_reads_stdin_in_sequence() {
+    let dir = scratch_dir("dash");
+    let (first, middle, last) =
+        (b"first ".repeat(500), b"middle ".repeat(700), b"last".repeat(900));
+    fs::write(dir.join("a"), &first).unwrap();
+    fs::write(dir.join("b"), &last).unwrap();
+    let lzf = Path::new(env!("CARGO_BIN_EXE_lzf"));
+
+    let out = run_with_stdin(lzf, &dir, &["-O", "a", "-", "b"], &middle);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(
+        lzf_rust::decode_blocks(&out.stdout).unwrap(),
+        [&first[..], &middle, &last].concat()
+    );
+    assert_eq!(file_names(&dir), ["a", "b"]);
+
+    // Without -O, files are still replaced while `-` goes to stdout.
+    let out = run_with_stdin(lzf, &dir, &["a", "-", "b"], &middle);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(lzf_rust::decode_blocks(&out.stdout).unwrap(), middle);
+    assert_eq!(file_names(&dir), ["a.lzf", "b.lzf"]);
+
+    let packed = lzf_rust::encode_blocks(&middle, 4096).unwrap();
+    let out = run_with_stdin(lzf, &dir, &["-dO", "b.lzf", "-", "a.lzf"], &packed);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(out.stdout, [&last[..], &middle, &first].concat());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(lzf, dir.join("lzcat")).unwrap();
+        let out = run_with_stdin(&dir.join("lzcat"), &dir, &["-", "a.lzf", "-"], &packed);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        // The second `-` finds stdin at its end, an empty stream.
+        assert_eq!(out.stdout, [&middle[..], &first].concat());
+        assert_eq!(file_names(&dir), ["a.lzf", "b.lzf", "lzcat"]);
+    }
+
+    let out = run_with_stdin(lzf, &dir, &["-o", "x.lzf", "-"], &middle);
+    assert_eq!(out.status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_mode_checks_every_file_without_writing() {
+    let dir = scratch_dir("test");
+    let data = b"integrity integrity integrity ".repeat(400);
+    let framed = lzf_rust::encode_blocks(&data, 4096).unwrap();
+    assert_eq!(framed[2], 1, "first block is compressed");
+    let mut corrupt = framed.clone();
+    // A back reference at the start of a block points before the output.
+    corrupt[7] = 0xff;
+    fs::write(dir.join("good.lzf"), &framed).unwrap();
+    fs::write(dir.join("short.lzf"), &framed[..framed.len() - 3]).unwrap();
+    fs::write(dir.join("bad.lzf"), &corrupt).unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = run(&["-t", "good.lzf"]);
+    assert!(out.status.success());
+    assert!(out.stdout.is_empty() && out.stderr.is_empty());
+
+    let out = run(&["--test", "-v", "short.lzf", "good.lzf", "bad.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 4, "{stderr}");
+    assert!(lines[0].ends_with("short.lzf: unexpected end of input"), "{stderr}");
+    assert_eq!(lines[1], "good.lzf: OK");
+    assert!(lines[2].ends_with("bad.lzf: invalid compressed data"), "{stderr}");
+    assert!(lines[3].ends_with(": 1 succeeded, 2 failed"), "{stderr}");
+    assert_eq!(file_names(&dir), ["bad.lzf", "good.lzf", "short.lzf"]);
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .arg("-t")
+        .stdin(fs::File::open(dir.join("good.lzf")).unwrap())
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .arg("-t")
+        .stdin(fs::File::open(dir.join("bad.lzf")).unwrap())
+        .output()
+        .expect("run lzf");
+    assert!(!out.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn force_compresses_from_a_fifo_and_keeps_it() {
+    let dir = scratch_dir("fifo");
+    let data = b"through a named pipe ".repeat(4000);
+    let status = Command::new("mkfifo").arg(dir.join("pipe")).status().expect("run mkfifo");
+    assert!(status.success());
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    // Without -f the FIFO is skipped unopened, so nothing blocks here.
+    let out = run(&["pipe"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("not a regular file"));
+
+    let writer = std::thread::spawn({
+        let (path, data) = (dir.join("pipe"), data.clone());
+        move || fs::write(path, data).unwrap()
+    });
+    let out = run(&["-f", "-v", "pipe"]);
+    writer.join().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stderr).starts_with("pipe:  "));
+    assert_eq!(file_names(&dir), ["pipe", "pipe.lzf"]);
+    let packed = fs::read(dir.join("pipe.lzf")).unwrap();
+    assert_eq!(lzf_rust::decode_blocks(&packed).unwrap(), data);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn force_reads_dev_stdin_and_follows_symlinks() {
+    use std::io::Write;
+
+    let dir = scratch_dir("devstdin");
+    let data = b"piped through /dev/stdin ".repeat(3000);
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-f", "-o", "piped.lzf", "/dev/stdin"])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run lzf");
+    child.stdin.take().unwrap().write_all(&data).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let packed = fs::read(dir.join("piped.lzf")).unwrap();
+    assert_eq!(lzf_rust::decode_blocks(&packed).unwrap(), data);
+
+    // A link to a regular file is read through; the link itself is replaced.
+    std::os::unix::fs::symlink("piped.lzf", dir.join("link.lzf")).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-d", "link.lzf"])
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["link", "piped.lzf"]);
+    assert_eq!(fs::read(dir.join("link")).unwrap(), data);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn failed_writes_keep_the_source_and_leave_no_temp_files() {
+    let dir = scratch_dir("atomic");
+    let data = dump_like(100_000);
+    fs::write(dir.join("a"), &data).unwrap();
+    fs::write(dir.join("b.lzf"), b"previous output").unwrap();
+    // Output past 512 bytes fails with EFBIG instead of raising SIGXFSZ.
+    let limited = |args: &[&str]| {
+        Command::new("sh")
+            .current_dir(&dir)
+            .arg("-c")
+            .arg("trap '' XFSZ; ulimit -f 1; exec \"$0\" \"$@\"")
+            .arg(env!("CARGO_BIN_EXE_lzf"))
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = limited(&["a"]);
+    assert_eq!(out.status.code(), Some(1), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["a", "b.lzf"]);
+    assert_eq!(fs::read(dir.join("a")).unwrap(), data);
+
+    let out = limited(&["-T", "2", "a"]);
+    assert_eq!(out.status.code(), Some(1), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("a.lzf: "));
+    assert_eq!(file_names(&dir), ["a", "b.lzf"]);
+
+    let out = limited(&["-f", "-o", "b.lzf", "a"]);
+    assert_eq!(out.status.code(), Some(1), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["a", "b.lzf"]);
+    assert_eq!(fs::read(dir.join("b.lzf")).unwrap(), b"previous output");
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-o", "b.lzf", "a"])
+        .output()
+        .expect("run lzf");
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("b.lzf: already exists"));
+
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-f", "-o", "b.lzf", "a"])
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["b.lzf"]);
+    assert_eq!(lzf_rust::decode_blocks(&fs::read(dir.join("b.lzf")).unwrap()).unwrap(), data);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn formats_are_recognized_by_content() {
+    let dir = scratch_dir("sniff");
+    let data = b"sniffed sniffed sniffed".repeat(40);
+    let packed = lzf_rust::encode_blocks(&data, 4096).unwrap();
+    fs::write(dir.join("blob"), &packed).unwrap();
+    fs::write(dir.join("fake.lzf"), b"this is not compressed at all").unwrap();
+    fs::write(dir.join("plain"), &data).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    // A framed file without the suffix decompresses to `.out`.
+    let out = run(&["-d", "blob"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read(dir.join("blob.out")).unwrap(), data);
+
+    let out = run(&["-d", "fake.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("fake.lzf: not in lzf format"), "{stderr}");
+    let out = run(&["-dO", "fake.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    assert_eq!(file_names(&dir), ["blob.out", "fake.lzf", "plain"]);
+
+    // Compressed data is not compressed again unless forced.
+    fs::write(dir.join("again"), &packed).unwrap();
+    let out = run(&["again", "plain"]);
+    assert_eq!(out.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("again: already lzf-compressed -- unchanged"), "{stderr}");
+    assert_eq!(file_names(&dir), ["again", "blob.out", "fake.lzf", "plain.lzf"]);
+    let out = run(&["-f", "again"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let twice = fs::read(dir.join("again.lzf")).unwrap();
+    assert_eq!(lzf_rust::decode_blocks(&twice).unwrap(), packed);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn raw_mode_round_trips_headerless_payloads() {
+    let dir = scratch_dir("raw");
+    let data = b"raw tokens, no framing. ".repeat(5000);
+    let lzf = Path::new(env!("CARGO_BIN_EXE_lzf"));
+    let run =
+        |args: &[&str]| Command::new(lzf).current_dir(&dir).args(args).output().expect("run lzf");
+
+    fs::write(dir.join("bare"), &data).unwrap();
+    fs::write(dir.join("prefixed"), &data).unwrap();
+    let out = run(&["--raw", "bare"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["--raw=prefixed", "-9", "prefixed"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    let bare = fs::read(dir.join("bare.lzf")).unwrap();
+    assert!(bare.len() < data.len() / 10);
+    assert_eq!(lzf_rust::decompress_to_vec(&bare).unwrap(), data);
+    let prefixed = fs::read(dir.join("prefixed.lzf")).unwrap();
+
+    // The snippet from the README.
+    let (len, tokens) = prefixed.split_at(4);
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    let mut decoded = vec![0u8; len];
+    assert_eq!(lzf_rust::decompress(tokens, &mut decoded).unwrap(), len);
+    assert_eq!(decoded, data);
+
+    let out = run(&["-d", "--raw", "bare.lzf"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("needs --size or --raw=prefixed"));
+    let out = run(&["-d", "--raw", "--size", &(data.len() - 1).to_string(), "bare.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    let size = data.len().to_string();
+    let out = run(&["-d", "--raw", &format!("--size={size}"), "bare.lzf"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["-d", "--raw=prefixed", "prefixed.lzf"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read(dir.join("bare")).unwrap(), data);
+    assert_eq!(fs::read(dir.join("prefixed")).unwrap(), data);
+
+    let packed = run_with_stdin(lzf, &dir, &["--raw=prefixed"], &data);
+    assert!(packed.status.success());
+    assert_eq!(packed.stdout, prefixed_normal(&data));
+    let out = run_with_stdin(lzf, &dir, &["-d", "--raw=prefixed"], &packed.stdout);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(out.stdout, data);
+
+    for args in
+        [&["--raw", "-b", "4096", "bare"][..], &["--raw", "-T2", "bare"], &["--size=5", "bare"]]
+    {
+        let out = run(args);
+        assert_eq!(out.status.code(), Some(2), "{args:?}");
+    }
+    assert_eq!(file_names(&dir), ["bare", "prefixed"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The `--raw=prefixed` encoding of `data` at the default level.
+fn prefixed_normal(data: &[u8]) -> Vec<u8> {
+    let mut tokens = vec![0u8; lzf_rust::max_compressed_size(data.len())];
+    let n = lzf_rust::compress(data, &mut tokens).unwrap();
+    let mut out = (data.len() as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(&tokens[..n]);
+    out
+}
+
+#[test]
+fn quiet_hides_warnings_and_verbose_adds_detail() {
+    let dir = scratch_dir("quiet");
+    let data = b"quiet please ".repeat(10_000);
+    fs::write(dir.join("a"), &data).unwrap();
+    fs::write(dir.join("b.lzf"), lzf_rust::encode_blocks(&data, 65535).unwrap()).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    // The skipped input keeps its exit status, but only errors are printed.
+    let out = run(&["b.lzf"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("already lzf-compressed"));
+    let out = run(&["-q", "b.lzf"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(out.stderr.is_empty(), "{}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["--quiet", "b.lzf", "missing"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+    assert!(stderr.contains("missing: "), "{stderr}");
+    // The last of -q and -v wins.
+    let out = run(&["-qv", "b.lzf"]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("already lzf-compressed"));
+
+    let out = run(&["-vv", "-9", "-b", "16k", "a"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 2, "{stderr}");
+    assert_eq!(lines[0], "a: 8 blocks (0 stored), best mode");
+    assert!(lines[1].starts_with("a: "), "{stderr}");
+
+    let out = run(&["-d", "-vvv", "a.lzf"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 10, "{stderr}");
+    assert_eq!(lines[0], "a.lzf: 8 blocks (0 stored)");
+    assert!(lines[1].starts_with("a.lzf: block 0 at 0: Compressed, 16384 bytes in "), "{stderr}");
+    assert!(lines[8].starts_with("a.lzf: block 7 at "), "{stderr}");
+    assert!(lines[9].contains("-- replaced with a"), "{stderr}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn version_and_argument_errors() {
+    let run = |args: &[&str]| Command::new(env!("CARGO_BIN_EXE_lzf")).args(args).output().unwrap();
+
+    for flag in ["-V", "--version"] {
+        let out = run(&[flag, "ignored"]);
+        assert_eq!(out.status.code(), Some(0));
+        let expected = format!("lzf-rust-cli {}\n", env!("CARGO_PKG_VERSION"));
+        assert_eq!(String::from_utf8_lossy(&out.stdout), expected);
+    }
+    let out = run(&["-h"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("usage: lzf"));
+
+    for (args, message) in [
+        (&["--foo"][..], "unrecognized option '--foo'"),
+        (&["-v", "--foo=1", "file"], "unrecognized option '--foo'"),
+        (&["--blocksize"], "option '--blocksize' requires an argument"),
+        (&["-vb"], "option '-b' requires an argument"),
+        (&["-x"], "invalid option -- 'x'"),
+        (&["-vfz", "file"], "invalid option -- 'z'"),
+        (&["--force=yes"], "option '--force' doesn't allow an argument"),
+        (&["-T", "many"], "invalid thread count 'many'"),
+        (&["--raw=zip"], "invalid argument 'zip' for '--raw' -- expected 'prefixed'"),
+        (&["-b70000"], "invalid block size '70000' -- expected 1 to 65535"),
+    ] {
+        let out = run(args);
+        assert_eq!(out.status.code(), Some(2), "{args:?}");
+        assert!(out.stdout.is_empty());
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let expected = format!("lzf: {message}\nTry 'lzf --help' for more information.\n");
+        assert_eq!(stderr, expected, "{args:?}");
+    }
+}
+
+/// A stored block of "hello", a compressed block of 20 `a`s, a block of the
+/// unknown type 7, another stored block, and the end-of-stream marker.
+const LAYOUT: &[u8] = b"ZV\x00\x00\x05hello\
+    ZV\x01\x00\x05\x00\x14\x00a\xe0\x0a\x00\
+    ZV\x07\x00\x03xyz\
+    ZV\x00\x00\x01!\
+    \x00";
+
+#[test]
+fn info_reports_the_block_layout() {
+    let dir = scratch_dir("info");
+    let decoded = lzf_rust::decode_blocks_lenient(LAYOUT).unwrap();
+    assert_eq!(decoded, [&b"hello"[..], &[b'a'; 20], b"!"].concat());
+    fs::write(dir.join("layout.lzf"), LAYOUT).unwrap();
+    fs::write(dir.join("cut.lzf"), &LAYOUT[..20]).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = run(&["--info", "layout.lzf", "cut.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "\
+layout.lzf:
+      offset type             compressed uncompressed  ratio
+           0 uncompressed              5            5   0.0%
+          10 compressed                5           20  75.0%
+          22 unknown (7)               3            -
+          30 uncompressed              1            1   0.0%
+       total 3 blocks                 11           26  57.7%
+end marker: present, 0 bytes after it
+
+cut.lzf:
+      offset type             compressed uncompressed  ratio
+           0 uncompressed              5            5   0.0%
+       total 1 blocks                  5            5   0.0%
+error at offset 10: truncated payload
+"
+    );
+    assert_eq!(file_names(&dir), ["cut.lzf", "layout.lzf"]);
+
+    // Blocks straddling the read buffer are reported whole.
+    let framed = lzf_rust::encode_blocks(&dump_like(400_000), 65535).unwrap();
+    let out = run_with_stdin(Path::new(env!("CARGO_BIN_EXE_lzf")), &dir, &["--info"], &framed);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    let frames: Vec<_> = lzf_rust::FrameIter::new(&framed).map(Result::unwrap).collect();
+    assert_eq!(lines.len(), 2 + frames.len() + 2, "{stdout}");
+    for (line, frame) in lines[2..].iter().zip(&frames) {
+        let sizes = format!("{:>12} {:>12}", frame.compressed_len, frame.uncompressed_len);
+        assert!(line.starts_with(&format!("{:>12} ", frame.offset)), "{line}");
+        assert!(line.contains(&sizes), "{line}");
+    }
+    assert_eq!(lines[lines.len() - 1], "end marker: absent");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn exit_codes_follow_gzip_conventions() {
+    let dir = scratch_dir("exit");
+    let data = b"exit codes exit codes".repeat(60);
+    let mut corrupt = lzf_rust::encode_blocks(&data, 4096).unwrap();
+    corrupt[7] = 0xff;
+    fs::write(dir.join("a"), &data).unwrap();
+    fs::write(dir.join("bad.lzf"), &corrupt).unwrap();
+    fs::write(dir.join("plain"), &data).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = run(&["a"]);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(out.stderr.is_empty());
+    assert_eq!(run(&["missing"]).status.code(), Some(1));
+    assert_eq!(run(&["-d", "bad.lzf"]).status.code(), Some(1));
+    assert_eq!(run(&["--no-such-flag", "a.lzf"]).status.code(), Some(2));
+    assert_eq!(run(&["-T", "many", "a.lzf"]).status.code(), Some(2));
+
+    // A skipped input is a warning; an error elsewhere outranks it.
+    assert_eq!(run(&["-d", "plain"]).status.code(), Some(2));
+    let out = run(&["-d", "plain", "missing", "a.lzf"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.ends_with(": 1 succeeded, 2 failed\n"), "{stderr}");
+    assert_eq!(file_names(&dir), ["a", "bad.lzf", "plain"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn suffix_option_names_compressed_files() {
+    let dir = scratch_dir("suffix");
+    let data = b"suffix suffix suffix".repeat(50);
+    fs::write(dir.join("a"), &data).unwrap();
+    fs::write(dir.join("b"), &data).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    assert!(run(&["-S", ".zv", "a"]).status.success());
+    assert!(run(&["b"]).status.success());
+    assert_eq!(file_names(&dir), ["a.zv", "b.lzf"]);
+    assert_eq!(lzf_rust::decode_blocks(&fs::read(dir.join("a.zv")).unwrap()).unwrap(), data);
+
+    // Both the configured and the default suffix are stripped.
+    let out = run(&["-d", "--suffix=.zv", "a.zv", "b.lzf"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["a", "b"]);
+    assert_eq!(fs::read(dir.join("a")).unwrap(), data);
+
+    let out = run(&["-S", "", "a"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("empty suffix"));
+    assert_eq!(file_names(&dir), ["a", "b"]);
+
+    let out = run(&["-d", "-S.zv", "a"]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("a: unknown suffix -- expected .zv or .lzf"), "{stderr}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn output_option_names_the_written_file() {
+    let dir = scratch_dir("output");
+    let data = b"output output output".repeat(80);
+    fs::write(dir.join("a"), &data).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = run(&["-o", "out/packed", "a"]);
+    assert!(!out.status.success());
+    assert_eq!(file_names(&dir), ["a"]);
+
+    let out = run(&["--parents", "-o", "out/packed", "a"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["out"]);
+    let packed = fs::read(dir.join("out/packed")).unwrap();
+    assert_eq!(lzf_rust::decode_blocks(&packed).unwrap(), data);
+
+    fs::write(dir.join("a"), b"in the way").unwrap();
+    let out = run(&["-d", "-oa", "out/packed"]);
+    assert!(!out.status.success());
+    assert_eq!(fs::read(dir.join("a")).unwrap(), b"in the way");
+    let out = run(&["-d", "-f", "--output=a", "out/packed"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read(dir.join("a")).unwrap(), data);
+    assert_eq!(file_names(&dir.join("out")), Vec::<String>::new());
+
+    let out = run(&["-o", "-", "a"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(lzf_rust::decode_blocks(&out.stdout).unwrap(), data);
+    assert_eq!(file_names(&dir), ["a", "out"]);
+
+    fs::write(dir.join("b"), &data).unwrap();
+    let out = run(&["-o", "both", "a", "b"]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("-o needs exactly one input file"), "{stderr}");
+    assert_eq!(file_names(&dir), ["a", "b", "out"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn list_mode_reports_sizes_from_block_headers() {
+    let dir = scratch_dir("list");
+    let text = lzf_rust::encode_blocks(&[b'a'; 10_000], 4096).unwrap();
+    let mut state = 1u32;
+    let noise: Vec<u8> = (0..5000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect();
+    let noise = lzf_rust::encode_blocks(&noise, 4096).unwrap();
+    fs::write(dir.join("text.lzf"), &text).unwrap();
+    fs::write(dir.join("noise.lzf"), &noise).unwrap();
+    fs::write(dir.join("plain.txt"), b"not compressed").unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+
+    let out = run(&["-l", "text.lzf"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let lines: Vec<Vec<&str>> = stdout.lines().map(|l| l.split_whitespace().collect()).collect();
+    assert_eq!(lines[0], ["compressed", "uncompressed", "ratio", "name"]);
+    let pct = 100.0 - text.len() as f64 / 100.0;
+    assert_eq!(lines[1], [&text.len().to_string(), "10000", &format!("{pct:.1}%"), "text.lzf"]);
+    assert_eq!(lines.len(), 2, "no totals for a single file");
+
+    let out = run(&["--list", "-v", "text.lzf", "plain.txt", "noise.lzf"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("plain.txt: not in lzf format"));
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let lines: Vec<Vec<&str>> = stdout.lines().map(|l| l.split_whitespace().collect()).collect();
+    assert_eq!(lines[0], ["compressed", "uncompressed", "ratio", "blocks", "stored", "name"]);
+    assert_eq!(lines[1][3..], ["3", "0", "text.lzf"]);
+    assert_eq!(lines[2][1..2], ["5000"]);
+    assert_eq!(lines[2][3..], ["2", "2", "noise.lzf"]);
+    let total = (text.len() + noise.len()).to_string();
+    assert_eq!(lines[3][..2], [total.as_str(), "15000"]);
+    assert_eq!(lines[3][3..], ["5", "2", "(totals)"]);
+    assert_eq!(file_names(&dir), ["noise.lzf", "plain.txt", "text.lzf"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Semi-compressible input of `len` bytes.
+fn dump_like(len: usize) -> Vec<u8> {
+    let mut state = 7u64;
+    (0..len)
+        .map(|i| {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            if i % 512 < 384 { (i / 512) as u8 } else { (state >> 56) as u8 }
+        })
+        .collect()
+}
+
+/// Compresses `input` over stdin with the given extra arguments.
+fn compress_stdin(args: &[&str], input: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run lzf");
+    let mut stdin = child.stdin.take().unwrap();
+    std::thread::scope(|scope| {
+        scope.spawn(move || stdin.write_all(input).expect("feed stdin"));
+        let out = child.wait_with_output().expect("wait");
+        assert!(out.status.success());
+        out.stdout
+    })
+}
+
+#[test]
+fn block_sizes_accept_radix_prefixes_and_suffixes() {
+    let dir = scratch_dir("blocksize");
+    let input = dump_like(150_000);
+    fs::write(dir.join("dump"), &input).unwrap();
+    let run = |env: Option<&str>, args: &[&str]| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_lzf"));
+        cmd.current_dir(&dir).env_remove("LZF_BLOCKSIZE").args(args).args(["-O", "dump"]);
+        if let Some(value) = env {
+            cmd.env("LZF_BLOCKSIZE", value);
+        }
+        cmd.output().expect("run lzf")
+    };
+
+    for (value, blocksize) in [
+        ("1000", 1000),
+        ("0x400", 1024),
+        ("02000", 1024),
+        ("1k", 1024),
+        ("0x3fK", 63 * 1024),
+        ("65535", 65535),
+        (" 7 ", 7),
+    ] {
+        let expected = lzf_rust::encode_blocks(&input, blocksize).unwrap();
+        let out = run(None, &["-b", value]);
+        assert!(out.status.success(), "{value}: {}", String::from_utf8_lossy(&out.stderr));
+        assert!(out.stdout == expected, "-b {value}");
+        let out = run(Some(value), &[]);
+        assert!(out.status.success() && out.stderr.is_empty(), "LZF_BLOCKSIZE={value}");
+        assert!(out.stdout == expected, "LZF_BLOCKSIZE={value}");
+    }
+
+    let default = lzf_rust::encode_blocks(&input, 65535).unwrap();
+    for value in ["0", "0k", "65536", "64k", "128k", "1m", "08", "0x", "k", "12q", "-5", ""] {
+        let out = run(None, &[&format!("--blocksize={value}")]);
+        assert_eq!(out.status.code(), Some(2), "-b {value}");
+        assert!(out.stdout.is_empty());
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let message = format!("invalid block size '{value}' -- expected 1 to 65535");
+        assert!(stderr.contains(&message), "{stderr}");
+
+        let out = run(Some(value), &[]);
+        assert!(out.status.success(), "LZF_BLOCKSIZE={value}");
+        assert!(out.stdout == default, "LZF_BLOCKSIZE={value}");
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(stderr.contains(&format!("ignoring invalid LZF_BLOCKSIZE '{value}'")), "{stderr}");
+    }
+
+    // The flag overrides the variable.
+    let out = run(Some("0x400"), &["-b4k"]);
+    assert!(out.stdout == lzf_rust::encode_blocks(&input, 4096).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn level_flags_select_the_compression_mode() {
+    let input: Vec<u8> = (0..8_000u32)
+        .flat_map(|i| format!("row {i}: {} {}\n", i * 7 % 1000, i % 13).into_bytes())
+        .collect();
+    let normal = lzf_rust::encode_blocks_with_mode(&input, 65535, CompressionMode::Normal).unwrap();
+    let best = lzf_rust::encode_blocks_with_mode(&input, 65535, CompressionMode::Best).unwrap();
+    assert!(best.len() < normal.len());
+
+    // The last level given wins, in combined or separate flags.
+    for (args, expected) in [
+        (&["-1"][..], &normal),
+        (&["-6"], &normal),
+        (&["--fast"], &normal),
+        (&["--best"], &best),
+        (&["-19"], &best),
+        (&["-91"], &normal),
+        (&["-9", "--fast"], &normal),
+        (&["--fast", "-f9"], &best),
+    ] {
+        assert!(compress_stdin(args, &input) == *expected, "{args:?}");
+    }
+
+    let dir = scratch_dir("level");
+    fs::write(dir.join("dump"), &input).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+        .current_dir(&dir)
+        .args(["-v9f", "dump"])
+        .output()
+        .expect("run lzf");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(fs::read(dir.join("dump.lzf")).unwrap() == best);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("% (level 9) -- replaced with dump.lzf"), "{stderr}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn threaded_compression_matches_single_threaded_output() {
+    let input = dump_like(3_000_000 + 123);
+    for (blocksize, mode) in [(65535, CompressionMode::Normal), (4096, CompressionMode::Best)] {
+        let expected = lzf_rust::encode_blocks_with_mode(&input, blocksize, mode).unwrap();
+        let b = blocksize.to_string();
+        let mut args = vec!["-b", &b, "-T", "4"];
+        if mode == CompressionMode::Best {
+            args.push("-9");
+        }
+        assert!(compress_stdin(&args, &input) == expected, "stdin, blocksize {blocksize}");
+
+        let dir = scratch_dir(&format!("threads-{blocksize}"));
+        fs::write(dir.join("dump"), &input).unwrap();
+        args.extend(["--threads=0", "dump"]);
+        let out = Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(&args)
+            .output()
+            .expect("run lzf");
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert!(fs::read(dir.join("dump.lzf")).unwrap() == expected, "file, blocksize {blocksize}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn threaded_compression_of_a_large_file_streams_it() {
+    let dir = scratch_dir("large");
+    let input = dump_like(64 << 20);
+    fs::write(dir.join("big"), &input).unwrap();
+    let single = compress_stdin(&["-T", "1"], &input);
+
+    // Holding the input or the output in memory would exceed 32 MiB of data.
+    let capped = |args: &[&str]| {
+        Command::new("sh")
+            .current_dir(&dir)
+            .arg("-c")
+            .arg("ulimit -d 32768; exec \"$0\" \"$@\"")
+            .arg(env!("CARGO_BIN_EXE_lzf"))
+            .args(args)
+            .output()
+            .expect("run lzf")
+    };
+    let out = capped(&["-T", "4", "-O", "big"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(out.stdout == single);
+
+    let out = capped(&["-T", "4", "-v", "big"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(file_names(&dir), ["big.lzf"]);
+    assert!(fs::read(dir.join("big.lzf")).unwrap() == single);
+    let pct = 100.0 - (single.len() as f64 / (input.len() as f64 / 100.0));
+    let expected = format!("big:  {pct:5.1}% (level 1) -- replaced with big.lzf\n");
+    assert_eq!(String::from_utf8_lossy(&out.stderr), expected);
+
+    // The whole-buffer path does run out of memory under the same cap.
+    fs::rename(dir.join("big.lzf"), dir.join("copy.lzf")).unwrap();
+    assert!(!capped(&["-d", "copy.lzf"]).status.success());
+    assert!(lzf_rust::decode_blocks(&single).unwrap() == input);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn selftest_reports_pass_and_fail() {
+    let dir = scratch_dir("selftest");
+    let run = |damage: bool| {
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_lzf"));
+        cmd.arg("--selftest").current_dir(&dir).env_remove("LZF_SELFTEST_DAMAGE");
+        if damage {
+            cmd.env("LZF_SELFTEST_DAMAGE", "1");
+        }
+        cmd.output().unwrap()
+    };
+
+    let out = run(false);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "selftest: PASS (20 checks)\n");
+    assert!(out.stderr.is_empty());
+
+    let out = run(true);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "selftest: FAIL (4 of 20 checks failed)\n");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert_eq!(stderr.lines().count(), 4);
+    assert!(stderr.lines().all(|line| line.starts_with("lzf: selftest: periodic, ")));
+    assert!(file_names(&dir).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn closed_stdout_pipe_ends_the_run_quietly() {
+    let dir = scratch_dir("broken-pipe");
+    let data = dump_like(8 << 20);
+    fs::write(dir.join("a"), &data).unwrap();
+    fs::write(dir.join("a.lzf"), lzf_rust::encode_blocks(&data, 65535).unwrap()).unwrap();
+    fs::write(dir.join("a.lzf.raw"), compress_stdin(&["--raw=prefixed"], &data)).unwrap();
+    let lzf = Path::new(env!("CARGO_BIN_EXE_lzf"));
+    std::os::unix::fs::symlink(lzf, dir.join("lzcat")).unwrap();
+
+    for (program, args, stdin) in [
+        ("lzcat", &["a.lzf", "a.lzf"][..], None),
+        ("lzcat", &[], Some("a.lzf")),
+        ("lzcat", &["--raw=prefixed"], Some("a.lzf.raw")),
+        ("lzf", &["-O", "a"], None),
+        ("lzf", &[], Some("a")),
+    ] {
+        let stdin = match stdin {
+            Some(name) => Stdio::from(fs::File::open(dir.join(name)).unwrap()),
+            None => Stdio::null(),
+        };
+        let program = if program == "lzf" { lzf.to_path_buf() } else { dir.join(program) };
+        let mut child = Command::new(&program)
+            .current_dir(&dir)
+            .args(args)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdout.take());
+        let out = child.wait_with_output().unwrap();
+        assert_eq!(out.status.code(), Some(0), "{} {args:?}", program.display());
+        assert_eq!(String::from_utf8_lossy(&out.stderr), "", "{} {args:?}", program.display());
+    }
+    assert_eq!(file_names(&dir), ["a", "a.lzf", "a.lzf.raw", "lzcat"]);
+}
+
+#[test]
+fn verbose_runs_over_several_files_print_totals() {
+    let dir = scratch_dir("totals");
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_lzf")).current_dir(&dir).args(args).output().unwrap()
+    };
+    let inputs = [
+        (
+            "rows",
+            (0..4000).map(|i| format!("row {i}: {}\n", i % 7)).collect::<String>().into_bytes(),
+        ),
+        ("dump", dump_like(70_000)),
+        ("tiny", b"x".to_vec()),
+    ];
+    for (name, data) in &inputs {
+        fs::write(dir.join(name), data).unwrap();
+    }
+    let size = |name: &str| fs::metadata(dir.join(name)).unwrap().len();
+    let pct = |compressed: u64, decoded: u64| {
+        format!("{:5.1}%", 100.0 - (compressed as f64 / (decoded as f64 / 100.0)))
+    };
+
+    let out = run(&["-v", "rows", "dump", "missing", "tiny"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.len(), 6, "{stderr}");
+    let (mut read, mut written) = (0, 0);
+    for (name, data) in &inputs {
+        let (len, packed) = (data.len() as u64, size(&format!("{name}.lzf")));
+        let line = format!("{name}:  {} (level 1) -- replaced with {name}.lzf", pct(packed, len));
+        assert!(lines.contains(&line.as_str()), "{line}\n{stderr}");
+        (read, written) = (read + len, written + packed);
+    }
+    assert_eq!(lines[4], "lzf: 3 succeeded, 1 failed");
+    let expected = format!(
+        "lzf: total: 4 files, 1 failed, {read} bytes in, {written} bytes out, {}, ",
+        pct(written, read)
+    );
+    assert!(lines[5].starts_with(&expected) && lines[5].ends_with('s'), "{}", lines[5]);
+
+    let out = run(&["-dv", "rows.lzf", "dump.lzf", "tiny.lzf"]);
+    assert_eq!(out.status.code(), Some(0));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let expected = format!(
+        "lzf: total: 3 files, 0 failed, {written} bytes in, {read} bytes out, {}, ",
+        pct(written, read)
+    );
+    assert!(stderr.lines().last().unwrap().starts_with(&expected), "{stderr}");
+
+    // Without -v, or with a single file, there is no totals line.
+    let out = run(&["rows", "dump"]);
+    assert!(!String::from_utf8_lossy(&out.stderr).contains("total:"));
+    let out = run(&["-dv", "rows.lzf"]);
+    assert_eq!(String::from_utf8_lossy(&out.stderr).lines().count(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn stdio_failures_name_the_side_that_failed() {
+    let dir = scratch_dir("stdio-failures");
+    let data = dump_like(200_000);
+    fs::write(dir.join("a.lzf"), lzf_rust::encode_blocks(&data, 65535).unwrap()).unwrap();
+    fs::write(dir.join("a"), &data).unwrap();
+    let run = |args: &[&str], stdin: &Path, stdout: Option<&Path>| {
+        let stdout = match stdout {
+            Some(path) => Stdio::from(fs::OpenOptions::new().write(true).open(path).unwrap()),
+            None => Stdio::piped(),
+        };
+        Command::new(env!("CARGO_BIN_EXE_lzf"))
+            .current_dir(&dir)
+            .args(args)
+            .stdin(fs::File::open(stdin).unwrap())
+            .stdout(stdout)
+            .output()
+            .unwrap()
+    };
+
+    for args in [&["-d"][..], &["-f"], &["-f", "-T", "2"]] {
+        let out = run(args, &dir, None);
+        assert_eq!(out.status.code(), Some(1), "{args:?}");
+        assert_eq!(String::from_utf8_lossy(&out.stderr), "lzf: stdin: is a directory\n");
+    }
+    let full = Path::new("/dev/full");
+    if full.exists() {
+        for (args, input) in [(&["-d"][..], "a.lzf"), (&["-f"], "a"), (&["-f", "-T", "2"], "a")] {
+            let out = run(args, &dir.join(input), Some(full));
+            assert_eq!(out.status.code(), Some(1), "{args:?}");
+            assert_eq!(String::from_utf8_lossy(&out.stderr), "lzf: write error\n");
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
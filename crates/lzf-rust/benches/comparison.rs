@@ -1,11 +1,14 @@
 // SPDX-License-Identifier: ISC
 use divan::{
-    Bencher, black_box,
+    AllocProfiler, Bencher, black_box,
     counter::{BytesCount, ItemsCount},
     main,
 };
 use lzf_rust::{compress, decompress, max_compressed_size};
 
+#[global_allocator]
+static ALLOC: AllocProfiler = AllocProfiler::system();
+
 const SIZES: [usize; 3] = [1024, 8 * 1024, 64 * 1024];
 
 fn gen_input(size: usize) -> Vec<u8> {
@@ -52,6 +55,25 @@ mod lzf {
             });
     }
 
+    // All-zero payloads decode almost entirely through distance-1
+    // back-references.
+    #[divan::bench(args = SIZES)]
+    fn decompress_rust_zeros(bencher: Bencher, size: usize) {
+        let input = vec![0u8; size];
+        let mut compressed = vec![0u8; max_compressed_size(input.len())];
+        let compressed_len = compress(&input, &mut compressed).expect("compress baseline");
+        compressed.truncate(compressed_len);
+
+        bencher
+            .counter(BytesCount::new(input.len()))
+            .counter(ItemsCount::new(1u64))
+            .with_inputs(|| vec![0u8; input.len()])
+            .bench_refs(|out| {
+                let written = decompress(&compressed, out).expect("decompress");
+                black_box(written);
+            });
+    }
+
     #[divan::bench(args = SIZES)]
     fn decompress_rust_from_rust_buf(bencher: Bencher, size: usize) {
         let input = gen_input(size);
@@ -69,3 +91,176 @@ mod lzf {
             });
     }
 }
+
+#[divan::bench_group]
+mod framed {
+    use super::*;
+    use lzf_rust::encode_blocks;
+
+    const FRAMED_LEN: usize = 64 << 20;
+
+    #[divan::bench(sample_count = 10, sample_size = 1)]
+    fn encode_blocks_4k(bencher: Bencher) {
+        let input = gen_input(FRAMED_LEN);
+
+        bencher.counter(BytesCount::new(input.len())).bench(|| {
+            black_box(encode_blocks(&input, 4096).expect("encode"));
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    #[divan::bench(sample_count = 10, sample_size = 1, args = [1, 2, 4, 8])]
+    fn encode_blocks_parallel_4k(bencher: Bencher, threads: usize) {
+        let input = gen_input(FRAMED_LEN);
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("pool");
+
+        bencher.counter(BytesCount::new(input.len())).bench(|| {
+            pool.install(|| {
+                black_box(
+                    lzf_rust::encode_blocks_parallel(
+                        &input,
+                        4096,
+                        lzf_rust::CompressionMode::Normal,
+                    )
+                    .expect("encode"),
+                );
+            });
+        });
+    }
+
+    /// Already-compressed input, as in JPEG or zip payloads: stored blocks
+    /// either way, with or without the incompressibility probe.
+    #[divan::bench(sample_count = 10, sample_size = 1, args = [false, true])]
+    fn encode_blocks_incompressible_4k(bencher: Bencher, skip_incompressible: bool) {
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        let input: Vec<u8> = (0..FRAMED_LEN)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+        let options = lzf_rust::EncodeOptions::new().skip_incompressible(skip_incompressible);
+
+        bencher.counter(BytesCount::new(input.len())).bench(|| {
+            black_box(lzf_rust::encode_blocks_with_options(&input, 4096, options).expect("encode"));
+        });
+    }
+
+    /// Framing without compression; should run at about memcpy speed.
+    #[divan::bench(sample_count = 10, sample_size = 1)]
+    fn encode_blocks_uncompressed_4k(bencher: Bencher) {
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        let input: Vec<u8> = (0..FRAMED_LEN)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+        let options = lzf_rust::EncodeOptions::new().compress(false);
+
+        bencher.counter(BytesCount::new(input.len())).bench(|| {
+            black_box(lzf_rust::encode_blocks_with_options(&input, 4096, options).expect("encode"));
+        });
+    }
+
+    /// Baseline for `encode_blocks_uncompressed_4k`: a plain copy of the input.
+    #[divan::bench(sample_count = 10, sample_size = 1)]
+    fn memcpy(bencher: Bencher) {
+        let input = gen_input(FRAMED_LEN);
+
+        bencher.counter(BytesCount::new(input.len())).bench(|| {
+            black_box(input.clone());
+        });
+    }
+
+    #[divan::bench(sample_count = 10, sample_size = 1)]
+    fn decode_blocks_4k(bencher: Bencher) {
+        let framed = encode_blocks(&gen_input(FRAMED_LEN), 4096).expect("encode");
+
+        bencher.counter(BytesCount::new(FRAMED_LEN)).bench(|| {
+            black_box(lzf_rust::decode_blocks(&framed).expect("decode"));
+        });
+    }
+
+    #[divan::bench(sample_count = 10, sample_size = 1)]
+    fn decode_blocks_64k(bencher: Bencher) {
+        let framed = encode_blocks(&gen_input(FRAMED_LEN), 65535).expect("encode");
+
+        bencher.counter(BytesCount::new(FRAMED_LEN)).bench(|| {
+            black_box(lzf_rust::decode_blocks(&framed).expect("decode"));
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    #[divan::bench(sample_count = 10, sample_size = 1, args = [1, 2, 4, 8])]
+    fn decode_blocks_parallel_4k(bencher: Bencher, threads: usize) {
+        let framed = encode_blocks(&gen_input(FRAMED_LEN), 4096).expect("encode");
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("pool");
+
+        bencher.counter(BytesCount::new(FRAMED_LEN)).bench(|| {
+            pool.install(|| {
+                black_box(lzf_rust::decode_blocks_parallel(&framed).expect("decode"));
+            });
+        });
+    }
+}
+
+#[divan::bench_group]
+mod stream {
+    use super::*;
+    use lzf_rust::{LzfReader, Read, encode_blocks};
+
+    const STREAM_LEN: usize = 1 << 20;
+    const READ_BUF: usize = 256 * 1024;
+
+    // The bytes counter reports the decoded bytes that pass through the reader's
+    // internal block buffer, i.e. the extra memory traffic of each path.
+    fn copied_bytes(framed: &[u8], direct: bool) -> usize {
+        let mut reader = LzfReader::new(framed);
+        let mut out = vec![0u8; READ_BUF];
+        for _ in 0..STREAM_LEN / READ_BUF {
+            if direct {
+                reader.read_into_exact(&mut out).expect("read_into_exact");
+            } else {
+                reader.read_exact(&mut out).expect("read_exact");
+            }
+        }
+        STREAM_LEN - reader.zero_copy_bytes() as usize
+    }
+
+    #[divan::bench(args = [false, true])]
+    fn read_stream(bencher: Bencher, direct: bool) {
+        let framed = encode_blocks(&gen_input(STREAM_LEN), 64 * 1024 - 1).expect("encode");
+
+        bencher.counter(BytesCount::new(copied_bytes(&framed, direct))).bench(|| {
+            let mut reader = LzfReader::new(framed.as_slice());
+            let mut out = vec![0u8; READ_BUF];
+            for _ in 0..STREAM_LEN / READ_BUF {
+                if direct {
+                    reader.read_into_exact(&mut out).expect("read_into_exact");
+                } else {
+                    reader.read_exact(&mut out).expect("read_exact");
+                }
+            }
+            black_box(out);
+        });
+    }
+
+    // Plain `read` calls: buffers smaller than a block go through the block
+    // buffer, larger ones receive whole blocks directly.
+    #[divan::bench(args = [16 * 1024, 1 << 20])]
+    fn read_buffer_len(bencher: Bencher, buf_len: usize) {
+        let framed = encode_blocks(&gen_input(STREAM_LEN), 64 * 1024 - 1).expect("encode");
+
+        bencher.counter(BytesCount::new(STREAM_LEN)).bench(|| {
+            let mut reader = LzfReader::new(framed.as_slice());
+            let mut out = vec![0u8; buf_len];
+            while reader.read(&mut out).expect("read") != 0 {}
+            black_box(out);
+        });
+    }
+}
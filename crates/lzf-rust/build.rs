@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: ISC
+//! Builds the liblzf C reference decoder for differential tests.
+//!
+//! Only active with the testing-only `liblzf-differential` feature. The
+//! decoder is liblzf 3.6's `lzf_d.c`, vendored with its headers under
+//! `tests/liblzf`.
+
+fn main() {
+    #[cfg(feature = "liblzf-differential")]
+    build_liblzf();
+}
+
+#[cfg(feature = "liblzf-differential")]
+fn build_liblzf() {
+    let dir = std::path::Path::new("tests/liblzf");
+    println!("cargo::rerun-if-changed={}", dir.display());
+    cc::Build::new()
+        .file(dir.join("lzf_d.c"))
+        .file(dir.join("ref_errno.c"))
+        .include(dir)
+        .warnings(false)
+        .compile("lzf_c_ref");
+}
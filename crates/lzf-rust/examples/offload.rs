@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: ISC
+//! Offloads `LzfWriter` block compression to Tokio's blocking thread pool.
+use lzf_rust::{LzfReader, LzfWriter, Read, Write};
+use tokio::task::JoinSet;
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().expect("runtime");
+    runtime.block_on(async {
+        let input: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8 ^ (i >> 12) as u8).collect();
+
+        let mut writer = LzfWriter::new(Vec::new(), 65535).expect("writer");
+        writer.enable_block_jobs();
+
+        let mut running = JoinSet::new();
+        for chunk in input.chunks(256 * 1024) {
+            // Buffering is cheap; compression happens on the blocking pool.
+            writer.write_all(chunk).expect("write");
+            while let Some(job) = writer.take_block_job() {
+                running.spawn_blocking(move || job.run());
+            }
+            // Frames may complete in any order; the writer restores it.
+            while let Some(frame) = running.try_join_next() {
+                writer.accept_frame(frame.expect("join")).expect("accept_frame");
+            }
+        }
+        while let Some(frame) = running.join_next().await {
+            writer.accept_frame(frame.expect("join")).expect("accept_frame");
+        }
+        let encoded = writer.finish().expect("finish");
+
+        let mut reader = LzfReader::new(encoded.as_slice());
+        let mut decoded = vec![0u8; input.len()];
+        reader.read_exact(&mut decoded).expect("read");
+        assert_eq!(decoded, input);
+        println!("encoded={} decoded={}", encoded.len(), decoded.len());
+    });
+}
@@ -10,14 +10,7 @@ fn main() {
 
     let mut reader = LzfReader::new(encoded.as_slice());
     let mut decoded = Vec::new();
-    let mut chunk = [0u8; 64];
-    loop {
-        let n = reader.read(&mut chunk).expect("read");
-        if n == 0 {
-            break;
-        }
-        decoded.extend_from_slice(&chunk[..n]);
-    }
+    reader.read_to_end(&mut decoded).expect("read");
 
     assert_eq!(decoded, input);
     println!("encoded={} decoded={}", encoded.len(), decoded.len());
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: ISC
+//! Dumps the token structure of a raw LZF stream.
+//!
+//! ```text
+//! cargo run --example tokens -- compressed.lzf
+//! ```
+//!
+//! Without an argument a built-in sample is compressed and dumped.
+use lzf_rust::{Token, Tokens, compress, max_compressed_size};
+
+fn main() {
+    let compressed = match std::env::args_os().nth(1) {
+        Some(path) => {
+            std::fs::read(&path).unwrap_or_else(|e| panic!("read {}: {e}", path.to_string_lossy()))
+        }
+        None => {
+            let input = b"tokens tokens tokens: literal runs and back-references";
+            let mut out = vec![0u8; max_compressed_size(input.len())];
+            let n = compress(input, &mut out).expect("compression failed");
+            out.truncate(n);
+            out
+        }
+    };
+
+    let (mut literals, mut literal_bytes, mut backrefs, mut backref_bytes) = (0, 0, 0, 0);
+    let mut tokens = Tokens::new(&compressed);
+    loop {
+        let (ip, op) = (tokens.input_pos(), tokens.output_pos());
+        let Some(token) = tokens.next() else { break };
+        match token {
+            Ok(Token::Literal { input_offset, len }) => {
+                println!("{ip:>8} {op:>10}  literal len={len:<3} at={input_offset}");
+                literals += 1;
+                literal_bytes += len;
+            }
+            Ok(Token::Backref { offset, len }) => {
+                println!("{ip:>8} {op:>10}  backref len={len:<3} offset={offset}");
+                backrefs += 1;
+                backref_bytes += len;
+            }
+            Err(e) => {
+                println!("{ip:>8} {op:>10}  error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!(
+        "input={} output={} literals={literals} ({literal_bytes} bytes) \
+         backrefs={backrefs} ({backref_bytes} bytes)",
+        compressed.len(),
+        tokens.output_pos()
+    );
+}
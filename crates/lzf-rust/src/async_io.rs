@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: ISC
+use core::pin::Pin;
+use core::task::{Context, Poll, ready};
+use std::io;
+
+use futures_io::AsyncRead;
+#[cfg(feature = "encoder")]
+use futures_io::AsyncWrite;
+
+#[cfg(feature = "encoder")]
+use crate::CompressionMode;
+#[cfg(feature = "encoder")]
+use crate::framed::EncodeOptions;
+use crate::nonblocking::ReadCore;
+#[cfg(feature = "encoder")]
+use crate::nonblocking::WriteCore;
+
+/// Asynchronous reader that decodes framed LZF (`ZV` block stream) from a
+/// `futures_io::AsyncRead` source.
+///
+/// Decodes the same streams as [`LzfReader`](crate::LzfReader) in its
+/// default mode: reading ends at the `0` end-of-stream marker or at the end
+/// of the source, and checked blocks are verified. A header or payload
+/// split across pending polls is resumed where it left off. Errors are
+/// converted with `From<Error> for std::io::Error`.
+///
+/// Like the `futures::io` extension methods, this requires an `Unpin`
+/// source; pin other sources with `Box::pin` first.
+///
+/// # Example
+///
+/// ```
+/// use futures::io::AsyncReadExt;
+/// use lzf_rust::{AsyncLzfReader, encode_blocks};
+///
+/// let framed = encode_blocks(b"async reader example", 4096).unwrap();
+/// let mut reader = AsyncLzfReader::new(framed.as_slice());
+/// let mut out = Vec::new();
+/// futures::executor::block_on(reader.read_to_end(&mut out)).unwrap();
+/// assert_eq!(out, b"async reader example");
+/// ```
+pub struct AsyncLzfReader<R> {
+    inner: R,
+    core: ReadCore,
+}
+
+impl<R> AsyncLzfReader<R> {
+    /// Creates a new asynchronous framed LZF reader.
+    pub fn new(inner: R) -> Self {
+        Self { inner, core: ReadCore::new() }
+    }
+
+    /// Unwraps the reader and returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for AsyncLzfReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            let ready = this.core.available();
+            if !ready.is_empty() || buf.is_empty() {
+                let n = ready.len().min(buf.len());
+                buf[..n].copy_from_slice(&ready[..n]);
+                this.core.consume(n);
+                return Poll::Ready(Ok(n));
+            }
+
+            let Some(input) = this.core.input_buf()? else {
+                return Poll::Ready(Ok(0));
+            };
+            match ready!(Pin::new(&mut this.inner).poll_read(cx, input))? {
+                0 => {
+                    this.core.end_of_input()?;
+                    return Poll::Ready(Ok(0));
+                }
+                n => this.core.advance(n)?,
+            }
+        }
+    }
+}
+
+/// Asynchronous writer that encodes framed LZF (`ZV` block stream) into a
+/// `futures_io::AsyncWrite` sink.
+///
+/// Produces the same bytes as [`LzfWriter`](crate::LzfWriter) with the same
+/// block size, options, and end-of-stream marker setting. A frame only
+/// partially accepted by the sink before a pending poll is resumed on the
+/// next poll, and no new input is taken until it is out.
+///
+/// `poll_flush` frames the buffered partial block, like `LzfWriter::flush`.
+/// `poll_close` frames the last block, writes the end-of-stream marker if
+/// enabled, and closes the sink; [`finish`](Self::finish) does the same but
+/// flushes the sink instead of closing it and returns it. Writes after
+/// either fail with `std::io::ErrorKind::InvalidInput`.
+///
+/// # Example
+///
+/// ```
+/// use futures::io::AsyncWriteExt;
+/// use lzf_rust::{AsyncLzfWriter, decode_blocks};
+///
+/// futures::executor::block_on(async {
+///     let mut writer = AsyncLzfWriter::new(Vec::new(), 4096).unwrap();
+///     writer.write_all(b"async writer example").await.unwrap();
+///     let framed = writer.finish().await.unwrap();
+///     assert_eq!(decode_blocks(&framed).unwrap(), b"async writer example");
+/// });
+/// ```
+#[cfg(feature = "encoder")]
+pub struct AsyncLzfWriter<W> {
+    inner: W,
+    core: WriteCore,
+}
+
+#[cfg(feature = "encoder")]
+impl<W> AsyncLzfWriter<W> {
+    /// Creates a new asynchronous framed LZF writer with the given block
+    /// size (`1..=65535`).
+    pub fn new(inner: W, block_size: usize) -> crate::Result<Self> {
+        Self::new_with_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates a new asynchronous framed LZF writer with an explicit
+    /// compression mode.
+    pub fn new_with_mode(
+        inner: W,
+        block_size: usize,
+        mode: CompressionMode,
+    ) -> crate::Result<Self> {
+        Self::new_with_options(inner, block_size, EncodeOptions::new().mode(mode))
+    }
+
+    /// Creates a new asynchronous framed LZF writer with explicit encoding
+    /// options.
+    pub fn new_with_options(
+        inner: W,
+        block_size: usize,
+        options: EncodeOptions,
+    ) -> crate::Result<Self> {
+        Ok(Self { inner, core: WriteCore::new(block_size, options, false)? })
+    }
+
+    /// Creates a writer that ends the stream with a `0` end-of-stream marker.
+    pub fn new_with_eof_marker(inner: W, block_size: usize) -> crate::Result<Self> {
+        Self::new_with_eof_marker_and_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates a writer that ends the stream with a `0` end-of-stream marker,
+    /// with an explicit compression mode.
+    pub fn new_with_eof_marker_and_mode(
+        inner: W,
+        block_size: usize,
+        mode: CompressionMode,
+    ) -> crate::Result<Self> {
+        let options = EncodeOptions::new().mode(mode);
+        Ok(Self { inner, core: WriteCore::new(block_size, options, true)? })
+    }
+
+    /// Unwraps the writer and returns the underlying writer, dropping any
+    /// unwritten data.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying writer.
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: AsyncWrite + Unpin> AsyncLzfWriter<W> {
+    /// Finishes the stream, flushes the underlying writer, and returns it.
+    pub async fn finish(mut self) -> io::Result<W> {
+        core::future::poll_fn(|cx| {
+            self.core.finish();
+            ready!(self.poll_pending(cx))?;
+            Pin::new(&mut self.inner).poll_flush(cx)
+        })
+        .await?;
+        Ok(self.inner)
+    }
+
+    /// Writes out the encoded bytes the core holds.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.core.pending().is_empty() {
+            match ready!(Pin::new(&mut self.inner).poll_write(cx, self.core.pending()))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => self.core.consume(n),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: AsyncWrite + Unpin> AsyncWrite for AsyncLzfWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Poll::Ready(Ok(this.core.accept(buf)?))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.core.flush_block();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.core.finish();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut this.inner).poll_close(cx)
+    }
+}
@@ -8,7 +8,17 @@ pub type Result<T> = core::result::Result<T, Error>;
 ///
 /// The variants are shared by raw token APIs, framed block APIs, and streaming
 /// reader/writer adapters.
+///
+/// More variants may be added in minor releases. To decide how to handle an
+/// error without matching every variant, use [`is_corruption`],
+/// [`is_io`], and [`is_config`]: every variant, present and future, is
+/// covered by exactly one of them.
+///
+/// [`is_corruption`]: Self::is_corruption
+/// [`is_io`]: Self::is_io
+/// [`is_config`]: Self::is_config
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
     /// End of input reached unexpectedly.
     Eof,
@@ -28,10 +38,64 @@ pub enum Error {
     UnknownBlockType(u8),
     /// Configuration is invalid.
     InvalidParameter,
+    /// Output would exceed a caller-supplied size limit: decoded output, or
+    /// bytes written through a `LimitedWriter`.
+    LimitExceeded,
+    /// A checked block's contents do not match its checksum.
+    ChecksumMismatch,
     /// Other I/O error.
     Other,
 }
 
+/// The classes of [`Error::is_corruption`], [`Error::is_io`], and
+/// [`Error::is_config`].
+#[derive(Clone, Copy)]
+enum ErrorClass {
+    Corruption,
+    Io,
+    Config,
+}
+
+impl Error {
+    const fn class(&self) -> ErrorClass {
+        match self {
+            Self::InvalidData
+            | Self::InvalidHeader
+            | Self::UnknownBlockType(_)
+            | Self::ChecksumMismatch => ErrorClass::Corruption,
+            Self::Eof | Self::Interrupted | Self::WriteZero | Self::Other => ErrorClass::Io,
+            Self::InvalidParameter | Self::OutputTooSmall | Self::LimitExceeded => {
+                ErrorClass::Config
+            }
+        }
+    }
+
+    /// Returns `true` if the input is not valid compressed data: malformed
+    /// tokens or block headers, unknown block types, and checksum mismatches.
+    ///
+    /// Retrying with the same input fails the same way.
+    pub const fn is_corruption(&self) -> bool {
+        matches!(self.class(), ErrorClass::Corruption)
+    }
+
+    /// Returns `true` for failures of the underlying reader or writer,
+    /// including input that ends early (`Eof`) and `Interrupted`.
+    ///
+    /// These may succeed when retried or given more input.
+    pub const fn is_io(&self) -> bool {
+        matches!(self.class(), ErrorClass::Io)
+    }
+
+    /// Returns `true` if the caller's parameters rule out the operation:
+    /// invalid arguments, too small an output buffer, or an exceeded
+    /// caller-supplied size limit.
+    ///
+    /// Retrying needs different parameters, not different input.
+    pub const fn is_config(&self) -> bool {
+        matches!(self.class(), ErrorClass::Config)
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -43,6 +107,8 @@ impl fmt::Display for Error {
             Self::InvalidHeader => f.write_str("invalid LZF block header"),
             Self::UnknownBlockType(kind) => write!(f, "unknown LZF block type: {kind}"),
             Self::InvalidParameter => f.write_str("invalid parameter"),
+            Self::LimitExceeded => f.write_str("output size limit exceeded"),
+            Self::ChecksumMismatch => f.write_str("block checksum mismatch"),
             Self::Other => f.write_str("I/O error"),
         }
     }
@@ -51,9 +117,103 @@ impl fmt::Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+/// Formats with the same messages as the `Display` impl.
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Self::Eof => defmt::write!(f, "unexpected end of input"),
+            Self::Interrupted => defmt::write!(f, "operation interrupted"),
+            Self::OutputTooSmall => defmt::write!(f, "output buffer too small"),
+            Self::WriteZero => defmt::write!(f, "failed to write data"),
+            Self::InvalidData => defmt::write!(f, "invalid compressed data"),
+            Self::InvalidHeader => defmt::write!(f, "invalid LZF block header"),
+            Self::UnknownBlockType(kind) => defmt::write!(f, "unknown LZF block type: {=u8}", kind),
+            Self::InvalidParameter => defmt::write!(f, "invalid parameter"),
+            Self::LimitExceeded => defmt::write!(f, "output size limit exceeded"),
+            Self::ChecksumMismatch => defmt::write!(f, "block checksum mismatch"),
+            Self::Other => defmt::write!(f, "I/O error"),
+        }
+    }
+}
+
+/// Raw decode failure with the position of the offending token.
+///
+/// Returned by `decompress_detailed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeErrorDetail {
+    /// The error `decompress` reports for the same input.
+    pub kind: Error,
+    /// Offset in the compressed input of the failing token's control byte.
+    pub input_offset: usize,
+    /// Number of bytes decoded before the failing token.
+    pub output_offset: usize,
+}
+
+impl fmt::Display for DecodeErrorDetail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at input offset {} (output offset {})",
+            self.kind, self.input_offset, self.output_offset
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeErrorDetail {}
+
+impl From<DecodeErrorDetail> for Error {
+    fn from(value: DecodeErrorDetail) -> Self {
+        value.kind
+    }
+}
+
+/// Framed decode failure with the position of the offending block.
+///
+/// Returned by `decode_blocks_detailed` and `LzfReader::last_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramedError {
+    /// The error `decode_blocks` reports for the same input, also returned
+    /// as the error's `source`.
+    pub kind: Error,
+    /// Index of the offending block, counting from 0.
+    pub block_index: usize,
+    /// Offset in the framed input of the offending block's header.
+    pub input_offset: usize,
+}
+
+impl fmt::Display for FramedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in block {} at input offset {}",
+            self.kind, self.block_index, self.input_offset
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FramedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+impl From<FramedError> for Error {
+    fn from(value: FramedError) -> Self {
+        value.kind
+    }
+}
+
 #[cfg(feature = "std")]
 impl From<std::io::Error> for Error {
     fn from(value: std::io::Error) -> Self {
+        // Errors converted by `From<Error> for std::io::Error` come back as
+        // they were.
+        if let Some(err) = value.get_ref().and_then(|e| e.downcast_ref::<Self>()) {
+            return *err;
+        }
         match value.kind() {
             std::io::ErrorKind::UnexpectedEof => Self::Eof,
             std::io::ErrorKind::Interrupted => Self::Interrupted,
@@ -64,3 +224,30 @@ impl From<std::io::Error> for Error {
         }
     }
 }
+
+/// Converts to a `std::io::Error` of the closest kind, keeping `value` as the
+/// error's source.
+///
+/// Decoding failures, including corrupt framing and checksum mismatches,
+/// become `InvalidData`; `LimitExceeded` becomes `FileTooLarge`.
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match value {
+            Error::Eof => ErrorKind::UnexpectedEof,
+            Error::Interrupted => ErrorKind::Interrupted,
+            Error::WriteZero => ErrorKind::WriteZero,
+            Error::OutputTooSmall
+            | Error::InvalidData
+            | Error::InvalidHeader
+            | Error::UnknownBlockType(_)
+            | Error::ChecksumMismatch => ErrorKind::InvalidData,
+            Error::InvalidParameter => ErrorKind::InvalidInput,
+            Error::LimitExceeded => ErrorKind::FileTooLarge,
+            Error::Other => ErrorKind::Other,
+        };
+        Self::new(kind, value)
+    }
+}
@@ -1,18 +1,27 @@
 // SPDX-License-Identifier: ISC
 use alloc::vec;
 use alloc::vec::Vec;
+use core::ops::Range;
 
+use crate::crc32::{crc32, crc32_update};
 use crate::decompress;
 #[cfg(feature = "encoder")]
 use crate::{CompressionMode, compress_with_mode};
-use crate::{Error, Result};
+use crate::{Error, FramedError, Result, Write};
 
 const MAGIC_0: u8 = b'Z';
 const MAGIC_1: u8 = b'V';
 const TYPE_UNCOMPRESSED: u8 = 0;
 const TYPE_COMPRESSED: u8 = 1;
+const TYPE_CHECKED: u8 = 2;
 const TYPE0_HDR_SIZE: usize = 5;
 const TYPE1_HDR_SIZE: usize = 7;
+const TYPE2_HDR_SIZE: usize = 11;
+pub(crate) const FOOTER_MAGIC: [u8; 4] = *b"LZFT";
+pub(crate) const FOOTER_LEN: usize = 16;
+/// Default and minimum payload savings for storing a block compressed.
+#[cfg(feature = "encoder")]
+const MIN_SAVINGS: usize = 4;
 
 /// Encodes input into `lzf` block stream format (`ZV\0`/`ZV\1` blocks).
 ///
@@ -37,55 +46,976 @@ pub fn encode_blocks_with_mode(
     input: &[u8],
     block_size: usize,
     mode: CompressionMode,
+) -> Result<Vec<u8>> {
+    encode_blocks_with_options(input, block_size, EncodeOptions::new().mode(mode))
+}
+
+/// Encodes input into a framed stream of checked `ZV\2` blocks.
+///
+/// `block_size` must be in `1..=65535`. Each block carries a CRC-32 of its
+/// uncompressed contents, which `decode_blocks` and `LzfReader` verify,
+/// failing with `Error::ChecksumMismatch` on corruption of either stored or
+/// compressed payloads.
+///
+/// A `ZV\2` block has the `ZV\1` header fields followed by the big-endian
+/// CRC-32, for an 11-byte header. The payload is stored verbatim when its
+/// compressed length equals its uncompressed length and is an LZF token
+/// stream otherwise. Checked blocks are an extension of this crate: other
+/// `lzf` decoders reject them as an unknown block type.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, decode_blocks, encode_blocks_checked};
+///
+/// let framed = encode_blocks_checked(b"checked checked", 4096, CompressionMode::Normal).unwrap();
+/// assert_eq!(&framed[..3], b"ZV\x02");
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"checked checked");
+/// ```
+#[cfg(feature = "encoder")]
+pub fn encode_blocks_checked(
+    input: &[u8],
+    block_size: usize,
+    mode: CompressionMode,
+) -> Result<Vec<u8>> {
+    encode_blocks_with_options(input, block_size, EncodeOptions::new().mode(mode).checksums(true))
+}
+
+/// Encodes input into a framed stream ending with the `0` end-of-stream
+/// marker and a whole-stream footer.
+///
+/// `block_size` must be in `1..=65535`. The blocks are those of
+/// `encode_blocks_with_mode`; the footer lets decoders check the stream end
+/// to end without per-block overhead. It is 16 bytes following the marker:
+///
+/// | Offset | Size | Contents                                   |
+/// |--------|------|--------------------------------------------|
+/// | 0      | 4    | magic `LZFT` (`4c 5a 46 54`)               |
+/// | 4      | 8    | total decoded length, big-endian `u64`     |
+/// | 12     | 4    | CRC-32 of the decoded bytes, big-endian    |
+///
+/// `decode_blocks` and the other slice decoders verify a footer found after
+/// the marker and fail with `Error::ChecksumMismatch` if either field does not
+/// match the decoded stream, or `Error::Eof` if the footer is cut short;
+/// `LzfReader` does so once `enable_footer_check` is called. Streams without
+/// a footer decode as before. Decoders unaware of footers stop at the marker
+/// and never look at it.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, Error, decode_blocks, encode_blocks_with_footer};
+///
+/// let mut framed = encode_blocks_with_footer(b"footer", 4096, CompressionMode::Normal).unwrap();
+/// assert_eq!(&framed[framed.len() - 16..][..4], b"LZFT");
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"footer");
+///
+/// *framed.last_mut().unwrap() ^= 1;
+/// assert_eq!(decode_blocks(&framed), Err(Error::ChecksumMismatch));
+/// ```
+#[cfg(feature = "encoder")]
+pub fn encode_blocks_with_footer(
+    input: &[u8],
+    block_size: usize,
+    mode: CompressionMode,
+) -> Result<Vec<u8>> {
+    let mut output = encode_blocks_with_mode(input, block_size, mode)?;
+    output.reserve(1 + FOOTER_LEN);
+    output.push(0);
+    output.extend_from_slice(&StreamFooter::of(input).to_bytes());
+    Ok(output)
+}
+
+/// Running length and CRC-32 of a decoded stream, as stored in its footer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct StreamFooter {
+    decoded_len: u64,
+    crc: u32,
+}
+
+impl StreamFooter {
+    pub(crate) const fn new() -> Self {
+        Self { decoded_len: 0, crc: 0 }
+    }
+
+    /// Returns the footer of a stream decoding to `decoded`.
+    pub(crate) fn of(decoded: &[u8]) -> Self {
+        let mut footer = Self::new();
+        footer.update(decoded);
+        footer
+    }
+
+    /// Accounts for `data` following the bytes seen so far.
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.decoded_len += data.len() as u64;
+        self.crc = crc32_update(self.crc, data);
+    }
+
+    #[cfg(feature = "encoder")]
+    pub(crate) fn to_bytes(self) -> [u8; FOOTER_LEN] {
+        let mut bytes = [0u8; FOOTER_LEN];
+        bytes[..4].copy_from_slice(&FOOTER_MAGIC);
+        bytes[4..12].copy_from_slice(&self.decoded_len.to_be_bytes());
+        bytes[12..].copy_from_slice(&self.crc.to_be_bytes());
+        bytes
+    }
+
+    /// Parses the footer at the start of `bytes`.
+    ///
+    /// Returns `None` if `bytes` does not start with the footer magic, and
+    /// `Error::Eof` if the magic is followed by less than a full footer.
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Option<Self>> {
+        if !bytes.starts_with(&FOOTER_MAGIC) {
+            return Ok(None);
+        }
+        let Some(bytes) = bytes.get(..FOOTER_LEN) else {
+            return Err(Error::Eof);
+        };
+        let mut len = [0u8; 8];
+        len.copy_from_slice(&bytes[4..12]);
+        let crc = u32::from_be_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+        Ok(Some(Self { decoded_len: u64::from_be_bytes(len), crc }))
+    }
+
+    /// Checks the footer against the running footer of the decoded stream.
+    pub(crate) fn verify(self, decoded: Self) -> Result<()> {
+        if self == decoded { Ok(()) } else { Err(Error::ChecksumMismatch) }
+    }
+}
+
+/// Options for framed encoding with `encode_blocks_with_options`.
+///
+/// The defaults match `encode_blocks`: `CompressionMode::Normal`, plain
+/// `ZV\0`/`ZV\1` blocks, and a compression attempt on every block.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, EncodeOptions, decode_blocks, encode_blocks_with_options};
+///
+/// let options = EncodeOptions::new().mode(CompressionMode::Best).skip_incompressible(true);
+/// let framed = encode_blocks_with_options(b"options options", 4096, options).unwrap();
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"options options");
+/// ```
+#[cfg(feature = "encoder")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    mode: CompressionMode,
+    checksums: bool,
+    compress: bool,
+    skip_incompressible: bool,
+    min_savings_bytes: usize,
+    min_savings_per_mille: u16,
+}
+
+#[cfg(feature = "encoder")]
+impl EncodeOptions {
+    /// Returns the default options.
+    pub const fn new() -> Self {
+        Self {
+            mode: CompressionMode::Normal,
+            checksums: false,
+            compress: true,
+            skip_incompressible: false,
+            min_savings_bytes: MIN_SAVINGS,
+            min_savings_per_mille: 0,
+        }
+    }
+
+    /// Selects the raw compressor mode.
+    pub const fn mode(mut self, mode: CompressionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Frames every block as a checked `ZV\2` block, as `encode_blocks_checked`
+    /// does.
+    pub const fn checksums(mut self, enabled: bool) -> Self {
+        self.checksums = enabled;
+        self
+    }
+
+    /// Controls whether blocks are compressed at all.
+    ///
+    /// With `false` the encoder is never run and every block is stored as
+    /// `ZV\0` (or as an uncompressed `ZV\2` block when checksums are on), so
+    /// framing costs little more than copying the input. This suits data
+    /// known to be incompressible that still needs `lzf` framing. The default
+    /// is `true`.
+    pub const fn compress(mut self, enabled: bool) -> Self {
+        self.compress = enabled;
+        self
+    }
+
+    /// Stores blocks that look incompressible without trying to compress them.
+    ///
+    /// Before compressing a block of at least 1 KiB, a few short windows spread
+    /// over it are probed for repeated 3-byte sequences, the unit LZF matches
+    /// are built from. When almost none repeat, as in already-compressed or
+    /// encrypted data, the block is stored as `ZV\0` right away, which skips
+    /// the full match search. Such blocks would almost always have been stored
+    /// anyway, so the output usually does not change. Repeats that are farther
+    /// apart than the probed windows are missed, so a block that only
+    /// compresses through long-range matches can end up stored.
+    pub const fn skip_incompressible(mut self, enabled: bool) -> Self {
+        self.skip_incompressible = enabled;
+        self
+    }
+
+    /// Requires a compressed payload to be at least `bytes` shorter than the
+    /// block before it is stored as `ZV\1`; blocks saving less are stored
+    /// uncompressed.
+    ///
+    /// The default, and the smallest accepted value, is 4 bytes: the `ZV\1`
+    /// header is 2 bytes longer than the `ZV\0` header, and the historical
+    /// `lzf` utility never compresses a block that saves less. Smaller values
+    /// are raised to 4, so blocks of 4 bytes or less are always stored.
+    pub const fn min_savings_bytes(mut self, bytes: usize) -> Self {
+        self.min_savings_bytes = if bytes < MIN_SAVINGS { MIN_SAVINGS } else { bytes };
+        self
+    }
+
+    /// Requires a compressed payload to save at least `per_mille` thousandths
+    /// of the block's length, rounded up, before it is stored as `ZV\1`.
+    ///
+    /// For example `125` only compresses blocks that shrink by 12.5% or more,
+    /// trading ratio for cheaper reads. Values above 1000 are clamped to
+    /// 1000, which stores every block uncompressed. Combined with
+    /// `min_savings_bytes`, the larger of the two requirements applies. The
+    /// default is 0.
+    pub const fn min_savings_per_mille(mut self, per_mille: u16) -> Self {
+        self.min_savings_per_mille = if per_mille > 1000 { 1000 } else { per_mille };
+        self
+    }
+
+    /// Returns the selected raw compressor mode.
+    pub(crate) const fn compression_mode(&self) -> CompressionMode {
+        self.mode
+    }
+
+    /// Returns the number of bytes compression must save on a block of
+    /// `len` bytes.
+    fn required_savings(&self, len: usize) -> usize {
+        let relative = (len * self.min_savings_per_mille as usize).div_ceil(1000);
+        if relative > self.min_savings_bytes { relative } else { self.min_savings_bytes }
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encodes input into `lzf` block stream format with explicit options.
+///
+/// `block_size` must be in `1..=65535`. With default options the output is
+/// identical to `encode_blocks`.
+#[cfg(feature = "encoder")]
+pub fn encode_blocks_with_options(
+    input: &[u8],
+    block_size: usize,
+    options: EncodeOptions,
 ) -> Result<Vec<u8>> {
     if block_size == 0 || block_size > usize::from(u16::MAX) {
         return Err(Error::InvalidParameter);
     }
 
-    let mut output = Vec::new();
+    let mut capacity = max_encoded_blocks_size(input.len(), block_size);
+    if options.checksums {
+        capacity += input.len().div_ceil(block_size) * (TYPE2_HDR_SIZE - TYPE0_HDR_SIZE);
+    }
+    let mut output = Vec::with_capacity(capacity);
+    let mut comp_buf = Vec::new();
+    for block in input.chunks(block_size) {
+        let (header, payload) = frame_block(block, &options, &mut comp_buf);
+        output.extend_from_slice(header.as_slice());
+        output.extend_from_slice(payload);
+    }
+
+    Ok(output)
+}
+
+/// Computes an upper bound for the size of `input_len` bytes framed with
+/// `block_size`-byte blocks.
+///
+/// Every block is stored either compressed, when its payload is at least 4
+/// bytes shorter than the block, or uncompressed behind a 5-byte header, so
+/// the bound is `input_len` plus 5 bytes per block. The end-of-stream marker, if
+/// written, is one more byte. `block_size` is clamped to `1..=65535`, the
+/// range the encoders accept. Checked `ZV\2` streams need 6 more bytes per
+/// block.
+#[inline]
+pub const fn max_encoded_blocks_size(input_len: usize, block_size: usize) -> usize {
+    let block_size = if block_size == 0 {
+        1
+    } else if block_size > u16::MAX as usize {
+        u16::MAX as usize
+    } else {
+        block_size
+    };
+    input_len.saturating_add(input_len.div_ceil(block_size).saturating_mul(TYPE0_HDR_SIZE))
+}
+
+/// Encodes input into `lzf` block stream format, writing each block to `out`
+/// as soon as it is framed.
+///
+/// `block_size` must be in `1..=65535`. One compression scratch buffer is
+/// reused across blocks, so memory use does not grow with the input. The
+/// bytes written are identical to `encode_blocks_with_mode` with the same
+/// parameters. Returns the total number of bytes written.
+///
+/// Each block's header and payload are handed to `out` in a single
+/// `write_all` call, so a failing writer never receives a header without the
+/// start of its payload. Errors from `out` are returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, decode_blocks, encode_blocks_to_writer};
+///
+/// let input = b"framed straight into a writer";
+/// let mut out = Vec::new();
+/// let n = encode_blocks_to_writer(input, 4096, CompressionMode::Normal, &mut out).unwrap();
+/// assert_eq!(n, out.len() as u64);
+/// assert_eq!(decode_blocks(&out).unwrap(), input);
+/// ```
+#[cfg(feature = "encoder")]
+pub fn encode_blocks_to_writer<W: Write + ?Sized>(
+    input: &[u8],
+    block_size: usize,
+    mode: CompressionMode,
+    out: &mut W,
+) -> Result<u64> {
+    if block_size == 0 || block_size > usize::from(u16::MAX) {
+        return Err(Error::InvalidParameter);
+    }
 
+    let options = EncodeOptions::new().mode(mode);
+    let mut comp_buf = Vec::new();
+    let mut frame = Vec::new();
+    let mut total = 0u64;
     for block in input.chunks(block_size) {
-        let max_try = block.len().saturating_sub(4);
-        let mut compressed = vec![0u8; max_try];
+        let (header, payload) = frame_block(block, &options, &mut comp_buf);
+        frame.clear();
+        frame.extend_from_slice(header.as_slice());
+        frame.extend_from_slice(payload);
+        out.write_all(&frame)?;
+        total += frame.len() as u64;
+    }
 
-        let encoded_len = if max_try == 0 {
-            Err(Error::OutputTooSmall)
-        } else {
-            compress_with_mode(block, &mut compressed, mode)
-        };
+    Ok(total)
+}
+
+/// Serialized `ZV` block header (5, 7, or 11 bytes).
+#[cfg(feature = "encoder")]
+pub(crate) struct FrameHeader {
+    bytes: [u8; TYPE2_HDR_SIZE],
+    len: usize,
+}
+
+#[cfg(feature = "encoder")]
+impl FrameHeader {
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Frames `block` as a compressed block when the payload saves at least
+/// the bytes required by `options` (4 by default), or as an uncompressed block
+/// otherwise.
+///
+/// With `options.checksums`, the block is framed as a `ZV\2` block carrying
+/// the CRC-32 of `block` instead. `comp_buf` is used as compression scratch
+/// space and grown as needed.
+#[cfg(feature = "encoder")]
+pub(crate) fn frame_block<'a>(
+    block: &'a [u8],
+    options: &EncodeOptions,
+    comp_buf: &'a mut Vec<u8>,
+) -> (FrameHeader, &'a [u8]) {
+    debug_assert!(block.len() <= usize::from(u16::MAX));
+    let us = (block.len() as u16).to_be_bytes();
+
+    let mut payload = None;
+    let max_try = block.len().saturating_sub(options.required_savings(block.len()));
+    if options.compress
+        && max_try > 0
+        && !(options.skip_incompressible && looks_incompressible(block))
+    {
+        if comp_buf.len() < max_try {
+            comp_buf.resize(max_try, 0);
+        }
+        // The only possible failure is `OutputTooSmall`, i.e. the block did
+        // not compress well enough; store it uncompressed in that case.
+        if let Ok(cs) = compress_with_mode(block, &mut comp_buf[..max_try], options.mode) {
+            payload = Some(&comp_buf[..cs]);
+        }
+    }
+
+    let mut bytes = [MAGIC_0, MAGIC_1, TYPE_UNCOMPRESSED, us[0], us[1], 0, 0, 0, 0, 0, 0];
+    if options.checksums {
+        let payload = payload.unwrap_or(block);
+        let cs = (payload.len() as u16).to_be_bytes();
+        bytes[2..7].copy_from_slice(&[TYPE_CHECKED, cs[0], cs[1], us[0], us[1]]);
+        bytes[7..].copy_from_slice(&crc32(block).to_be_bytes());
+        return (FrameHeader { bytes, len: TYPE2_HDR_SIZE }, payload);
+    }
+    match payload {
+        Some(payload) => {
+            let cs = (payload.len() as u16).to_be_bytes();
+            bytes[2..7].copy_from_slice(&[TYPE_COMPRESSED, cs[0], cs[1], us[0], us[1]]);
+            (FrameHeader { bytes, len: TYPE1_HDR_SIZE }, payload)
+        }
+        None => (FrameHeader { bytes, len: TYPE0_HDR_SIZE }, block),
+    }
+}
+
+/// Smallest block `looks_incompressible` probes; shorter blocks are cheap
+/// enough to just compress.
+#[cfg(feature = "encoder")]
+const PROBE_MIN_BLOCK: usize = 1024;
+#[cfg(feature = "encoder")]
+const PROBE_WINDOWS: usize = 4;
+#[cfg(feature = "encoder")]
+const PROBE_WINDOW_LEN: usize = 128;
+#[cfg(feature = "encoder")]
+const PROBE_HASH_LOG: u32 = 14;
+
+/// Predicts whether compressing `block` is pointless.
+///
+/// Hashes the trigrams of `PROBE_WINDOWS` evenly spaced windows into a
+/// bitset and counts trigrams whose hash was already seen. On random data
+/// only hash collisions repeat, about 8 of the roughly 500 trigrams; the
+/// block is reported incompressible when fewer than 1 in 16 repeat.
+#[cfg(feature = "encoder")]
+fn looks_incompressible(block: &[u8]) -> bool {
+    if block.len() < PROBE_MIN_BLOCK {
+        return false;
+    }
+
+    let mut seen = [0u64; (1 << PROBE_HASH_LOG) / 64];
+    let (mut trigrams, mut repeats) = (0usize, 0usize);
+    let stride = (block.len() - PROBE_WINDOW_LEN) / (PROBE_WINDOWS - 1);
+    for window in 0..PROBE_WINDOWS {
+        for t in block[window * stride..][..PROBE_WINDOW_LEN].windows(3) {
+            let v = u32::from(t[0]) << 16 | u32::from(t[1]) << 8 | u32::from(t[2]);
+            let h = (v.wrapping_mul(0x9e37_79b1) >> (32 - PROBE_HASH_LOG)) as usize;
+            let bit = 1u64 << (h % 64);
+            if seen[h / 64] & bit != 0 {
+                repeats += 1;
+            }
+            seen[h / 64] |= bit;
+            trigrams += 1;
+        }
+    }
+    repeats * 16 < trigrams
+}
+
+/// Frames `block` as a single `ZV` block.
+///
+/// The block is stored compressed (`ZV\1`) when its compressed payload is at
+/// least 4 bytes shorter than the block and uncompressed (`ZV\0`) otherwise,
+/// exactly as `encode_blocks_with_mode` frames each of its blocks.
+/// Concatenated outputs form a valid framed stream.
+///
+/// Returns `Error::InvalidParameter` if `block` is longer than 65535 bytes.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, decode_block, encode_block};
+///
+/// let frame = encode_block(b"one block, one block", CompressionMode::Normal).unwrap();
+/// let (decoded, consumed) = decode_block(&frame).unwrap();
+/// assert_eq!(decoded, b"one block, one block");
+/// assert_eq!(consumed, frame.len());
+/// ```
+#[cfg(feature = "encoder")]
+pub fn encode_block(block: &[u8], mode: CompressionMode) -> Result<Vec<u8>> {
+    if block.len() > usize::from(u16::MAX) {
+        return Err(Error::InvalidParameter);
+    }
+    let mut comp_buf = Vec::new();
+    let (header, payload) = frame_block(block, &EncodeOptions::new().mode(mode), &mut comp_buf);
+    let mut frame = Vec::with_capacity(header.len() + payload.len());
+    frame.extend_from_slice(header.as_slice());
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// Decodes the first `ZV` block of `input`.
+///
+/// Returns the decoded block and the number of input bytes it occupied, so
+/// callers can walk concatenated blocks themselves. Bytes after the block are
+/// not examined. Decoded blocks never exceed 65535 bytes, the largest length
+/// a block header can declare.
+///
+/// Returns `Error::Eof` if `input` is empty or starts with the `0`
+/// end-of-stream marker; malformed blocks fail as in `decode_blocks`.
+pub fn decode_block(input: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let frame = parse_frame(input, 0, false)?.ok_or(Error::Eof)?;
+    let block = if frame.is_compressed() {
+        let mut block = vec![0u8; frame.uncompressed_len];
+        decode_frame(&frame, &mut block)?;
+        block
+    } else {
+        verify_checksum(&frame, frame.payload)?;
+        frame.payload.to_vec()
+    };
+    Ok((block, frame.end()))
+}
+
+/// Kind of a `ZV` block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockType {
+    /// `ZV\0`: payload stored verbatim.
+    Uncompressed,
+    /// `ZV\1`: payload is a raw LZF token stream.
+    Compressed,
+    /// `ZV\2`: checked block carrying a CRC-32 of its decoded contents; see
+    /// `encode_blocks_checked`.
+    Checked,
+}
+
+/// Metadata of one block in a framed stream, as yielded by [`FrameIter`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FrameInfo<'a> {
+    /// Offset of the block header in the framed input.
+    pub offset: usize,
+    /// Block kind.
+    pub block_type: BlockType,
+    /// Length of the payload as stored in the input.
+    pub compressed_len: usize,
+    /// Length of the block once decoded.
+    pub uncompressed_len: usize,
+    /// Range of the payload in the framed input.
+    pub payload_range: Range<usize>,
+    /// The payload bytes, `&input[payload_range]`.
+    pub payload: &'a [u8],
+    /// CRC-32 of the decoded block, for checked blocks.
+    pub checksum: Option<u32>,
+}
+
+impl FrameInfo<'_> {
+    /// Returns the offset just past this block in the framed input.
+    pub fn end(&self) -> usize {
+        self.payload_range.end
+    }
+
+    /// Returns whether the payload is an LZF token stream rather than the
+    /// block contents stored verbatim.
+    pub fn is_compressed(&self) -> bool {
+        match self.block_type {
+            BlockType::Uncompressed => false,
+            BlockType::Compressed => true,
+            BlockType::Checked => self.compressed_len != self.uncompressed_len,
+        }
+    }
+}
+
+/// Iterator over the blocks of a framed stream, without decompressing them.
+///
+/// Iteration ends at the end of the input or at the `0` end-of-stream marker.
+/// Each header is checked like `decode_blocks` checks it (magic, header
+/// size, block type, payload presence); the first malformed header is yielded
+/// as an error, after which the iterator is exhausted. Compressed payloads
+/// are not validated.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{BlockType, FrameIter, encode_blocks};
+///
+/// let framed = encode_blocks(&[b'a'; 6000], 4096).unwrap();
+/// let frames: Vec<_> = FrameIter::new(&framed).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(frames.len(), 2);
+/// assert_eq!(frames[0].offset, 0);
+/// assert_eq!(frames[0].block_type, BlockType::Compressed);
+/// assert_eq!(frames[1].offset, frames[0].end());
+/// assert_eq!(frames[0].uncompressed_len + frames[1].uncompressed_len, 6000);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FrameIter<'a> {
+    input: &'a [u8],
+    pos: usize,
+    done: bool,
+}
+
+impl<'a> FrameIter<'a> {
+    /// Creates an iterator over the blocks of the framed stream `input`.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0, done: false }
+    }
+
+    /// Returns the offset of the next block header, or of the end-of-stream
+    /// marker once iteration has stopped there.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
 
-        match encoded_len {
-            Ok(cs) => {
-                let cs_u16 = u16::try_from(cs).map_err(|_| Error::InvalidParameter)?;
-                let us_u16 = u16::try_from(block.len()).map_err(|_| Error::InvalidParameter)?;
-
-                output.push(MAGIC_0);
-                output.push(MAGIC_1);
-                output.push(TYPE_COMPRESSED);
-                output.extend_from_slice(&cs_u16.to_be_bytes());
-                output.extend_from_slice(&us_u16.to_be_bytes());
-                output.extend_from_slice(&compressed[..cs]);
+impl<'a> Iterator for FrameIter<'a> {
+    type Item = Result<FrameInfo<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match parse_frame(self.input, self.pos, false) {
+            Ok(Some(frame)) => {
+                self.pos = frame.end();
+                Some(Ok(frame))
             }
-            Err(Error::OutputTooSmall) => {
-                let us_u16 = u16::try_from(block.len()).map_err(|_| Error::InvalidParameter)?;
-
-                output.push(MAGIC_0);
-                output.push(MAGIC_1);
-                output.push(TYPE_UNCOMPRESSED);
-                output.extend_from_slice(&us_u16.to_be_bytes());
-                output.extend_from_slice(block);
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
             }
-            Err(err) => return Err(err),
         }
     }
+}
 
-    Ok(output)
+impl core::iter::FusedIterator for FrameIter<'_> {}
+
+/// Parses the block starting at `input[ip..]`.
+///
+/// Returns `None` at the end of the input or at the `0` end-of-stream marker.
+/// Checks the header and the presence of the payload, but not the payload
+/// itself. With `skip_unknown`, blocks of unknown type are skipped using the
+/// `ZV\0` length convention described for `decode_blocks_lenient`.
+pub(crate) fn parse_frame(
+    input: &[u8],
+    mut ip: usize,
+    skip_unknown: bool,
+) -> Result<Option<FrameInfo<'_>>> {
+    let (block_type, header_len) = loop {
+        if ip >= input.len() || input[ip] == 0 {
+            return Ok(None);
+        }
+
+        if input.len() - ip < TYPE0_HDR_SIZE {
+            return Err(Error::InvalidHeader);
+        }
+        if input[ip] != MAGIC_0 || input[ip + 1] != MAGIC_1 {
+            return Err(Error::InvalidHeader);
+        }
+
+        match input[ip + 2] {
+            TYPE_UNCOMPRESSED => break (BlockType::Uncompressed, TYPE0_HDR_SIZE),
+            TYPE_COMPRESSED => break (BlockType::Compressed, TYPE1_HDR_SIZE),
+            TYPE_CHECKED => break (BlockType::Checked, TYPE2_HDR_SIZE),
+            _ if skip_unknown => {
+                let len = usize::from(u16::from_be_bytes([input[ip + 3], input[ip + 4]]));
+                if input.len() - ip - TYPE0_HDR_SIZE < len {
+                    return Err(Error::InvalidData);
+                }
+                ip += TYPE0_HDR_SIZE + len;
+            }
+            other => return Err(Error::UnknownBlockType(other)),
+        }
+    };
+    if input.len() - ip < header_len {
+        return Err(Error::InvalidHeader);
+    }
+
+    let header = &input[ip..ip + header_len];
+    let compressed_len = usize::from(u16::from_be_bytes([header[3], header[4]]));
+    let (uncompressed_len, checksum) = match block_type {
+        BlockType::Uncompressed => (compressed_len, None),
+        BlockType::Compressed => (usize::from(u16::from_be_bytes([header[5], header[6]])), None),
+        BlockType::Checked => (
+            usize::from(u16::from_be_bytes([header[5], header[6]])),
+            Some(u32::from_be_bytes([header[7], header[8], header[9], header[10]])),
+        ),
+    };
+    let start = ip + header_len;
+
+    if input.len() - start < compressed_len {
+        return Err(Error::InvalidData);
+    }
+    let payload_range = start..start + compressed_len;
+    Ok(Some(FrameInfo {
+        offset: ip,
+        block_type,
+        compressed_len,
+        uncompressed_len,
+        payload: &input[payload_range.clone()],
+        payload_range,
+        checksum,
+    }))
+}
+
+/// Returns the length of the frame starting with `partial`, as far as it is
+/// known: the header length while the header is incomplete, the full frame
+/// length afterwards. `partial` must not start with the end-of-stream marker.
+pub(crate) fn frame_len_hint(partial: &[u8]) -> Result<usize> {
+    if partial.len() < TYPE0_HDR_SIZE {
+        return Ok(TYPE0_HDR_SIZE);
+    }
+    if partial[0] != MAGIC_0 || partial[1] != MAGIC_1 {
+        return Err(Error::InvalidHeader);
+    }
+    let header_len = match partial[2] {
+        TYPE_UNCOMPRESSED => TYPE0_HDR_SIZE,
+        TYPE_COMPRESSED => TYPE1_HDR_SIZE,
+        TYPE_CHECKED => TYPE2_HDR_SIZE,
+        other => return Err(Error::UnknownBlockType(other)),
+    };
+    if partial.len() < header_len {
+        return Ok(header_len);
+    }
+    Ok(header_len + usize::from(u16::from_be_bytes([partial[3], partial[4]])))
+}
+
+/// Returns whether `input` starts with a complete block header of a known
+/// type, as a cheap check for `lzf` framed data before decoding it.
+///
+/// Only the header is looked at, so a `true` result does not mean the stream
+/// decodes. An empty stream (no input, or only the end-of-stream marker)
+/// yields `false`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{encode_blocks, starts_with_block_header};
+///
+/// assert!(starts_with_block_header(&encode_blocks(b"some data", 4096).unwrap()));
+/// assert!(!starts_with_block_header(b"plain text"));
+/// assert!(!starts_with_block_header(b"ZV\x01\x00"));
+/// ```
+pub const fn starts_with_block_header(input: &[u8]) -> bool {
+    let header_len = match input {
+        [MAGIC_0, MAGIC_1, TYPE_UNCOMPRESSED, ..] => TYPE0_HDR_SIZE,
+        [MAGIC_0, MAGIC_1, TYPE_COMPRESSED, ..] => TYPE1_HDR_SIZE,
+        [MAGIC_0, MAGIC_1, TYPE_CHECKED, ..] => TYPE2_HDR_SIZE,
+        _ => return false,
+    };
+    input.len() >= header_len
+}
+
+/// Decodes a compressed `frame` payload into `block`, which must be exactly
+/// `frame.uncompressed_len` bytes long, and verifies its checksum.
+pub(crate) fn decode_frame(frame: &FrameInfo<'_>, block: &mut [u8]) -> Result<()> {
+    let written = decompress(frame.payload, block)?;
+    if written != frame.uncompressed_len {
+        return Err(Error::InvalidData);
+    }
+    verify_checksum(frame, block)
+}
+
+/// Checks `decoded` against the checksum of a checked `frame`.
+pub(crate) fn verify_checksum(frame: &FrameInfo<'_>, decoded: &[u8]) -> Result<()> {
+    match frame.checksum {
+        Some(expected) if crc32(decoded) != expected => Err(Error::ChecksumMismatch),
+        _ => Ok(()),
+    }
+}
+
+/// Computes the decoded length of a framed stream from its block headers.
+///
+/// Walks the block headers up to the end of the input or the `0`
+/// end-of-stream marker, checking the magic, header sizes, and payload
+/// presence, and sums the declared uncompressed lengths. Payloads are not
+/// decompressed, so a stream accepted here may still fail in
+/// `decode_blocks` if a compressed payload is corrupt.
+///
+/// Returns `Error::InvalidHeader`, `Error::UnknownBlockType`, or
+/// `Error::InvalidData` for the same structural problems `decode_blocks`
+/// reports.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decoded_len_of_blocks, encode_blocks};
+///
+/// let framed = encode_blocks(&[7u8; 10_000], 4096).unwrap();
+/// assert_eq!(decoded_len_of_blocks(&framed).unwrap(), 10_000);
+/// ```
+pub fn decoded_len_of_blocks(input: &[u8]) -> Result<u64> {
+    let mut ip = 0usize;
+    let mut total = 0u64;
+    while let Some(frame) = parse_frame(input, ip, false)? {
+        total += frame.uncompressed_len as u64;
+        ip = frame.end();
+    }
+    Ok(total)
+}
+
+/// Block statistics of a framed stream, as returned by [`analyze_blocks`].
+///
+/// Block ratios are stored payload length over decoded length; blocks that
+/// decode to nothing are counted but have no ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BlockStats {
+    /// Number of blocks.
+    pub blocks: usize,
+    /// Number of blocks whose payload is an LZF token stream.
+    pub compressed_blocks: usize,
+    /// Number of blocks whose payload is stored verbatim.
+    pub uncompressed_blocks: usize,
+    /// Total length of the stored payloads.
+    pub payload_len: u64,
+    /// Total length of the block headers.
+    pub header_len: u64,
+    /// Total decoded length declared by the block headers.
+    pub decoded_len: u64,
+    /// Number of input bytes that are part of the stream, including the
+    /// end-of-stream marker if present.
+    pub stream_len: usize,
+    /// Whether the stream ended with a `0` end-of-stream marker.
+    pub eof_marker: bool,
+    /// Smallest block ratio, `None` if no block has one.
+    pub min_block_ratio: Option<f64>,
+    /// Largest block ratio, `None` if no block has one.
+    pub max_block_ratio: Option<f64>,
+    /// Mean of the block ratios, `None` if no block has one.
+    pub avg_block_ratio: Option<f64>,
+}
+
+impl BlockStats {
+    /// Returns the stream length over the decoded length, or `None` for a
+    /// stream that decodes to nothing.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.decoded_len > 0).then(|| self.stream_len as f64 / self.decoded_len as f64)
+    }
+}
+
+/// Collects block statistics of a framed stream from its block headers.
+///
+/// Walks the headers like `decoded_len_of_blocks`, stopping at the end of the
+/// input or after the `0` end-of-stream marker; bytes after the marker are
+/// not examined. Payloads are not decompressed.
+///
+/// Returns `Error::InvalidHeader`, `Error::UnknownBlockType`, or
+/// `Error::InvalidData` (truncated payload) for malformed streams.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{analyze_blocks, encode_blocks};
+///
+/// let mut framed = encode_blocks(&[b'a'; 10_000], 4096).unwrap();
+/// framed.push(0);
+/// let stats = analyze_blocks(&framed).unwrap();
+/// assert_eq!((stats.blocks, stats.compressed_blocks), (3, 3));
+/// assert_eq!(stats.decoded_len, 10_000);
+/// assert_eq!(stats.stream_len, framed.len());
+/// assert!(stats.eof_marker);
+/// assert!(stats.ratio().unwrap() < 0.1);
+/// ```
+pub fn analyze_blocks(input: &[u8]) -> Result<BlockStats> {
+    let mut stats = BlockStats::default();
+    let mut ratio_sum = 0f64;
+    let mut ratios = 0usize;
+    let mut ip = 0usize;
+
+    while let Some(frame) = parse_frame(input, ip, false)? {
+        stats.blocks += 1;
+        if frame.is_compressed() {
+            stats.compressed_blocks += 1;
+        } else {
+            stats.uncompressed_blocks += 1;
+        }
+        stats.payload_len += frame.compressed_len as u64;
+        stats.header_len += (frame.payload_range.start - frame.offset) as u64;
+        stats.decoded_len += frame.uncompressed_len as u64;
+
+        if frame.uncompressed_len > 0 {
+            let ratio = frame.compressed_len as f64 / frame.uncompressed_len as f64;
+            stats.min_block_ratio = Some(stats.min_block_ratio.map_or(ratio, |r| r.min(ratio)));
+            stats.max_block_ratio = Some(stats.max_block_ratio.map_or(ratio, |r| r.max(ratio)));
+            ratio_sum += ratio;
+            ratios += 1;
+        }
+        ip = frame.end();
+    }
+
+    stats.eof_marker = ip < input.len();
+    stats.stream_len = if stats.eof_marker { ip + 1 } else { ip };
+    stats.avg_block_ratio = (ratios > 0).then(|| ratio_sum / ratios as f64);
+    Ok(stats)
+}
+
+/// Summary of a framed stream checked by [`validate_blocks`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockValidation {
+    /// Number of blocks checked.
+    pub blocks: usize,
+    /// Total decoded length of the stream.
+    pub decoded_len: u64,
+}
+
+/// Checks that a framed stream decodes, without producing its output.
+///
+/// Unlike `decoded_len_of_blocks`, which only walks the headers, every
+/// compressed payload is decompressed into a reused scratch buffer of at
+/// most 64 KiB and must produce exactly the length its header declares, and
+/// checked blocks have their checksums verified. The stream ends at the end
+/// of the input or at the `0` end-of-stream marker, as for `decode_blocks`,
+/// and a stream footer after the marker is verified too.
+///
+/// Fails with the same errors as `decode_blocks` on the same input.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, decoded_len_of_blocks, encode_blocks, validate_blocks};
+///
+/// let mut framed = encode_blocks(&[b'v'; 10_000], 4096).unwrap();
+/// let report = validate_blocks(&framed).unwrap();
+/// assert_eq!((report.blocks, report.decoded_len), (3, 10_000));
+///
+/// // Declare one byte less for the first block: the headers still parse,
+/// // but the payload no longer fits.
+/// framed[5] -= 1;
+/// assert!(decoded_len_of_blocks(&framed).is_ok());
+/// assert_eq!(validate_blocks(&framed), Err(Error::OutputTooSmall));
+/// ```
+pub fn validate_blocks(input: &[u8]) -> Result<BlockValidation> {
+    let mut report = BlockValidation::default();
+    let mut scratch = Vec::new();
+    let mut digest = has_footer(input).then(StreamFooter::new);
+    let mut ip = 0usize;
+
+    while let Some(frame) = parse_frame(input, ip, false)? {
+        let block = if frame.is_compressed() {
+            if scratch.len() < frame.uncompressed_len {
+                scratch.resize(frame.uncompressed_len, 0);
+            }
+            let block = &mut scratch[..frame.uncompressed_len];
+            decode_frame(&frame, block)?;
+            &*block
+        } else {
+            verify_checksum(&frame, frame.payload)?;
+            frame.payload
+        };
+        if let Some(digest) = &mut digest {
+            digest.update(block);
+        }
+        report.blocks += 1;
+        report.decoded_len += frame.uncompressed_len as u64;
+        ip = frame.end();
+    }
+
+    end_of_stream(input, ip, || digest.unwrap_or(StreamFooter::new()))?;
+    Ok(report)
 }
 
 /// Decodes data encoded with `encode_blocks` or the `lzf` utility stream format.
 ///
 /// Returns `Error::InvalidHeader` for malformed frame headers and
-/// `Error::UnknownBlockType` for unsupported block type tags.
+/// `Error::UnknownBlockType` for unsupported block type tags. A stream footer
+/// after the end-of-stream marker, as written by `encode_blocks_with_footer`,
+/// is verified and fails with `Error::ChecksumMismatch` on a mismatch.
 ///
 /// # Example
 ///
@@ -98,58 +1028,573 @@ pub fn encode_blocks_with_mode(
 /// assert_eq!(decoded, input);
 /// ```
 pub fn decode_blocks(input: &[u8]) -> Result<Vec<u8>> {
+    decode_blocks_with_limit(input, usize::MAX)
+}
+
+/// Decodes a framed stream like `decode_blocks`, refusing to produce more than
+/// `max_output` bytes.
+///
+/// Each block header declares its decoded length, so the limit is enforced
+/// before the offending block is allocated or decoded: a small input chaining
+/// many highly compressed blocks cannot force allocations beyond the cap.
+///
+/// Returns `Error::LimitExceeded` once the decoded size would exceed
+/// `max_output`; malformed input fails as with `decode_blocks`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, decode_blocks_with_limit, encode_blocks};
+///
+/// let framed = encode_blocks(&[0u8; 100_000], 65535).unwrap();
+/// assert!(framed.len() < 2_000);
+/// assert_eq!(decode_blocks_with_limit(&framed, 4096), Err(Error::LimitExceeded));
+/// assert_eq!(decode_blocks_with_limit(&framed, 100_000).unwrap().len(), 100_000);
+/// ```
+pub fn decode_blocks_with_limit(input: &[u8], max_output: usize) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    decode_member(input, max_output, false, &mut output)?;
+    Ok(output)
+}
+
+/// Like [`decode_blocks`], reporting which block failed to decode.
+///
+/// On failure the returned [`FramedError`] carries the same error kind
+/// `decode_blocks` returns, the index of the offending block, and the input
+/// offset of its header. For corrupt payloads the kind is the raw decoder's
+/// error (`Error::InvalidData` or `Error::OutputTooSmall`), or
+/// `Error::ChecksumMismatch` for checked blocks.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, FrameIter, decode_blocks_detailed, encode_blocks};
+///
+/// let mut framed = encode_blocks(&[b'x'; 10_000], 4096).unwrap();
+/// let second = FrameIter::new(&framed).nth(1).unwrap().unwrap().offset;
+/// framed[second + 2] = 5;
+/// let err = decode_blocks_detailed(&framed).unwrap_err();
+/// assert_eq!(err.kind, Error::UnknownBlockType(5));
+/// assert_eq!((err.block_index, err.input_offset), (1, second));
+/// ```
+pub fn decode_blocks_detailed(input: &[u8]) -> core::result::Result<Vec<u8>, FramedError> {
+    let mut output = Vec::new();
+    decode_member(input, usize::MAX, false, &mut output)?;
+    Ok(output)
+}
+
+/// Decodes a framed stream like `decode_blocks`, skipping blocks of unknown
+/// type.
+///
+/// Some producers interleave vendor-specific blocks with the standard ones.
+/// An unknown block is assumed to follow the `ZV\0` layout: the type byte is
+/// followed by a big-endian `u16` payload length, and the payload is skipped
+/// unread. Everything else, including a truncated header or payload of an
+/// unknown block, fails as with `decode_blocks`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, decode_blocks, decode_blocks_lenient, encode_blocks};
+///
+/// let mut framed = b"ZV\x7f\x00\x03xyz".to_vec();
+/// framed.extend(encode_blocks(b"standard", 4096).unwrap());
+/// assert_eq!(decode_blocks(&framed), Err(Error::UnknownBlockType(0x7f)));
+/// assert_eq!(decode_blocks_lenient(&framed).unwrap(), b"standard");
+/// ```
+pub fn decode_blocks_lenient(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    decode_member(input, usize::MAX, true, &mut output)?;
+    Ok(output)
+}
+
+/// Policy for the `0` end-of-stream marker in `decode_blocks_strict`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EofMarker {
+    /// The stream must end at a block boundary without a marker.
+    Forbidden,
+    /// The stream may end with a marker or at a block boundary.
+    Optional,
+    /// The stream must end with a marker.
+    Required,
+}
+
+/// Decodes a framed stream like `decode_blocks`, rejecting streams that do
+/// not end exactly where the input does.
+///
+/// `decode_blocks` stops at the first `0` byte found at a block boundary and
+/// returns what it decoded so far, so a corrupted byte that became `0`, or
+/// an archive truncated at a block boundary, decodes "successfully" with
+/// data missing. This variant applies a stricter policy:
+///
+/// - A `0` marker is only accepted as the very last input byte, or followed
+///   only by a stream footer, and only if `marker` allows one; a marker
+///   followed by more input, or one that `marker` forbids, fails with
+///   `Error::InvalidData`.
+/// - Input ending without a marker fails with `Error::Eof` if `marker`
+///   requires one.
+/// - Input ending inside a block header or payload fails with `Error::Eof`
+///   instead of the `Error::InvalidHeader` or `Error::InvalidData` reported
+///   by `decode_blocks`, as does input ending inside a footer.
+///
+/// The stream structure is checked before any payload is decoded; corrupt
+/// payloads then fail as with `decode_blocks`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{EofMarker, Error, decode_blocks, decode_blocks_strict, encode_blocks};
+///
+/// let mut framed = encode_blocks(b"first", 4096).unwrap();
+/// framed.push(0);
+/// assert_eq!(decode_blocks_strict(&framed, EofMarker::Optional).unwrap(), b"first");
+/// assert_eq!(decode_blocks_strict(&framed, EofMarker::Forbidden), Err(Error::InvalidData));
+///
+/// framed.extend(encode_blocks(b"second", 4096).unwrap());
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"first");
+/// assert_eq!(decode_blocks_strict(&framed, EofMarker::Optional), Err(Error::InvalidData));
+/// ```
+pub fn decode_blocks_strict(input: &[u8], marker: EofMarker) -> Result<Vec<u8>> {
+    let mut ip = 0usize;
+    loop {
+        match parse_frame(input, ip, false) {
+            Ok(Some(frame)) => ip = frame.end(),
+            Ok(None) => break,
+            Err(_) if is_truncated(input, ip) => return Err(Error::Eof),
+            Err(err) => return Err(err),
+        }
+    }
+
+    let end = if ip == input.len() {
+        ip
+    } else if StreamFooter::parse(&input[ip + 1..])?.is_some() {
+        ip + 1 + FOOTER_LEN
+    } else {
+        ip + 1
+    };
+    match (ip < input.len(), marker) {
+        (true, EofMarker::Forbidden) => return Err(Error::InvalidData),
+        (true, _) if end < input.len() => return Err(Error::InvalidData),
+        (false, EofMarker::Required) => return Err(Error::Eof),
+        _ => {}
+    }
+    decode_blocks(input)
+}
+
+/// Finds the end of a framed stream at the start of `input`.
+///
+/// Returns the offset one past the final block, or past the `0` end-of-stream
+/// marker and the footer following it if the stream has them, so that
+/// `&input[..end]` is the whole stream and `&input[end..]` whatever follows
+/// it. Headers are validated along the way but payloads are not decompressed.
+///
+/// Only the marker or the end of the input ends the stream cleanly, so a
+/// stream followed by other data must be terminated by the marker.
+///
+/// Returns `Error::Eof` if the input ends inside a block header, payload, or
+/// footer, `Error::InvalidHeader` where a block should start but the bytes
+/// are neither a `ZV` header nor the marker, and `Error::UnknownBlockType`
+/// for a `ZV` header of unknown type.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, encode_blocks, find_stream_end};
+///
+/// let mut framed = encode_blocks(b"embedded stream", 4096).unwrap();
+/// framed.push(0);
+/// let mut container = framed.clone();
+/// container.extend_from_slice(b"unrelated trailer");
+/// assert_eq!(find_stream_end(&container).unwrap(), framed.len());
+///
+/// // Without the marker, the trailer is taken for a malformed block.
+/// container.remove(framed.len() - 1);
+/// assert_eq!(find_stream_end(&container), Err(Error::InvalidHeader));
+/// ```
+pub fn find_stream_end(input: &[u8]) -> Result<usize> {
     let mut ip = 0usize;
+    loop {
+        let rest = &input[ip..];
+        if rest.first() == Some(&0) {
+            return Ok(match StreamFooter::parse(&rest[1..])? {
+                Some(_) => ip + 1 + FOOTER_LEN,
+                None => ip + 1,
+            });
+        }
+        match parse_frame(input, ip, false) {
+            Ok(Some(frame)) => ip = frame.end(),
+            Ok(None) => return Ok(ip),
+            Err(_) if is_truncated(input, ip) => return Err(Error::Eof),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Returns whether `input[ip..]` is a proper prefix of a well-formed block,
+/// i.e. the block is cut off by the end of the input rather than malformed.
+fn is_truncated(input: &[u8], ip: usize) -> bool {
+    let rest = &input[ip..];
+    if rest.iter().zip([MAGIC_0, MAGIC_1]).any(|(&b, magic)| b != magic) {
+        return false;
+    }
+    if rest.len() < 3 {
+        return true;
+    }
+    let header_len = match rest[2] {
+        TYPE_UNCOMPRESSED => TYPE0_HDR_SIZE,
+        TYPE_COMPRESSED => TYPE1_HDR_SIZE,
+        TYPE_CHECKED => TYPE2_HDR_SIZE,
+        _ => return false,
+    };
+    if rest.len() < header_len {
+        return true;
+    }
+    rest.len() - header_len < usize::from(u16::from_be_bytes([rest[3], rest[4]]))
+}
+
+/// Decodes a framed stream like `decode_blocks`, appending the decoded bytes
+/// to `out`.
+///
+/// Returns the number of bytes appended. Capacity is reserved block by block
+/// from the lengths declared in the block headers.
+///
+/// On error `out` is truncated back to its original length, so bytes it held
+/// before the call are preserved and no partial output is left behind.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks_into, encode_blocks};
+///
+/// let mut arena = b"header:".to_vec();
+/// let framed = encode_blocks(b"record", 4096).unwrap();
+/// assert_eq!(decode_blocks_into(&framed, &mut arena).unwrap(), 6);
+/// assert_eq!(arena, b"header:record");
+/// ```
+pub fn decode_blocks_into(input: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+    let start = out.len();
+    match decode_member(input, usize::MAX, false, out) {
+        Ok(_) => Ok(out.len() - start),
+        Err(err) => {
+            out.truncate(start);
+            Err(err.into())
+        }
+    }
+}
+
+/// Decodes the first stream of `input`, up to and including its `0`
+/// end-of-stream marker.
+///
+/// Returns the decoded bytes and the number of input bytes consumed: the
+/// offset just past the end-of-stream marker and its footer, if any, or
+/// `input.len()` if the stream runs to the end of the input without a
+/// marker. Callers can decode concatenated streams by continuing at the
+/// returned offset.
+///
+/// Malformed input fails as with `decode_blocks`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks_consumed, encode_blocks};
+///
+/// let mut input = encode_blocks(b"first", 4096).unwrap();
+/// input.push(0);
+/// let first_len = input.len();
+/// input.extend(encode_blocks(b"second", 4096).unwrap());
+///
+/// let (first, consumed) = decode_blocks_consumed(&input).unwrap();
+/// assert_eq!((&first[..], consumed), (&b"first"[..], first_len));
+/// assert_eq!(decode_blocks_consumed(&input[consumed..]).unwrap().0, b"second");
+/// ```
+pub fn decode_blocks_consumed(input: &[u8]) -> Result<(Vec<u8>, usize)> {
     let mut output = Vec::new();
+    let consumed = decode_member(input, usize::MAX, false, &mut output)?;
+    Ok((output, consumed))
+}
 
+/// Decodes every stream in `input`, continuing past end-of-stream markers.
+///
+/// Files produced by concatenating `lzf` streams (`cat a.lzf b.lzf`) contain
+/// one end-of-stream marker per member that was written with one;
+/// `decode_blocks` stops at the first of them, while this function decodes
+/// all members and returns their concatenated output. Empty members (runs of
+/// consecutive markers) are skipped, and each member's footer, if it has
+/// one, is checked against that member's output.
+///
+/// Bytes after a marker and its footer must start another valid stream:
+/// trailing garbage fails as a malformed header (`Error::InvalidHeader` or
+/// `Error::UnknownBlockType`) rather than being ignored. Use
+/// `decode_blocks_consumed` to stop at a member boundary instead.
+pub fn decode_blocks_multi(input: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut ip = 0usize;
     while ip < input.len() {
-        if input[ip] == 0 {
-            break;
-        }
+        ip += decode_member(&input[ip..], usize::MAX, false, &mut output)?;
+    }
+    Ok(output)
+}
 
-        if input.len() - ip < TYPE0_HDR_SIZE {
-            return Err(Error::InvalidHeader);
+/// Appends the decoded first stream of `input` to `output`, which may grow
+/// to at most `max_output` bytes.
+///
+/// Returns the number of input bytes consumed, including the end-of-stream
+/// marker if present. With `skip_unknown`, unknown block types are skipped as
+/// described for `decode_blocks_lenient`.
+fn decode_member(
+    input: &[u8],
+    max_output: usize,
+    skip_unknown: bool,
+    output: &mut Vec<u8>,
+) -> core::result::Result<usize, FramedError> {
+    let start = output.len();
+    let mut ip = 0usize;
+    let mut block_index = 0usize;
+
+    loop {
+        let fail = move |kind, input_offset| FramedError { kind, block_index, input_offset };
+        let frame = match parse_frame(input, ip, skip_unknown) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                return end_of_stream(input, ip, || StreamFooter::of(&output[start..]))
+                    .map_err(|kind| fail(kind, ip + 1));
+            }
+            Err(kind) => return Err(fail(kind, ip)),
+        };
+        if frame.uncompressed_len > max_output - output.len() {
+            return Err(fail(Error::LimitExceeded, frame.offset));
         }
-        if input[ip] != MAGIC_0 || input[ip + 1] != MAGIC_1 {
-            return Err(Error::InvalidHeader);
+        if frame.is_compressed() {
+            // Decompress straight into the output's tail, dropping it again if
+            // the block turns out to be corrupt.
+            let start = output.len();
+            output.resize(start + frame.uncompressed_len, 0);
+            if let Err(kind) = decode_frame(&frame, &mut output[start..]) {
+                output.truncate(start);
+                return Err(fail(kind, frame.offset));
+            }
+        } else {
+            verify_checksum(&frame, frame.payload).map_err(|kind| fail(kind, frame.offset))?;
+            output.extend_from_slice(frame.payload);
         }
+        ip = frame.end();
+        block_index += 1;
+    }
+}
 
-        let block_type = input[ip + 2];
-        match block_type {
-            TYPE_UNCOMPRESSED => {
-                let uncompressed_len =
-                    usize::from(u16::from_be_bytes([input[ip + 3], input[ip + 4]]));
-                ip += TYPE0_HDR_SIZE;
-                if input.len() - ip < uncompressed_len {
-                    return Err(Error::InvalidData);
+/// Summary of the damage skipped by [`decode_blocks_salvage`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// Number of blocks recovered.
+    pub blocks: usize,
+    /// Input ranges skipped while resynchronizing, in input order.
+    pub skipped: Vec<Range<usize>>,
+}
+
+impl SalvageReport {
+    /// Returns the total number of input bytes skipped.
+    pub fn skipped_bytes(&self) -> usize {
+        self.skipped.iter().map(|range| range.len()).sum()
+    }
+}
+
+/// Decodes as much of a damaged framed stream as possible.
+///
+/// Blocks are decoded as by `decode_blocks` until one fails to parse or
+/// decode. The decoder then scans forward for the next offset holding a
+/// block that both parses and decodes (verifying its checksum, if it has
+/// one) and is followed by the end of the input, the header of another
+/// block, or a `0` end-of-stream marker ending the input or followed by a
+/// footer or another stream. This keeps stray `ZV` bytes inside damaged data
+/// from being taken for a header. Decoding resumes there; the
+/// bytes passed over are reported as a skipped range and their blocks are
+/// left out of the output. Without another such block, the rest of the
+/// input is skipped.
+///
+/// Decoding stops at the `0` end-of-stream marker; a footer after it is not
+/// checked. Every step advances through the input, so garbage input ends in
+/// one skipped range rather than a loop.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks, decode_blocks_salvage, encode_blocks};
+///
+/// let input: Vec<u8> = (0..12_288u32).map(|i| (i % 7) as u8).collect();
+/// let mut framed = encode_blocks(&input, 4096).unwrap();
+/// // Break the second block's magic.
+/// let second = 7 + usize::from(u16::from_be_bytes([framed[3], framed[4]]));
+/// framed[second] = b'X';
+/// assert!(decode_blocks(&framed).is_err());
+///
+/// let (output, report) = decode_blocks_salvage(&framed);
+/// assert_eq!(output, [&input[..4096], &input[8192..]].concat());
+/// assert_eq!(report.blocks, 2);
+/// assert_eq!(report.skipped.len(), 1);
+/// assert_eq!(report.skipped[0].start, second);
+/// ```
+pub fn decode_blocks_salvage(input: &[u8]) -> (Vec<u8>, SalvageReport) {
+    let mut output = Vec::new();
+    let mut report = SalvageReport::default();
+    let mut ip = 0usize;
+    while ip < input.len() && input[ip] != 0 {
+        if let Some(end) = salvage_block(input, ip, &mut output) {
+            report.blocks += 1;
+            ip = end;
+            continue;
+        }
+
+        let resume = (ip + 1..input.len())
+            .filter(|&p| input[p] == MAGIC_0 && input.get(p + 1) == Some(&MAGIC_1))
+            .find_map(|p| {
+                let frame = parse_frame(input, p, false).ok()??;
+                if !plausible_block_end(input, frame.end()) {
+                    return None;
                 }
-                output.extend_from_slice(&input[ip..ip + uncompressed_len]);
-                ip += uncompressed_len;
+                salvage_frame(&frame, &mut output).then(|| (p, frame.end()))
+            });
+        match resume {
+            Some((p, end)) => {
+                report.skipped.push(ip..p);
+                report.blocks += 1;
+                ip = end;
             }
-            TYPE_COMPRESSED => {
-                if input.len() - ip < TYPE1_HDR_SIZE {
-                    return Err(Error::InvalidHeader);
-                }
-                let compressed_len =
-                    usize::from(u16::from_be_bytes([input[ip + 3], input[ip + 4]]));
-                let uncompressed_len =
-                    usize::from(u16::from_be_bytes([input[ip + 5], input[ip + 6]]));
-                ip += TYPE1_HDR_SIZE;
+            None => {
+                report.skipped.push(ip..input.len());
+                break;
+            }
+        }
+    }
+    (output, report)
+}
 
-                if input.len() - ip < compressed_len {
-                    return Err(Error::InvalidData);
-                }
+/// Returns whether `input[end..]` looks like what follows a genuine block:
+/// nothing, another well-formed block header, or an end-of-stream marker
+/// followed by nothing, a footer, or a new stream.
+fn plausible_block_end(input: &[u8], end: usize) -> bool {
+    match input.get(end) {
+        None => true,
+        Some(0) => {
+            let rest = &input[end + 1..];
+            rest.is_empty()
+                || rest.starts_with(&FOOTER_MAGIC)
+                || rest.starts_with(&[MAGIC_0, MAGIC_1])
+        }
+        Some(_) => matches!(parse_frame(input, end, false), Ok(Some(_))),
+    }
+}
 
-                let mut block = vec![0u8; uncompressed_len];
-                let written = decompress(&input[ip..ip + compressed_len], &mut block)?;
-                if written != uncompressed_len {
-                    return Err(Error::InvalidData);
-                }
-                output.extend_from_slice(&block);
-                ip += compressed_len;
+/// Decodes the block at `input[ip..]` onto `output`, returning the offset
+/// past it, or `None` (leaving `output` unchanged) if it is damaged.
+fn salvage_block(input: &[u8], ip: usize, output: &mut Vec<u8>) -> Option<usize> {
+    let frame = parse_frame(input, ip, false).ok()??;
+    salvage_frame(&frame, output).then(|| frame.end())
+}
+
+/// Decodes `frame` onto `output`, returning whether it decoded; a damaged
+/// frame leaves `output` unchanged.
+fn salvage_frame(frame: &FrameInfo<'_>, output: &mut Vec<u8>) -> bool {
+    let start = output.len();
+    output.resize(start + frame.uncompressed_len, 0);
+    let res = if frame.is_compressed() {
+        decode_frame(frame, &mut output[start..])
+    } else {
+        output[start..].copy_from_slice(frame.payload);
+        verify_checksum(frame, frame.payload)
+    };
+    if res.is_err() {
+        output.truncate(start);
+    }
+    res.is_ok()
+}
+
+/// Finishes a stream whose blocks end at `input[ip..]`, returning the
+/// offset one past its end.
+///
+/// At a `0` end-of-stream marker, a footer following it is parsed and
+/// checked against `decoded()`, which is only called when there is a footer.
+pub(crate) fn end_of_stream(
+    input: &[u8],
+    ip: usize,
+    decoded: impl FnOnce() -> StreamFooter,
+) -> Result<usize> {
+    if ip == input.len() {
+        return Ok(ip);
+    }
+    match StreamFooter::parse(&input[ip + 1..])? {
+        Some(footer) => {
+            footer.verify(decoded())?;
+            Ok(ip + 1 + FOOTER_LEN)
+        }
+        None => Ok(ip + 1),
+    }
+}
+
+/// Decodes a framed stream block by block, writing the decoded bytes to `out`.
+///
+/// Unlike `decode_blocks`, the decoded stream is never held in memory: each
+/// compressed block is decoded into a single reused scratch buffer of at most
+/// 64 KiB and written before the next block is read. Returns the total number
+/// of bytes written.
+///
+/// Malformed input fails with the same errors as `decode_blocks`; blocks
+/// preceding the malformed one have already been written by then, and a
+/// stream footer is only checked once every block has been written. Errors
+/// from `out` are returned unchanged.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks_to_writer, encode_blocks};
+///
+/// let input = b"framed to a writer";
+/// let framed = encode_blocks(input, 4096).unwrap();
+/// let mut out = Vec::new();
+/// assert_eq!(decode_blocks_to_writer(&framed, &mut out).unwrap(), input.len() as u64);
+/// assert_eq!(out, input);
+/// ```
+pub fn decode_blocks_to_writer<W: Write + ?Sized>(input: &[u8], out: &mut W) -> Result<u64> {
+    let mut ip = 0usize;
+    let mut total = 0u64;
+    let mut scratch = Vec::new();
+    let mut digest = has_footer(input).then(StreamFooter::new);
+
+    while let Some(frame) = parse_frame(input, ip, false)? {
+        let block = if frame.is_compressed() {
+            if scratch.len() < frame.uncompressed_len {
+                scratch.resize(frame.uncompressed_len, 0);
             }
-            other => return Err(Error::UnknownBlockType(other)),
+            let block = &mut scratch[..frame.uncompressed_len];
+            decode_frame(&frame, block)?;
+            &*block
+        } else {
+            verify_checksum(&frame, frame.payload)?;
+            frame.payload
+        };
+        out.write_all(block)?;
+        if let Some(digest) = &mut digest {
+            digest.update(block);
         }
+        total += frame.uncompressed_len as u64;
+        ip = frame.end();
     }
 
-    Ok(output)
+    end_of_stream(input, ip, || digest.unwrap_or(StreamFooter::new()))?;
+    Ok(total)
+}
+
+/// Returns whether the stream in `input` ends with a footer, judging from its
+/// block headers alone.
+///
+/// Lets streaming decoders skip the running CRC-32 for streams without one.
+/// Malformed streams report `false`; decoding them fails before their end.
+fn has_footer(input: &[u8]) -> bool {
+    let mut ip = 0usize;
+    loop {
+        match parse_frame(input, ip, false) {
+            Ok(Some(frame)) => ip = frame.end(),
+            Ok(None) => return ip < input.len() && input[ip + 1..].starts_with(&FOOTER_MAGIC),
+            Err(_) => return false,
+        }
+    }
 }
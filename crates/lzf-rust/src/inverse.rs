@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: ISC
+//! Adapters that run the codec against the direction of the I/O: decoding
+//! data pushed in through `Write` and encoding data pulled out through
+//! `Read`.
+#[cfg(feature = "encoder")]
+use alloc::vec;
+#[cfg(feature = "encoder")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "encoder")]
+use crate::CompressionMode;
+#[cfg(feature = "encoder")]
+use crate::Read;
+#[cfg(feature = "encoder")]
+use crate::framed::EncodeOptions;
+use crate::nonblocking::ReadCore;
+#[cfg(feature = "encoder")]
+use crate::nonblocking::WriteCore;
+use crate::{Error, Result, Write};
+
+/// Writer that decodes framed LZF (`ZV` block stream) written into it.
+///
+/// The push counterpart of [`LzfReader`](crate::LzfReader): framed bytes
+/// are written in chunks of any size, headers and payloads may be split
+/// across writes, and each block's decoded bytes are written to the
+/// underlying writer as soon as the block is complete. Decoding follows
+/// `decode_blocks`, except that bytes after the `0` end-of-stream marker,
+/// such as a stream footer, are accepted and ignored.
+///
+/// Call [`finish`](Self::finish) once all input is written; it fails with
+/// `Error::Eof` if the input stopped inside a block.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{LzfDecodeWriter, LzfWrite, encode_blocks};
+///
+/// let framed = encode_blocks(b"pushed through a writer", 4096).unwrap();
+/// let mut writer = LzfDecodeWriter::new(Vec::new());
+/// for chunk in framed.chunks(3) {
+///     writer.write_all(chunk).unwrap();
+/// }
+/// assert_eq!(writer.finish().unwrap(), b"pushed through a writer");
+/// ```
+pub struct LzfDecodeWriter<W: Write> {
+    inner: W,
+    core: ReadCore,
+}
+
+impl<W: Write> LzfDecodeWriter<W> {
+    /// Creates a new decoding writer.
+    pub fn new(inner: W) -> Self {
+        Self { inner, core: ReadCore::new() }
+    }
+
+    /// Checks that the input ended between blocks, writes any decoded bytes
+    /// still pending, and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.core.end_of_input()?;
+        self.drain()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Returns whether the `0` end-of-stream marker has been written.
+    pub fn is_finished(&self) -> bool {
+        self.core.is_finished()
+    }
+
+    /// Unwraps the writer and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying writer.
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes the decoded bytes of the last completed block that the
+    /// underlying writer has not taken yet.
+    fn drain(&mut self) -> Result<()> {
+        while !self.core.available().is_empty() {
+            match self.inner.write(self.core.available()) {
+                Ok(0) => return Err(Error::WriteZero),
+                Ok(n) => self.core.consume(n),
+                Err(Error::Interrupted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for LzfDecodeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut taken = 0usize;
+        while taken < buf.len() {
+            // Completing a block replaces the decoded bytes, so those of the
+            // previous block must be out first.
+            if let Err(err) = self.drain() {
+                return if taken == 0 { Err(err) } else { Ok(taken) };
+            }
+            let Some(input) = self.core.input_buf()? else {
+                return Ok(buf.len());
+            };
+            let n = input.len().min(buf.len() - taken);
+            input[..n].copy_from_slice(&buf[taken..taken + n]);
+            self.core.advance(n)?;
+            taken += n;
+        }
+        // The input is taken either way; a failure to write the decoded
+        // bytes is reported again by the next call.
+        let _ = self.drain();
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.drain()?;
+        self.inner.flush()
+    }
+}
+
+/// Reader that encodes an uncompressed source into framed LZF (`ZV` block
+/// stream).
+///
+/// The pull counterpart of [`LzfWriter`](crate::LzfWriter): each `read`
+/// returns framed bytes, reading and compressing the next block of the
+/// source on demand, so only about one block of input and its frame are
+/// held at a time. Reads may be of any size, down to single bytes. The
+/// output is identical to `encode_blocks_with_options` over the whole
+/// source, followed by the `0` end-of-stream marker when enabled.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{LzfEncodeReader, LzfRead, decode_blocks};
+///
+/// let source: &[u8] = b"pulled through a reader";
+/// let mut reader = LzfEncodeReader::new(source, 4096).unwrap();
+/// let mut framed = Vec::new();
+/// reader.read_to_end(&mut framed).unwrap();
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"pulled through a reader");
+/// ```
+#[cfg(feature = "encoder")]
+pub struct LzfEncodeReader<R: Read> {
+    inner: R,
+    core: WriteCore,
+    /// Source bytes read but not yet taken by `core`.
+    src_buf: Vec<u8>,
+    src_pos: usize,
+    src_len: usize,
+    src_done: bool,
+}
+
+#[cfg(feature = "encoder")]
+impl<R: Read> LzfEncodeReader<R> {
+    /// Creates an encoding reader with the given block size (`1..=65535`).
+    pub fn new(inner: R, block_size: usize) -> Result<Self> {
+        Self::new_with_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates an encoding reader with an explicit compression mode.
+    pub fn new_with_mode(inner: R, block_size: usize, mode: CompressionMode) -> Result<Self> {
+        Self::new_with_options(inner, block_size, EncodeOptions::new().mode(mode))
+    }
+
+    /// Creates an encoding reader with explicit encoding options.
+    pub fn new_with_options(inner: R, block_size: usize, options: EncodeOptions) -> Result<Self> {
+        Self::build(inner, block_size, options, false)
+    }
+
+    /// Creates an encoding reader that ends its output with a trailing zero
+    /// byte EOF marker.
+    pub fn new_with_eof_marker(inner: R, block_size: usize) -> Result<Self> {
+        Self::new_with_eof_marker_and_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates an encoding reader that ends its output with a trailing zero
+    /// byte EOF marker.
+    ///
+    /// Compression mode is explicitly selected.
+    pub fn new_with_eof_marker_and_mode(
+        inner: R,
+        block_size: usize,
+        mode: CompressionMode,
+    ) -> Result<Self> {
+        Self::build(inner, block_size, EncodeOptions::new().mode(mode), true)
+    }
+
+    fn build(
+        inner: R,
+        block_size: usize,
+        options: EncodeOptions,
+        eof_marker: bool,
+    ) -> Result<Self> {
+        let core = WriteCore::new(block_size, options, eof_marker)?;
+        Ok(Self {
+            inner,
+            core,
+            src_buf: vec![0u8; block_size],
+            src_pos: 0,
+            src_len: 0,
+            src_done: false,
+        })
+    }
+
+    /// Unwraps the reader and returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<R: Read> Read for LzfEncodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let pending = self.core.pending();
+            if !pending.is_empty() {
+                let n = pending.len().min(buf.len());
+                buf[..n].copy_from_slice(&pending[..n]);
+                self.core.consume(n);
+                return Ok(n);
+            }
+            if self.src_done {
+                return Ok(0);
+            }
+            if self.src_pos < self.src_len {
+                self.src_pos += self.core.accept(&self.src_buf[self.src_pos..self.src_len])?;
+                continue;
+            }
+            match self.inner.read(&mut self.src_buf) {
+                Ok(0) => {
+                    self.core.finish();
+                    self.src_done = true;
+                }
+                Ok(n) => {
+                    self.src_pos = 0;
+                    self.src_len = n;
+                }
+                Err(Error::Interrupted) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -1,5 +1,4 @@
 // SPDX-License-Identifier: ISC
-#[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
 use crate::{Error, Result};
@@ -8,6 +7,18 @@ use crate::{Error, Result};
 ///
 /// This trait is intentionally close to `std::io::Read` so the same code can
 /// be shared across `std` and `no_std` builds.
+///
+/// With the `std` feature, every `std::io::Read` type implements this trait
+/// through a blanket impl. Your own types that do not implement
+/// `std::io::Read` can still implement this trait directly, in `std` and
+/// `no_std` builds alike; a type that implements `std::io::Read` already has
+/// this trait and must not implement it again.
+///
+/// The forwarding impls for `&mut R` and `Box<R>` come from `std::io` in
+/// `std` builds, so there they cover only `std::io::Read` types. Hand such a
+/// custom reader to [`LzfReader`](crate::LzfReader) by value and take it
+/// back with `into_inner`, or borrow it through [`copy`] and the other
+/// functions that take `&mut R`.
 pub trait Read {
     /// Reads bytes into `buf`, returning the number of bytes read.
     fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
@@ -20,11 +31,470 @@ pub trait Read {
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
         default_read_exact(self, buf)
     }
+
+    /// Reads until `buf` is full or input ends, returning the number of bytes
+    /// read.
+    ///
+    /// Unlike [`read_exact`](Self::read_exact), running out of input is not
+    /// an error: a count below `buf.len()` means input ended after that many
+    /// bytes, and 0 that it had already ended. `Error::Interrupted` is
+    /// retried; other errors are returned, and the bytes read before them are
+    /// lost as with `read_exact`.
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<usize> {
+        default_read_exact_or_eof(self, buf)
+    }
+
+    /// Reads until end of input, appending everything read to `buf`.
+    ///
+    /// Returns the number of bytes appended. `Error::Interrupted` is retried;
+    /// on any other error the bytes read so far stay appended and the error
+    /// is returned, as with `std::io::Read::read_to_end`.
+    ///
+    /// Each read goes into a zero-filled extension of `buf` that is truncated
+    /// to the bytes read. Its size starts small, doubles while reads fill it,
+    /// and is capped at 64 KiB, so at most that much is zero-filled per read.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        default_read_to_end(self, buf)
+    }
+}
+
+/// A borrowed buffer for [`Write::write_vectored`].
+///
+/// With the `std` feature this is `std::io::IoSlice`.
+#[cfg(feature = "std")]
+pub type IoSlice<'a> = std::io::IoSlice<'a>;
+
+/// A borrowed buffer for [`Write::write_vectored`].
+///
+/// Without the `std` feature this stands in for `std::io::IoSlice`, with the
+/// same `new` constructor and dereferencing to the wrapped bytes.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy, Debug)]
+pub struct IoSlice<'a>(&'a [u8]);
+
+#[cfg(not(feature = "std"))]
+impl<'a> IoSlice<'a> {
+    /// Wraps `buf`.
+    #[inline]
+    pub const fn new(buf: &'a [u8]) -> Self {
+        Self(buf)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl core::ops::Deref for IoSlice<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// An in-memory buffer with a read/write position.
+///
+/// With the `std` feature this is `std::io::Cursor`.
+#[cfg(feature = "std")]
+pub type Cursor<T> = std::io::Cursor<T>;
+
+/// An in-memory buffer with a read/write position.
+///
+/// Without the `std` feature this stands in for `std::io::Cursor`, with the
+/// same methods and the same [`Read`] and [`Write`] behavior: reads start at
+/// the position, writes to `Cursor<&mut [u8]>` stop at the end of the slice,
+/// and writes to `Cursor<Vec<u8>>` overwrite from the position and extend
+/// the vector, zero-filling any gap before the position.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cursor<T> {
+    inner: T,
+    pos: u64,
+}
+
+#[cfg(not(feature = "std"))]
+impl<T> Cursor<T> {
+    /// Creates a cursor over `inner` at position 0.
+    pub const fn new(inner: T) -> Self {
+        Self { inner, pos: 0 }
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying buffer.
+    pub const fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying buffer.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the current position.
+    pub const fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Sets the position; it may lie past the end of the buffer.
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: AsRef<[u8]>> Cursor<T> {
+    /// Returns the bytes from the position on, empty past the end.
+    fn remaining_slice(&self) -> &[u8] {
+        let data = self.inner.as_ref();
+        let start = usize::try_from(self.pos).map_or(data.len(), |pos| pos.min(data.len()));
+        &data[start..]
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<T: AsRef<[u8]>> Read for Cursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let rest = self.remaining_slice();
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Write for Cursor<&mut [u8]> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let len = self.inner.len();
+        let start = usize::try_from(self.pos).map_or(len, |pos| pos.min(len));
+        let n = (len - start).min(buf.len());
+        self.inner[start..start + n].copy_from_slice(&buf[..n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Write for Cursor<Vec<u8>> {
+    /// Fails with `Error::InvalidParameter` if the position does not fit in
+    /// `usize`.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let start = usize::try_from(self.pos).map_err(|_| Error::InvalidParameter)?;
+        let end = start.checked_add(buf.len()).ok_or(Error::InvalidParameter)?;
+        if self.inner.len() < start {
+            self.inner.resize(start, 0);
+        }
+        let overlap = self.inner.len().min(end) - start;
+        self.inner[start..start + overlap].copy_from_slice(&buf[..overlap]);
+        self.inner.extend_from_slice(&buf[overlap..]);
+        self.pos = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Extension methods for [`Read`].
+///
+/// With the `std` feature, call these as `ReadExt::take(reader, limit)` when
+/// `std::io::Read` is also in scope, since it has methods of the same names.
+pub trait ReadExt: Read {
+    /// Returns an adapter that reads at most `limit` bytes from `self`.
+    ///
+    /// Works like `std::io::Read::take` for any [`Read`] source.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{Read, ReadExt};
+    ///
+    /// let mut section = (&b"headerbody"[..]).take(6);
+    /// let mut out = Vec::new();
+    /// section.read_to_end(&mut out).unwrap();
+    /// assert_eq!(out, b"header");
+    /// assert_eq!(section.into_inner(), b"body");
+    /// ```
+    fn take(self, limit: u64) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take { inner: self, limit }
+    }
+
+    /// Returns an adapter that reads all of `self`, then all of `next`.
+    ///
+    /// Works like `std::io::Read::chain` for any [`Read`] sources.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{Read, ReadExt};
+    ///
+    /// let mut joined = (&b"scatter "[..]).chain(&b"gather"[..]);
+    /// let mut out = Vec::new();
+    /// joined.read_to_end(&mut out).unwrap();
+    /// assert_eq!(out, b"scatter gather");
+    /// ```
+    fn chain<R: Read>(self, next: R) -> Chain<Self, R>
+    where
+        Self: Sized,
+    {
+        Chain { first: self, second: next, done_first: false }
+    }
+}
+
+impl<R: Read + ?Sized> ReadExt for R {}
+
+/// A reader that stops after a fixed number of bytes of another reader.
+///
+/// Created by [`ReadExt::take`]. Reports end of input once `limit` bytes
+/// have been read or the inner reader ends, whichever comes first, and never
+/// asks the inner reader for bytes past the limit, as with `std::io::Take`.
+#[derive(Debug)]
+pub struct Take<R> {
+    inner: R,
+    limit: u64,
+}
+
+impl<R> Take<R> {
+    /// Returns the number of bytes that can still be read before the limit.
+    pub const fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Sets the number of bytes that can still be read.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns a shared reference to the inner reader.
+    pub const fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: Read> Read for Take<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.limit == 0 {
+            return Ok(0);
+        }
+        let max = usize::try_from(self.limit).map_or(buf.len(), |limit| limit.min(buf.len()));
+        let n = self.inner.read(&mut buf[..max])?;
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+/// A reader that reads one reader to its end, then another.
+///
+/// Created by [`ReadExt::chain`]. Once the first reader reports end of
+/// input, only the second is read, as with `std::io::Chain`; reads, including
+/// `read_exact`, continue seamlessly across the boundary.
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    done_first: bool,
+}
+
+impl<A, B> Chain<A, B> {
+    /// Returns the two readers.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+
+    /// Returns shared references to the two readers.
+    pub const fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Returns mutable references to the two readers.
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+}
+
+impl<A: Read, B: Read> Read for Chain<A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if !self.done_first {
+            match self.first.read(buf)? {
+                0 if !buf.is_empty() => self.done_first = true,
+                n => return Ok(n),
+            }
+        }
+        self.second.read(buf)
+    }
+}
+
+/// A writer that accepts and discards all bytes, counting them.
+///
+/// Created by [`sink`]. Unlike `std::io::Sink` it keeps count of the bytes
+/// written, so decoding into it validates a stream and reports its decoded
+/// size without keeping the output.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks_to_writer, encode_blocks, sink};
+///
+/// let framed = encode_blocks(&[7u8; 10_000], 4096).unwrap();
+/// let mut out = sink();
+/// decode_blocks_to_writer(&framed, &mut out).unwrap();
+/// assert_eq!(out.bytes_written(), 10_000);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sink {
+    written: u64,
+}
+
+impl Sink {
+    /// Returns the number of bytes written so far.
+    pub const fn bytes_written(&self) -> u64 {
+        self.written
+    }
+}
+
+/// Returns a [`Sink`] with a count of 0.
+pub const fn sink() -> Sink {
+    Sink { written: 0 }
+}
+
+impl Write for Sink {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.written += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        self.written += len as u64;
+        Ok(len)
+    }
+}
+
+/// A reader that is always at end of input.
+///
+/// Created by [`empty`]; the counterpart of `std::io::Empty`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Empty;
+
+/// Returns an [`Empty`] reader.
+pub const fn empty() -> Empty {
+    Empty
+}
+
+impl Read for Empty {
+    #[inline]
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+/// A writer that passes at most a fixed number of bytes to another writer.
+///
+/// Once the budget is used up, writes of one or more bytes fail with
+/// `Error::LimitExceeded` without reaching the inner writer. A write larger
+/// than the remaining budget is cut short to it, so the inner writer ends up
+/// holding exactly the first `limit` bytes.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, LimitedWriter, Write};
+///
+/// let mut slot = LimitedWriter::new(Vec::new(), 4);
+/// assert_eq!(slot.write_all(b"too long"), Err(Error::LimitExceeded));
+/// assert_eq!(slot.remaining(), 0);
+/// assert_eq!(slot.into_inner(), b"too ");
+/// ```
+#[derive(Debug)]
+pub struct LimitedWriter<W> {
+    inner: W,
+    remaining: u64,
+}
+
+impl<W> LimitedWriter<W> {
+    /// Wraps `inner`, allowing `limit` more bytes to be written to it.
+    pub const fn new(inner: W, limit: u64) -> Self {
+        Self { inner, remaining: limit }
+    }
+
+    /// Returns the number of bytes that can still be written.
+    pub const fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Sets the number of bytes that can still be written.
+    pub fn set_remaining(&mut self, remaining: u64) {
+        self.remaining = remaining;
+    }
+
+    /// Returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns a shared reference to the inner writer.
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for LimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            return Err(Error::LimitExceeded);
+        }
+        let max = usize::try_from(self.remaining).map_or(buf.len(), |rem| rem.min(buf.len()));
+        let n = self.inner.write(&buf[..max])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
 }
 
 /// `no_std`-compatible write trait used by streaming interfaces.
 ///
 /// This trait mirrors the core behavior of `std::io::Write`.
+///
+/// As with [`Read`], every `std::io::Write` type implements this trait with
+/// the `std` feature, and other types of your own can implement it directly.
 pub trait Write {
     /// Writes bytes from `buf`, returning the number of bytes written.
     fn write(&mut self, buf: &[u8]) -> Result<usize>;
@@ -39,24 +509,121 @@ pub trait Write {
     fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
         default_write_all(self, &mut buf)
     }
+
+    /// Writes bytes from a sequence of buffers, returning the number of bytes
+    /// written.
+    ///
+    /// The buffers are written in order, as if concatenated. The default
+    /// implementation calls `write` for each non-empty buffer in turn and
+    /// stops at the first short write; an error is returned only if nothing
+    /// was written.
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        default_write_vectored(self, bufs)
+    }
+}
+
+/// Size of the stack buffer used by [`copy`].
+const COPY_BUF_LEN: usize = 8 * 1024;
+
+/// Copies everything from `reader` into `writer`, returning the number of
+/// bytes copied.
+///
+/// Data goes through an 8 KiB stack buffer; use [`copy_with_buffer`] to
+/// supply a buffer of another size. `Error::Interrupted` from either side is
+/// retried; any other error is returned, with the bytes copied so far already
+/// written. `writer` is not flushed.
+///
+/// # Example
+///
+/// ```
+/// let mut input: &[u8] = b"copied";
+/// let mut output = Vec::new();
+/// assert_eq!(lzf_rust::copy(&mut input, &mut output).unwrap(), 6);
+/// assert_eq!(output, b"copied");
+/// ```
+pub fn copy<R: Read + ?Sized, W: Write + ?Sized>(reader: &mut R, writer: &mut W) -> Result<u64> {
+    let mut buf = [0u8; COPY_BUF_LEN];
+    copy_with_buffer(reader, writer, &mut buf)
+}
+
+/// Copies everything from `reader` into `writer` through `buf`, returning
+/// the number of bytes copied.
+///
+/// Behaves like [`copy`], reading at most `buf.len()` bytes at a time.
+/// Returns `Error::InvalidParameter` if `buf` is empty.
+pub fn copy_with_buffer<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    buf: &mut [u8],
+) -> Result<u64> {
+    if buf.is_empty() {
+        return Err(Error::InvalidParameter);
+    }
+
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(Error::Interrupted) => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
 }
 
 #[inline]
-fn default_read_exact<R: Read + ?Sized>(this: &mut R, mut buf: &mut [u8]) -> Result<()> {
-    while !buf.is_empty() {
-        match this.read(buf) {
+fn default_read_exact<R: Read + ?Sized>(this: &mut R, buf: &mut [u8]) -> Result<()> {
+    if default_read_exact_or_eof(this, buf)? == buf.len() { Ok(()) } else { Err(Error::Eof) }
+}
+
+#[inline]
+fn default_read_exact_or_eof<R: Read + ?Sized>(this: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0usize;
+    while filled < buf.len() {
+        match this.read(&mut buf[filled..]) {
             Ok(0) => break,
-            Ok(n) => buf = &mut buf[n..],
+            Ok(n) => filled += n,
             Err(Error::Interrupted) => {}
             Err(e) => return Err(e),
         }
     }
+    Ok(filled)
+}
 
-    if buf.is_empty() { Ok(()) } else { Err(Error::Eof) }
+/// First and largest read sizes of `default_read_to_end`.
+const READ_TO_END_MIN_CHUNK: usize = 32;
+const READ_TO_END_MAX_CHUNK: usize = 64 * 1024;
+
+fn default_read_to_end<R: Read + ?Sized>(this: &mut R, buf: &mut Vec<u8>) -> Result<usize> {
+    let start = buf.len();
+    let mut chunk = READ_TO_END_MIN_CHUNK;
+    loop {
+        let len = buf.len();
+        buf.resize(len + chunk, 0);
+        match this.read(&mut buf[len..]) {
+            Ok(0) => {
+                buf.truncate(len);
+                return Ok(len - start);
+            }
+            Ok(n) => {
+                buf.truncate(len + n);
+                if n == chunk {
+                    chunk = (chunk * 2).min(READ_TO_END_MAX_CHUNK);
+                }
+            }
+            Err(Error::Interrupted) => buf.truncate(len),
+            Err(e) => {
+                buf.truncate(len);
+                return Err(e);
+            }
+        }
+    }
 }
 
 #[inline]
-fn default_write_all<W: Write + ?Sized>(this: &mut W, buf: &mut &[u8]) -> Result<()> {
+pub(crate) fn default_write_all<W: Write + ?Sized>(this: &mut W, buf: &mut &[u8]) -> Result<()> {
     while !buf.is_empty() {
         match this.write(buf) {
             Ok(0) => return Err(Error::WriteZero),
@@ -68,6 +635,23 @@ fn default_write_all<W: Write + ?Sized>(this: &mut W, buf: &mut &[u8]) -> Result
     Ok(())
 }
 
+fn default_write_vectored<W: Write + ?Sized>(this: &mut W, bufs: &[IoSlice<'_>]) -> Result<usize> {
+    let mut total = 0usize;
+    for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+        match this.write(buf) {
+            Ok(n) => {
+                total += n;
+                if n < buf.len() {
+                    break;
+                }
+            }
+            Err(e) if total == 0 => return Err(e),
+            Err(_) => break,
+        }
+    }
+    Ok(total)
+}
+
 #[cfg(not(feature = "std"))]
 impl<R: Read + ?Sized> Read for &mut R {
     #[inline(always)]
@@ -79,6 +663,16 @@ impl<R: Read + ?Sized> Read for &mut R {
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
         (**self).read_exact(buf)
     }
+
+    #[inline(always)]
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (**self).read_exact_or_eof(buf)
+    }
+
+    #[inline(always)]
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        (**self).read_to_end(buf)
+    }
 }
 
 #[cfg(not(feature = "std"))]
@@ -92,6 +686,11 @@ impl<W: Write + ?Sized> Write for &mut W {
     fn flush(&mut self) -> Result<()> {
         (**self).flush()
     }
+
+    #[inline(always)]
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        (**self).write_vectored(bufs)
+    }
 }
 
 #[cfg(not(feature = "std"))]
@@ -144,6 +743,33 @@ impl Write for Vec<u8> {
     }
 }
 
+#[cfg(not(feature = "std"))]
+impl Read for alloc::collections::VecDeque<u8> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let (front, back) = self.as_slices();
+        let n_front = front.len().min(buf.len());
+        buf[..n_front].copy_from_slice(&front[..n_front]);
+        let n_back = back.len().min(buf.len() - n_front);
+        buf[n_front..n_front + n_back].copy_from_slice(&back[..n_back]);
+        self.drain(..n_front + n_back);
+        Ok(n_front + n_back)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Write for alloc::collections::VecDeque<u8> {
+    #[inline(always)]
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.extend(buf);
+        Ok(buf.len())
+    }
+
+    #[inline(always)]
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(not(feature = "std"))]
 impl<R: Read + ?Sized> Read for alloc::boxed::Box<R> {
     #[inline(always)]
@@ -155,6 +781,16 @@ impl<R: Read + ?Sized> Read for alloc::boxed::Box<R> {
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
         (**self).read_exact(buf)
     }
+
+    #[inline(always)]
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<usize> {
+        (**self).read_exact_or_eof(buf)
+    }
+
+    #[inline(always)]
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        (**self).read_to_end(buf)
+    }
 }
 
 #[cfg(not(feature = "std"))]
@@ -168,6 +804,11 @@ impl<W: Write + ?Sized> Write for alloc::boxed::Box<W> {
     fn flush(&mut self) -> Result<()> {
         (**self).flush()
     }
+
+    #[inline(always)]
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        (**self).write_vectored(bufs)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -181,6 +822,11 @@ impl<R: std::io::Read + ?Sized> Read for R {
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
         std::io::Read::read_exact(self, buf).map_err(Error::from)
     }
+
+    #[inline]
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
+        std::io::Read::read_to_end(self, buf).map_err(Error::from)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -199,4 +845,9 @@ impl<W: std::io::Write + ?Sized> Write for W {
     fn write_all(&mut self, buf: &[u8]) -> Result<()> {
         std::io::Write::write_all(self, buf).map_err(Error::from)
     }
+
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<usize> {
+        std::io::Write::write_vectored(self, bufs).map_err(Error::from)
+    }
 }
@@ -7,16 +7,27 @@
 //!
 //! - Raw LZF token encode/decode (`compress`/`decompress`).
 //! - `lzf` block framing support (`ZV\0`/`ZV\1`) via `encode_blocks`/`decode_blocks`.
+//!   Optional checked `ZV\2` blocks carry a CRC-32 of each block, and an
+//!   optional footer after the end-of-stream marker one of the whole stream.
 //! - Streaming adapters (`LzfReader`, `LzfWriter`) for framed streams.
 //! - `no_std`-compatible I/O traits (`LzfRead`, `LzfWrite`).
 //!
 //! Raw token compatibility matches `liblzf` (`lzf_compress`, `lzf_compress_best`,
 //! and `lzf_decompress` behavior for valid inputs).
 //!
+//! Encoder output is deterministic across platforms: the same input and
+//! `CompressionMode` produce identical bytes regardless of endianness or
+//! pointer width, so compressed data can be signed on one machine and
+//! verified on another.
+//!
 //! # Features
 //!
 //! - `std` (default): integrates with `std::io::{Read, Write}`.
 //! - `encoder` (default): enables compression APIs and `LzfWriter`.
+//! - `rayon`: `encode_blocks_parallel` and `decode_blocks_parallel` on a thread pool.
+//! - `async`: `futures-io` adapters `AsyncLzfReader` and `AsyncLzfWriter`.
+//! - `tokio`: `tokio::io` adapters `TokioLzfReader` and `TokioLzfWriter`.
+//! - `defmt`: `defmt::Format` for `Error` and `CompressionMode`.
 //!
 //! # no_std
 //!
@@ -27,7 +38,9 @@
 //! lzf-rust = { version = "0.1", default-features = false, features = ["encoder"] }
 //! ```
 //!
-//! In this mode, use crate-level `LzfRead`/`LzfWrite` traits.
+//! In this mode, use crate-level `LzfRead`/`LzfWrite` traits, `Cursor` in
+//! place of `std::io::Cursor`, and `ReadExt` in place of the `take` and
+//! `chain` methods of `std::io::Read`.
 //!
 //! # Examples
 //!
@@ -76,40 +89,172 @@
 
 extern crate alloc;
 
+#[cfg(feature = "async")]
+mod async_io;
+mod crc32;
 mod error;
 mod framed;
+mod inverse;
 mod io;
+mod nonblocking;
+#[cfg(feature = "rayon")]
+mod parallel;
 mod raw;
 mod stream;
+#[cfg(feature = "tokio")]
+mod tokio_io;
 
+#[cfg(feature = "async")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+/// `futures-io` framed LZF stream reader.
+pub use async_io::AsyncLzfReader;
+#[cfg(all(feature = "async", feature = "encoder"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "async", feature = "encoder"))))]
+/// `futures-io` framed LZF stream writer.
+pub use async_io::AsyncLzfWriter;
 /// Crate error and result types.
-pub use error::{Error, Result};
-/// Decodes `lzf` framed block streams (`ZV\0`/`ZV\1`).
-pub use framed::decode_blocks;
+pub use error::{DecodeErrorDetail, Error, FramedError, Result};
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Frames a single block (`ZV\0`/`ZV\1`).
+pub use framed::encode_block;
 #[cfg(feature = "encoder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
 /// Encodes bytes into `lzf` framed block streams (`ZV\0`/`ZV\1`).
 pub use framed::encode_blocks;
 #[cfg(feature = "encoder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Encodes bytes into a framed stream of checksummed `ZV\2` blocks.
+pub use framed::encode_blocks_checked;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Encodes bytes into a framed block stream written to a `Write` sink.
+pub use framed::encode_blocks_to_writer;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Encodes bytes into a framed block stream ending with a whole-stream footer.
+pub use framed::encode_blocks_with_footer;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
 /// Encodes bytes into framed block streams with an explicit compression mode.
 pub use framed::encode_blocks_with_mode;
+/// Upper bound for the size of a framed block stream.
+pub use framed::max_encoded_blocks_size;
+/// Checks whether bytes start like an `lzf` framed stream.
+pub use framed::starts_with_block_header;
+/// Block-level inspection of `lzf` framed streams without decompressing.
+pub use framed::{BlockStats, BlockType, FrameInfo, FrameIter, analyze_blocks, find_stream_end};
+/// Decodes `lzf` framed block streams (`ZV\0`/`ZV\1`).
+pub use framed::{
+    BlockValidation, EofMarker, decode_block, decode_blocks, decode_blocks_consumed,
+    decode_blocks_detailed, decode_blocks_into, decode_blocks_lenient, decode_blocks_multi,
+    decode_blocks_strict, decode_blocks_to_writer, decode_blocks_with_limit, decoded_len_of_blocks,
+    validate_blocks,
+};
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Encodes bytes into framed block streams with explicit encoding options.
+pub use framed::{EncodeOptions, encode_blocks_with_options};
+/// Best-effort recovery of damaged framed block streams.
+pub use framed::{SalvageReport, decode_blocks_salvage};
+/// Framed LZF decoder fed through `Write`.
+pub use inverse::LzfDecodeWriter;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Framed LZF encoder drained through `Read`.
+pub use inverse::LzfEncodeReader;
+/// Reader that reads two readers one after the other, created by `ReadExt::chain`.
+pub use io::Chain;
+/// In-memory buffer with a position (`std::io::Cursor` under `std`).
+pub use io::Cursor;
+/// Borrowed buffer for vectored writes (`std::io::IoSlice` under `std`).
+pub use io::IoSlice;
+/// Writer passing on at most a fixed number of bytes.
+pub use io::LimitedWriter;
+/// Extension methods for `Read`: `take` and `chain`.
+pub use io::ReadExt;
+/// Reader limited to a fixed number of bytes, created by `ReadExt::take`.
+pub use io::Take;
+/// Copies a reader into a writer, retrying interrupted reads and writes.
+pub use io::copy;
+/// Copies a reader into a writer through a caller-supplied buffer.
+pub use io::copy_with_buffer;
+/// Reader that is always at end of input.
+pub use io::{Empty, empty};
 /// `no_std`-compatible read/write traits used by streaming APIs.
 pub use io::{Read, Write};
 /// Alias for `Read` to mirror naming used by related compression crates.
 pub use io::{Read as LzfRead, Write as LzfWrite};
+/// Writer discarding everything written to it, counting the bytes.
+pub use io::{Sink, sink};
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+/// Decodes framed block streams on the rayon thread pool.
+pub use parallel::decode_blocks_parallel;
+#[cfg(all(feature = "rayon", feature = "encoder"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "rayon", feature = "encoder"))))]
+/// Encodes framed block streams on the rayon thread pool.
+pub use parallel::encode_blocks_parallel;
+/// Push-based raw LZF decoder for chunked input.
+pub use raw::RawDecoder;
 #[cfg(feature = "encoder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
 /// Raw LZF encoder APIs.
 pub use raw::{CompressionMode, compress, compress_best, compress_with_mode};
+/// Iterator over raw LZF tokens, for inspecting compressed streams.
+pub use raw::{Token, Tokens};
 /// Raw LZF decoder APIs.
-pub use raw::{decompress, decompress_into_vec};
+pub use raw::{
+    decompress, decompress_detailed, decompress_into_vec, decompress_partial,
+    decompress_partial_with_history, decompress_to_vec, decompress_to_vec_with_limit,
+    decompress_to_writer, decompress_windowed, decompressed_size, validate,
+};
+/// Description of a block returned by `LzfReader::read_block`.
+pub use stream::DecodedBlock;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Error of `LzfWriter::try_finish`, which returns the writer.
+pub use stream::FinishError;
 /// Framed LZF stream reader.
 pub use stream::LzfReader;
+/// Builder for `LzfReader` options.
+pub use stream::LzfReaderBuilder;
 #[cfg(feature = "encoder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
 /// Framed LZF stream writer.
 pub use stream::LzfWriter;
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Builder for `LzfWriter` options.
+pub use stream::LzfWriterBuilder;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+/// `std::io::Read` and `std::io::BufRead` adapter for `LzfReader`.
+pub use stream::StdLzfReader;
+#[cfg(all(feature = "std", feature = "encoder"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "std", feature = "encoder"))))]
+/// `std::io::Write` adapter for `LzfWriter`.
+pub use stream::StdLzfWriter;
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+/// Random-access framed LZF reader over seekable sources.
+pub use stream::{BlockIndexEntry, LzfIndexedReader};
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Detached block compression units for offloading `LzfWriter` work.
+pub use stream::{BlockJob, CompressedFrame};
+#[cfg(feature = "encoder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoder")))]
+/// Streaming transcoding of framed streams to a new block size or mode.
+pub use stream::{TranscodeStats, recompress_blocks};
+#[cfg(feature = "tokio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+/// `tokio::io` framed LZF stream reader.
+pub use tokio_io::TokioLzfReader;
+#[cfg(all(feature = "tokio", feature = "encoder"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio", feature = "encoder"))))]
+/// `tokio::io` framed LZF stream writer.
+pub use tokio_io::TokioLzfWriter;
 
 /// Maximum literal run size in the LZF format.
 pub const MAX_LITERAL_LEN: usize = 1 << 5;
@@ -128,9 +273,17 @@ pub const fn max_compressed_size(input_len: usize) -> usize {
 
 /// Internal trait used by [`AutoFinisher`] to finalize streams on drop.
 #[doc(hidden)]
-pub trait AutoFinish {
+pub trait AutoFinish: Sized {
+    /// Value returned by a successful `finish`, usually the underlying writer.
+    type Output;
+
+    /// Finalizes the wrapped stream.
+    fn finish(self) -> Result<Self::Output>;
+
     /// Finalizes the wrapped stream and ignores any returned error.
-    fn finish_ignore_error(self);
+    fn finish_ignore_error(self) {
+        let _ = AutoFinish::finish(self);
+    }
 }
 
 /// Wrapper that attempts to finish the wrapped writer on drop.
@@ -139,6 +292,20 @@ pub trait AutoFinish {
 /// early returns or panics bypass an explicit `finish()` call.
 pub struct AutoFinisher<T: AutoFinish>(pub(crate) Option<T>);
 
+impl<T: AutoFinish> AutoFinisher<T> {
+    /// Finishes the wrapped writer now and returns the result that dropping
+    /// the wrapper would discard.
+    pub fn finish(mut self) -> Result<T::Output> {
+        self.0.take().expect("AutoFinisher: inner value missing").finish()
+    }
+
+    /// Disarms the wrapper and returns the wrapped writer without finishing
+    /// it.
+    pub fn into_inner(mut self) -> T {
+        self.0.take().expect("AutoFinisher: inner value missing")
+    }
+}
+
 impl<T: AutoFinish> Drop for AutoFinisher<T> {
     fn drop(&mut self) {
         if let Some(inner) = self.0.take() {
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: ISC
+//! I/O-free framing state shared by the asynchronous adapters,
+//! `LzfDecodeWriter`, and `LzfEncodeReader`.
+//!
+//! The adapters only move bytes between these cores and the underlying
+//! reader or writer, so every partially read header or partially written
+//! frame lives here and survives a pending poll or a split write.
+#[cfg(feature = "encoder")]
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "encoder")]
+use crate::framed::{EncodeOptions, frame_block};
+use crate::framed::{decode_frame, frame_len_hint, parse_frame, verify_checksum};
+use crate::{Error, Result};
+
+/// Decoding side: assembles one frame at a time and decodes it.
+pub(crate) struct ReadCore {
+    /// Bytes of the frame being assembled; `filled` of them are valid.
+    frame: Vec<u8>,
+    filled: usize,
+    out_buf: Vec<u8>,
+    out_pos: usize,
+    finished: bool,
+}
+
+impl ReadCore {
+    pub(crate) fn new() -> Self {
+        Self { frame: Vec::new(), filled: 0, out_buf: Vec::new(), out_pos: 0, finished: false }
+    }
+
+    /// Returns decoded bytes not handed out yet.
+    pub(crate) fn available(&self) -> &[u8] {
+        &self.out_buf[self.out_pos..]
+    }
+
+    /// Marks `n` bytes of [`available`](Self::available) as handed out.
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.out_pos += n;
+    }
+
+    /// Returns the buffer the next input bytes must be read into, or `None`
+    /// after the end-of-stream marker.
+    ///
+    /// The buffer never reaches past the current frame, so no input beyond
+    /// the stream's end is consumed.
+    pub(crate) fn input_buf(&mut self) -> Result<Option<&mut [u8]>> {
+        if self.finished {
+            return Ok(None);
+        }
+        // The first byte alone tells a header from the end-of-stream marker.
+        let target = match self.filled {
+            0 => 1,
+            _ => frame_len_hint(&self.frame[..self.filled])?,
+        };
+        self.frame.resize(target, 0);
+        Ok(Some(&mut self.frame[self.filled..]))
+    }
+
+    /// Records `n` bytes read into [`input_buf`](Self::input_buf), decoding
+    /// the frame once it is complete.
+    pub(crate) fn advance(&mut self, n: usize) -> Result<()> {
+        self.filled += n;
+        if self.filled == 1 && self.frame[0] == 0 {
+            self.finished = true;
+            return Ok(());
+        }
+        if self.filled == 1 || frame_len_hint(&self.frame[..self.filled])? > self.filled {
+            return Ok(());
+        }
+
+        let frame =
+            parse_frame(&self.frame[..self.filled], 0, false)?.ok_or(Error::InvalidHeader)?;
+        self.out_buf.resize(frame.uncompressed_len, 0);
+        self.out_pos = 0;
+        let res = if frame.is_compressed() {
+            decode_frame(&frame, &mut self.out_buf)
+        } else {
+            self.out_buf.copy_from_slice(frame.payload);
+            verify_checksum(&frame, &self.out_buf)
+        };
+        if res.is_err() {
+            self.out_buf.clear();
+        }
+        self.filled = 0;
+        res
+    }
+
+    /// Returns whether the end-of-stream marker has been read.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Handles end of input: fine between blocks, `Error::Eof` inside one.
+    pub(crate) fn end_of_input(&self) -> Result<()> {
+        if self.filled == 0 || self.finished { Ok(()) } else { Err(Error::Eof) }
+    }
+}
+
+/// Encoding side: buffers input blocks and holds the frames still to be
+/// written.
+#[cfg(feature = "encoder")]
+pub(crate) struct WriteCore {
+    block_size: usize,
+    options: EncodeOptions,
+    in_buf: Vec<u8>,
+    comp_buf: Vec<u8>,
+    /// Encoded bytes not written yet, from `out_pos` on.
+    out: Vec<u8>,
+    out_pos: usize,
+    eof_marker: bool,
+    finished: bool,
+}
+
+#[cfg(feature = "encoder")]
+impl WriteCore {
+    pub(crate) fn new(block_size: usize, options: EncodeOptions, eof_marker: bool) -> Result<Self> {
+        if block_size == 0 || block_size > usize::from(u16::MAX) {
+            return Err(Error::InvalidParameter);
+        }
+        Ok(Self {
+            block_size,
+            options,
+            in_buf: Vec::with_capacity(block_size),
+            comp_buf: vec![0u8; block_size.saturating_sub(4)],
+            out: Vec::new(),
+            out_pos: 0,
+            eof_marker,
+            finished: false,
+        })
+    }
+
+    /// Returns encoded bytes still to be written.
+    pub(crate) fn pending(&self) -> &[u8] {
+        &self.out[self.out_pos..]
+    }
+
+    /// Marks `n` bytes of [`pending`](Self::pending) as written.
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.out_pos += n;
+        if self.out_pos == self.out.len() {
+            self.out.clear();
+            self.out_pos = 0;
+        }
+    }
+
+    /// Buffers input, framing the block once it is full, and returns how
+    /// many bytes of `buf` were taken.
+    ///
+    /// Fails with `Error::InvalidParameter` once the stream is finished.
+    pub(crate) fn accept(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.finished {
+            return Err(Error::InvalidParameter);
+        }
+        let take = (self.block_size - self.in_buf.len()).min(buf.len());
+        self.in_buf.extend_from_slice(&buf[..take]);
+        if self.in_buf.len() == self.block_size {
+            self.flush_block();
+        }
+        Ok(take)
+    }
+
+    /// Frames the buffered partial block, if any.
+    pub(crate) fn flush_block(&mut self) {
+        if self.in_buf.is_empty() {
+            return;
+        }
+        let (header, payload) = frame_block(&self.in_buf, &self.options, &mut self.comp_buf);
+        self.out.extend_from_slice(header.as_slice());
+        self.out.extend_from_slice(payload);
+        self.in_buf.clear();
+    }
+
+    /// Frames the last block and appends the end-of-stream marker if
+    /// enabled. Later calls do nothing.
+    pub(crate) fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.flush_block();
+        if self.eof_marker {
+            self.out.push(0);
+        }
+        self.finished = true;
+    }
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: ISC
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+#[cfg(feature = "encoder")]
+use crate::CompressionMode;
+#[cfg(feature = "encoder")]
+use crate::framed::{EncodeOptions, frame_block, max_encoded_blocks_size};
+use crate::framed::{
+    FrameInfo, StreamFooter, decode_frame, end_of_stream, parse_frame, verify_checksum,
+};
+use crate::{Error, Result};
+
+/// Blocks handed to each pool thread per batch.
+///
+/// Blocks of one batch are buffered until the whole batch is done, so at most
+/// `BLOCKS_PER_THREAD * threads` blocks are in flight at a time.
+const BLOCKS_PER_THREAD: usize = 4;
+
+/// Encodes input into `lzf` block stream format on the rayon thread pool.
+///
+/// `block_size` must be in `1..=65535`. The output is byte-identical to
+/// `encode_blocks_with_mode` with the same arguments: blocks are compressed
+/// independently and their frames stitched together in input order.
+///
+/// Input is processed in batches of a few blocks per pool thread, so besides
+/// the output itself at most a small multiple of `threads * block_size` bytes
+/// of frames are buffered at a time.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, encode_blocks_parallel, encode_blocks_with_mode};
+///
+/// let input = vec![b'p'; 100_000];
+/// let framed = encode_blocks_parallel(&input, 4096, CompressionMode::Normal).unwrap();
+/// assert_eq!(framed, encode_blocks_with_mode(&input, 4096, CompressionMode::Normal).unwrap());
+/// ```
+#[cfg(feature = "encoder")]
+pub fn encode_blocks_parallel(
+    input: &[u8],
+    block_size: usize,
+    mode: CompressionMode,
+) -> Result<Vec<u8>> {
+    if block_size == 0 || block_size > usize::from(u16::MAX) {
+        return Err(Error::InvalidParameter);
+    }
+
+    let mut output = Vec::with_capacity(max_encoded_blocks_size(input.len(), block_size));
+    let options = EncodeOptions::new().mode(mode);
+    let batch_len = rayon::current_num_threads() * BLOCKS_PER_THREAD * block_size;
+    for batch in input.chunks(batch_len) {
+        let frames: Vec<Vec<u8>> = batch
+            .par_chunks(block_size)
+            .map_init(Vec::new, |comp_buf, block| {
+                let (header, payload) = frame_block(block, &options, comp_buf);
+                let mut frame = Vec::with_capacity(header.len() + payload.len());
+                frame.extend_from_slice(header.as_slice());
+                frame.extend_from_slice(payload);
+                frame
+            })
+            .collect();
+        for frame in &frames {
+            output.extend_from_slice(frame);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Decodes a framed stream on the rayon thread pool.
+///
+/// The block headers are walked first to learn each block's payload and
+/// uncompressed length. The blocks are then decompressed in parallel in
+/// batches of a few blocks per pool thread, each directly into its own slice
+/// of the output, which grows by one batch at a time. Declared lengths are
+/// only trusted one batch ahead of the blocks decoded so far, so a stream of
+/// small blocks claiming large outputs fails without allocating them all.
+///
+/// Results and errors match `decode_blocks`, including the check of a stream
+/// footer, which runs once all blocks are decoded. When several blocks are
+/// malformed, the error of the earliest one is returned, whether it is a
+/// malformed header or a corrupt payload, which is the error the sequential
+/// decoder stops at. Returns `Error::LimitExceeded` if the declared output
+/// size does not fit in `usize`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{decode_blocks_parallel, encode_blocks};
+///
+/// let input = vec![b'd'; 100_000];
+/// let framed = encode_blocks(&input, 4096).unwrap();
+/// assert_eq!(decode_blocks_parallel(&framed).unwrap(), input);
+/// ```
+pub fn decode_blocks_parallel(input: &[u8]) -> Result<Vec<u8>> {
+    let mut frames = Vec::new();
+    let mut total = 0usize;
+    let mut ip = 0usize;
+    // A malformed header stops the walk; blocks before it still decide the
+    // error if one of them is corrupt.
+    let header_error = loop {
+        match parse_frame(input, ip, false) {
+            Ok(Some(frame)) => {
+                total = total.checked_add(frame.uncompressed_len).ok_or(Error::LimitExceeded)?;
+                ip = frame.end();
+                frames.push(frame);
+            }
+            Ok(None) => break None,
+            Err(err) => break Some(err),
+        }
+    };
+
+    let mut output = Vec::new();
+    let batch_len = rayon::current_num_threads() * BLOCKS_PER_THREAD;
+    for batch in frames.chunks(batch_len) {
+        let start = output.len();
+        output.resize(start + batch.iter().map(|frame| frame.uncompressed_len).sum::<usize>(), 0);
+        let mut rest = &mut output[start..];
+        let mut jobs = Vec::with_capacity(batch.len());
+        for frame in batch {
+            let (block, tail) = core::mem::take(&mut rest).split_at_mut(frame.uncompressed_len);
+            jobs.push((frame, block));
+            rest = tail;
+        }
+
+        let first_error = jobs
+            .into_par_iter()
+            .enumerate()
+            .filter_map(|(index, (frame, block))| {
+                decode_into(frame, block).err().map(|e| (index, e))
+            })
+            .min_by_key(|&(index, _)| index);
+        if let Some((_, err)) = first_error {
+            return Err(err);
+        }
+    }
+
+    if let Some(err) = header_error {
+        return Err(err);
+    }
+    end_of_stream(input, ip, || StreamFooter::of(&output))?;
+    Ok(output)
+}
+
+/// Decodes the payload of `frame` into `block`, its slice of the output.
+fn decode_into(frame: &FrameInfo<'_>, block: &mut [u8]) -> Result<()> {
+    if frame.is_compressed() {
+        decode_frame(frame, block)
+    } else {
+        block.copy_from_slice(frame.payload);
+        verify_checksum(frame, block)
+    }
+}
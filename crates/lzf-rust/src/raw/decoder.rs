@@ -2,7 +2,7 @@
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::{Error, Result};
+use crate::{DecodeErrorDetail, Error, MAX_OFFSET, Result, Write};
 
 /// Decompresses raw LZF `input` into `output`.
 ///
@@ -28,19 +28,569 @@ use crate::{Error, Result};
 /// assert_eq!(out, input);
 /// ```
 pub fn decompress(input: &[u8], output: &mut [u8]) -> Result<usize> {
+    decode_tokens(input, 0, SliceSink(output)).map_err(|stop| stop.error)
+}
+
+/// Like [`decompress`], reporting where in the stream decoding failed.
+///
+/// On failure the returned [`DecodeErrorDetail`] carries the same error kind
+/// `decompress` returns, the input offset of the failing token's control
+/// byte, and the number of bytes decoded before it.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, decompress_detailed};
+///
+/// // A valid literal, then a literal run cut short.
+/// let mut out = [0u8; 16];
+/// let err = decompress_detailed(&[0x00, b'a', 0x03, b'b'], &mut out).unwrap_err();
+/// assert_eq!(err.kind, Error::InvalidData);
+/// assert_eq!((err.input_offset, err.output_offset), (2, 1));
+/// ```
+pub fn decompress_detailed(
+    input: &[u8],
+    output: &mut [u8],
+) -> core::result::Result<usize, DecodeErrorDetail> {
+    decode_tokens(input, 0, SliceSink(output)).map_err(|stop| DecodeErrorDetail {
+        kind: stop.error,
+        input_offset: stop.ip,
+        output_offset: stop.op,
+    })
+}
+
+/// Fully validates raw LZF `input` without producing output.
+///
+/// Performs exactly the checks `decompress` performs, including rejecting
+/// back-references that reach before the start of the output, by tracking the
+/// output position instead of writing bytes. Returns the decoded length.
+///
+/// `validate` accepts exactly the streams that `decompress` accepts given a
+/// large enough output buffer, and rejects the others with the same error.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Error, validate};
+///
+/// assert_eq!(validate(&[0x01, b'h', b'i']), Ok(2));
+/// // Back-reference before the start of the output.
+/// assert_eq!(validate(&[0x20, 0x00]), Err(Error::InvalidData));
+/// ```
+pub fn validate(input: &[u8]) -> Result<usize> {
+    decode_tokens(input, 0, CountingSink).map_err(|stop| stop.error)
+}
+
+/// Decompresses as many whole tokens of raw LZF `input` as fit in `output`.
+///
+/// Returns `(input_consumed, output_written)`. Decoding stops before the first
+/// token whose decoded bytes do not fit in the rest of `output`; a token is
+/// never split across calls. The caller continues by passing
+/// `&input[input_consumed..]` and a fresh output buffer to
+/// [`decompress_partial_with_history`], supplying the previously decoded
+/// bytes (at least the last `MAX_OFFSET` of them) as history.
+///
+/// No single token decodes to more than `MAX_MATCH_LEN` bytes, so an output
+/// buffer at least that large always makes progress on a non-empty input.
+///
+/// Returns `Error::InvalidData` when the token stream is malformed.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{compress, decompress_partial, max_compressed_size};
+///
+/// let input = [b'x'; 1000];
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(&input, &mut compressed).unwrap();
+///
+/// let mut out = [0u8; 300];
+/// let (consumed, written) = decompress_partial(&compressed[..clen], &mut out).unwrap();
+/// assert!(consumed < clen);
+/// assert!(written > 0 && written <= out.len());
+/// ```
+pub fn decompress_partial(input: &[u8], output: &mut [u8]) -> Result<(usize, usize)> {
+    decompress_partial_with_history(input, &[], output)
+}
+
+/// Like [`decompress_partial`], continuing a stream whose earlier output is
+/// `history`.
+///
+/// Back-references may reach into `history`, which must hold the output that
+/// immediately precedes `output` (the last `MAX_OFFSET` bytes suffice). An
+/// empty `history` means `input` starts a new stream.
+pub fn decompress_partial_with_history(
+    input: &[u8],
+    history: &[u8],
+    output: &mut [u8],
+) -> Result<(usize, usize)> {
+    let history = &history[history.len().saturating_sub(MAX_OFFSET)..];
+    let sink = HistorySink { history, output };
+    match decode_tokens(input, history.len(), sink) {
+        Ok(op) => Ok((input.len(), op - history.len())),
+        Err(stop) if stop.overflow => Ok((stop.ip, stop.op - history.len())),
+        Err(stop) => Err(stop.error),
+    }
+}
+
+/// Decompresses raw LZF `input`, streaming the decoded bytes into `out`.
+///
+/// Only the most recent `MAX_OFFSET` bytes of output (the furthest a
+/// back-reference can reach) plus a small staging area are held in memory,
+/// so arbitrarily large payloads can be decoded into files or sockets.
+///
+/// Returns the total number of bytes written.
+///
+/// Returns `Error::InvalidData` when the token stream is malformed; errors
+/// from `out` are returned unchanged. Output preceding a malformed token may
+/// already have been written.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{compress, decompress_to_writer, max_compressed_size};
+///
+/// let input = b"to the writer, to the writer";
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(input, &mut compressed).unwrap();
+///
+/// let mut out = Vec::new();
+/// let written = decompress_to_writer(&compressed[..clen], &mut out).unwrap();
+/// assert_eq!(written, input.len() as u64);
+/// assert_eq!(out, input);
+/// ```
+pub fn decompress_to_writer<W: Write + ?Sized>(input: &[u8], out: &mut W) -> Result<u64> {
+    let mut sink = WriterSink { out, buf: Vec::with_capacity(WRITER_SINK_BUF), flushed: 0 };
+    let written = decode_tokens(input, 0, &mut sink).map_err(|stop| stop.error)?;
+    sink.flush(false)?;
+    Ok(written as u64)
+}
+
+/// Decompresses raw LZF `input` through a caller-provided `window`, writing
+/// the decoded bytes to `out`.
+///
+/// `window` is used as a ring buffer holding the last `MAX_OFFSET` bytes of
+/// output, the furthest a back-reference can reach. Decoded bytes are handed
+/// to `out` whenever the ring is about to wrap over unwritten data, and at
+/// the end. This function never allocates, so it suits targets that can
+/// spare a fixed 8 KiB buffer but not the full decoded size.
+///
+/// Returns the total number of bytes written.
+///
+/// Returns `Error::InvalidData` when the token stream is malformed; errors
+/// from `out` are returned unchanged. Output preceding a malformed token may
+/// already have been written.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{MAX_OFFSET, compress, decompress_windowed, max_compressed_size};
+///
+/// let input = b"windowed windowed windowed";
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(input, &mut compressed).unwrap();
+///
+/// let mut window = [0u8; MAX_OFFSET];
+/// let mut out = Vec::new();
+/// let written = decompress_windowed(&compressed[..clen], &mut window, &mut out).unwrap();
+/// assert_eq!(written, input.len() as u64);
+/// assert_eq!(out, input);
+/// ```
+pub fn decompress_windowed<W: Write + ?Sized>(
+    input: &[u8],
+    window: &mut [u8; MAX_OFFSET],
+    out: &mut W,
+) -> Result<u64> {
+    let mut sink = RingSink { window, out, flushed: 0 };
+    let written = decode_tokens(input, 0, &mut sink).map_err(|stop| stop.error)?;
+    sink.flush(written)?;
+    Ok(written as u64)
+}
+
+/// Destination for decoded tokens.
+///
+/// `decode_tokens` performs all structural checks and bounds checks against
+/// `capacity` before calling into the sink, so implementations only move
+/// bytes (and report failures of their own, such as writer errors).
+trait Sink {
+    /// Whether back-references reaching before the start of the output are
+    /// rejected. Only sinks that merely measure the output skip the check.
+    const CHECK_REACH: bool = true;
+
+    /// Total number of bytes the sink can accept.
+    fn capacity(&self) -> usize;
+
+    /// Appends literal `bytes` at output position `op`.
+    fn literal(&mut self, op: usize, bytes: &[u8]) -> Result<()>;
+
+    /// Appends `len` bytes copied from output position `ref_pos` at `op`.
+    ///
+    /// `ref_pos < op` and `op - ref_pos <= MAX_OFFSET`; source and
+    /// destination may overlap. Not called for back-references reaching
+    /// before the start of the output when `CHECK_REACH` is `false`.
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()>;
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    const CHECK_REACH: bool = S::CHECK_REACH;
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        (**self).capacity()
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, op: usize, bytes: &[u8]) -> Result<()> {
+        (**self).literal(op, bytes)
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        (**self).backref(op, ref_pos, len)
+    }
+}
+
+/// Sink writing into a caller-provided slice.
+struct SliceSink<'a>(&'a mut [u8]);
+
+impl Sink for SliceSink<'_> {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, op: usize, bytes: &[u8]) -> Result<()> {
+        self.0[op..op + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        let output = &mut *self.0;
+        if len <= 8 {
+            let mut dst = op;
+            let mut src = ref_pos;
+            let end = dst + len;
+            while dst < end {
+                output[dst] = output[src];
+                dst += 1;
+                src += 1;
+            }
+        } else if ref_pos + len <= op {
+            let (head, tail) = output.split_at_mut(op);
+            tail[..len].copy_from_slice(&head[ref_pos..ref_pos + len]);
+        } else if op - ref_pos == 1 {
+            let b = output[ref_pos];
+            output[op..op + len].fill(b);
+        } else {
+            // The output repeats the `op - ref_pos` bytes at `ref_pos`; copy
+            // the already-written repetitions forward, doubling each time.
+            let mut dst = op;
+            let end = op + len;
+            while dst < end {
+                let n = (dst - ref_pos).min(end - dst);
+                output.copy_within(ref_pos..ref_pos + n, dst);
+                dst += n;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sink writing into a slice that continues the output in `history`.
+///
+/// Output positions count from the start of `history`.
+struct HistorySink<'a> {
+    history: &'a [u8],
+    output: &'a mut [u8],
+}
+
+impl Sink for HistorySink<'_> {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        self.history.len() + self.output.len()
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, op: usize, bytes: &[u8]) -> Result<()> {
+        let at = op - self.history.len();
+        self.output[at..at + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        let base = self.history.len();
+        for i in 0..len {
+            let src = ref_pos + i;
+            self.output[op - base + i] =
+                if src < base { self.history[src] } else { self.output[src - base] };
+        }
+        Ok(())
+    }
+}
+
+/// Sink that only tracks the output position.
+struct CountingSink;
+
+impl Sink for CountingSink {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, _op: usize, _bytes: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, _op: usize, _ref_pos: usize, _len: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink only measuring the output, without checking that back-references
+/// reach decoded bytes.
+struct SizeSink;
+
+impl Sink for SizeSink {
+    const CHECK_REACH: bool = false;
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, _op: usize, _bytes: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, _op: usize, _ref_pos: usize, _len: usize) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink appending to a `Vec` that may grow up to `max_len` bytes.
+struct VecSink {
+    output: Vec<u8>,
+    max_len: usize,
+}
+
+impl VecSink {
+    /// Makes room for `additional` more bytes, growing geometrically but never
+    /// past `max_len`.
+    #[inline(always)]
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.output.len() + additional;
+        if needed > self.output.capacity() {
+            let target = needed.saturating_mul(2).min(self.max_len).max(needed);
+            self.output.reserve_exact(target - self.output.len());
+        }
+    }
+}
+
+impl Sink for VecSink {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        self.max_len
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, _op: usize, bytes: &[u8]) -> Result<()> {
+        self.reserve(bytes.len());
+        self.output.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        self.reserve(len);
+        if ref_pos + len <= op {
+            self.output.extend_from_within(ref_pos..ref_pos + len);
+        } else {
+            for src in ref_pos..ref_pos + len {
+                let b = self.output[src];
+                self.output.push(b);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Buffered bytes at which `WriterSink` hands output to its writer.
+const WRITER_SINK_BUF: usize = 8 * MAX_OFFSET;
+
+/// Sink streaming output to a writer, keeping only the most recent
+/// `MAX_OFFSET` bytes for back-reference resolution.
+struct WriterSink<'a, W: Write + ?Sized> {
+    out: &'a mut W,
+    /// Most recent output, ending at the current output position.
+    buf: Vec<u8>,
+    /// Bytes of `buf` already written to `out`.
+    flushed: usize,
+}
+
+impl<W: Write + ?Sized> WriterSink<'_, W> {
+    /// Writes pending output and, once the buffer is full, slides it down to
+    /// the back-reference window.
+    fn flush(&mut self, slide: bool) -> Result<()> {
+        self.out.write_all(&self.buf[self.flushed..])?;
+        self.flushed = self.buf.len();
+        if slide && self.buf.len() > MAX_OFFSET {
+            let excess = self.buf.len() - MAX_OFFSET;
+            self.buf.copy_within(excess.., 0);
+            self.buf.truncate(MAX_OFFSET);
+            self.flushed = MAX_OFFSET;
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn make_room(&mut self, len: usize) -> Result<()> {
+        if self.buf.len() + len > WRITER_SINK_BUF { self.flush(true) } else { Ok(()) }
+    }
+}
+
+impl<W: Write + ?Sized> Sink for WriterSink<'_, W> {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, _op: usize, bytes: &[u8]) -> Result<()> {
+        self.make_room(bytes.len())?;
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        self.make_room(len)?;
+        let src = self.buf.len() - (op - ref_pos);
+        if src + len <= self.buf.len() {
+            self.buf.extend_from_within(src..src + len);
+        } else {
+            for i in src..src + len {
+                let b = self.buf[i];
+                self.buf.push(b);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sink decoding into a fixed `MAX_OFFSET`-byte ring buffer, handing bytes
+/// to a writer before they are overwritten.
+struct RingSink<'a, W: Write + ?Sized> {
+    window: &'a mut [u8; MAX_OFFSET],
+    out: &'a mut W,
+    /// Output position up to which bytes have been written to `out`.
+    flushed: usize,
+}
+
+impl<W: Write + ?Sized> RingSink<'_, W> {
+    /// Writes the output between `flushed` and `op` to `out`.
+    fn flush(&mut self, op: usize) -> Result<()> {
+        if op == self.flushed {
+            return Ok(());
+        }
+        let start = self.flushed % MAX_OFFSET;
+        let end = op % MAX_OFFSET;
+        if start < end {
+            self.out.write_all(&self.window[start..end])?;
+        } else {
+            self.out.write_all(&self.window[start..])?;
+            self.out.write_all(&self.window[..end])?;
+        }
+        self.flushed = op;
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn make_room(&mut self, op: usize, len: usize) -> Result<()> {
+        if op + len - self.flushed > MAX_OFFSET { self.flush(op) } else { Ok(()) }
+    }
+}
+
+impl<W: Write + ?Sized> Sink for RingSink<'_, W> {
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+
+    #[inline(always)]
+    fn literal(&mut self, op: usize, bytes: &[u8]) -> Result<()> {
+        self.make_room(op, bytes.len())?;
+        let at = op % MAX_OFFSET;
+        let head = bytes.len().min(MAX_OFFSET - at);
+        self.window[at..at + head].copy_from_slice(&bytes[..head]);
+        self.window[..bytes.len() - head].copy_from_slice(&bytes[head..]);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn backref(&mut self, op: usize, ref_pos: usize, len: usize) -> Result<()> {
+        self.make_room(op, len)?;
+        // Byte-wise so overlapping copies repeat, and a source exactly
+        // `MAX_OFFSET` back is read before its slot is overwritten.
+        for i in 0..len {
+            self.window[(op + i) % MAX_OFFSET] = self.window[(ref_pos + i) % MAX_OFFSET];
+        }
+        Ok(())
+    }
+}
+
+/// Where and why `decode_tokens` stopped before the end of its input.
+#[derive(Clone, Copy, Debug)]
+struct Stop {
+    error: Error,
+    /// Input offset of the control byte of the token that was not decoded.
+    ip: usize,
+    /// Output position at which that token would have been written.
+    op: usize,
+    /// The token was well-formed so far but did not fit in the sink.
+    overflow: bool,
+}
+
+impl Stop {
+    #[cold]
+    fn new(error: Error, ip: usize, op: usize, overflow: bool) -> Self {
+        Self { error, ip, op, overflow }
+    }
+}
+
+/// Walks the token stream in `input`, validating every token and handing the
+/// decoded bytes to `sink`, starting at output position `op`.
+///
+/// Returns the final output position.
+#[inline(always)]
+fn decode_tokens<S: Sink>(
+    input: &[u8],
+    mut op: usize,
+    mut sink: S,
+) -> core::result::Result<usize, Stop> {
+    let capacity = sink.capacity();
     let mut ip = 0usize;
-    let mut op = 0usize;
 
     while ip < input.len() {
+        let token = ip;
         let ctrl = input[ip];
         ip += 1;
 
         if ctrl < 32 {
             let len = usize::from(ctrl) + 1;
-            if ip + len > input.len() || op + len > output.len() {
-                return Err(Error::InvalidData);
+            if ip + len > input.len() {
+                return Err(Stop::new(Error::InvalidData, token, op, false));
+            }
+            if op + len > capacity {
+                return Err(Stop::new(Error::OutputTooSmall, token, op, true));
             }
-            output[op..op + len].copy_from_slice(&input[ip..ip + len]);
+            sink.literal(op, &input[ip..ip + len]).map_err(|e| Stop::new(e, token, op, false))?;
             ip += len;
             op += len;
             continue;
@@ -50,58 +600,59 @@ pub fn decompress(input: &[u8], output: &mut [u8]) -> Result<usize> {
         let off_hi = usize::from(ctrl & 0x1f) << 8;
         if len == 7 {
             if ip >= input.len() {
-                return Err(Error::InvalidData);
+                return Err(Stop::new(Error::InvalidData, token, op, false));
             }
             len += usize::from(input[ip]);
             ip += 1;
         }
 
         if ip >= input.len() {
-            return Err(Error::InvalidData);
+            return Err(Stop::new(Error::InvalidData, token, op, false));
         }
 
         let off = off_hi | usize::from(input[ip]);
         ip += 1;
 
         let copy_len = len + 2;
-        if op + copy_len > output.len() {
-            return Err(Error::OutputTooSmall);
+        if op + copy_len > capacity {
+            return Err(Stop::new(Error::OutputTooSmall, token, op, true));
         }
-        if off >= op {
-            return Err(Error::InvalidData);
-        }
-
-        let ref_pos = op - off - 1;
-        if copy_len <= 8 {
-            let mut dst = op;
-            let mut src = ref_pos;
-            let end = dst + copy_len;
-            while dst < end {
-                output[dst] = output[src];
-                dst += 1;
-                src += 1;
-            }
-            op += copy_len;
-        } else if ref_pos + copy_len <= op {
-            let (head, tail) = output.split_at_mut(op);
-            tail[..copy_len].copy_from_slice(&head[ref_pos..ref_pos + copy_len]);
-            op += copy_len;
-        } else {
-            let mut dst = op;
-            let mut src = ref_pos;
-            let end = dst + copy_len;
-            while dst < end {
-                output[dst] = output[src];
-                dst += 1;
-                src += 1;
-            }
-            op += copy_len;
+        if off < op {
+            sink.backref(op, op - off - 1, copy_len).map_err(|e| Stop::new(e, token, op, false))?;
+        } else if S::CHECK_REACH {
+            return Err(Stop::new(Error::InvalidData, token, op, false));
         }
+        op += copy_len;
     }
 
     Ok(op)
 }
 
+/// Computes the decoded length of raw LZF `input` without producing output.
+///
+/// Every token is checked for structural validity: literal runs must fit in
+/// the input and back-references must have all their bytes present. Because
+/// no output exists, back-reference distances are not checked against the
+/// bytes decoded so far, which makes this cheaper than [`validate`]; use that
+/// for full validation. For every stream `decompress` accepts, the result is
+/// the length it decodes.
+///
+/// Returns `Error::InvalidData` when the token stream is truncated.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{compress, decompressed_size, max_compressed_size};
+///
+/// let input = b"size size size size size";
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(input, &mut compressed).unwrap();
+/// assert_eq!(decompressed_size(&compressed[..clen]).unwrap(), input.len());
+/// ```
+pub fn decompressed_size(input: &[u8]) -> Result<usize> {
+    decode_tokens(input, 0, SizeSink).map_err(|stop| stop.error)
+}
+
 /// Decompresses raw LZF `input` into a fresh `Vec<u8>` of `output_len` bytes.
 ///
 /// Returns `Error::InvalidData` if the stream decodes to a length different
@@ -114,3 +665,44 @@ pub fn decompress_into_vec(input: &[u8], output_len: usize) -> Result<Vec<u8>> {
     }
     Ok(output)
 }
+
+/// Decompresses raw LZF `input` into a new `Vec<u8>` without knowing the
+/// decoded length in advance.
+///
+/// The output grows geometrically as tokens are decoded. Use
+/// [`decompress_to_vec_with_limit`] for untrusted input.
+///
+/// Returns `Error::InvalidData` when the token stream is malformed.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{compress, decompress_to_vec, max_compressed_size};
+///
+/// let input = b"grow grow grow grow grow";
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(input, &mut compressed).unwrap();
+///
+/// let out = decompress_to_vec(&compressed[..clen]).unwrap();
+/// assert_eq!(out, input);
+/// ```
+pub fn decompress_to_vec(input: &[u8]) -> Result<Vec<u8>> {
+    decompress_to_vec_with_limit(input, usize::MAX)
+}
+
+/// Decompresses raw LZF `input` into a new `Vec<u8>` of at most `max_len`
+/// bytes.
+///
+/// Returns:
+/// - `Error::InvalidData` when the token stream is malformed.
+/// - `Error::LimitExceeded` as soon as a token would grow the output past
+///   `max_len`; nothing beyond `max_len` bytes is ever allocated.
+pub fn decompress_to_vec_with_limit(input: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    let output = Vec::with_capacity(input.len().saturating_mul(2).min(max_len));
+    let mut sink = VecSink { output, max_len };
+    match decode_tokens(input, 0, &mut sink) {
+        Ok(_) => Ok(sink.output),
+        Err(stop) if stop.overflow => Err(Error::LimitExceeded),
+        Err(stop) => Err(stop.error),
+    }
+}
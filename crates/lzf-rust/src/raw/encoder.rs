@@ -8,7 +8,17 @@ const HASH_SIZE: usize = 1 << HASH_LOG;
 const HASH_BEST_SIZE: usize = 1 << HASH_LOG;
 
 /// Encoder mode for raw LZF compression.
+///
+/// # Determinism
+///
+/// For a given input and mode, the encoder produces the same bytes on every
+/// target, independent of endianness and pointer width: input bytes are
+/// combined with explicit shifts rather than word loads, and match-finder
+/// tables store positions as `u32` values with defined wrap-around. Inputs
+/// larger than 4 GiB can only be compressed on 64-bit targets, where the
+/// result is equally well defined.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum CompressionMode {
     /// Fast/liblzf default mode (`lzf_compress`).
     Normal,
@@ -32,6 +42,20 @@ fn hash_best3(input: &[u8], index: usize) -> usize {
         & (HASH_BEST_SIZE - 1)
 }
 
+/// Resolves a Best-mode chain head entry relative to `pos`.
+///
+/// Entries hold `position + 1` truncated to `u32` (0 means empty), so the
+/// table contents do not depend on the target's pointer width. The entry is
+/// resolved to the most recent position before `pos` with those low bits.
+#[inline]
+fn best_chain_head(pos: usize, entry: u32) -> Option<usize> {
+    if entry == 0 {
+        return None;
+    }
+    let back = (pos as u32).wrapping_sub(entry - 1);
+    if back == 0 { None } else { Some(pos - back as usize) }
+}
+
 #[inline]
 fn emit_literals(
     input: &[u8],
@@ -159,8 +183,8 @@ fn compress_best_impl(input: &[u8], output: &mut [u8]) -> Result<usize> {
         return Ok(0);
     }
 
-    // liblzf stores pointers; we store index+1 (0 == null).
-    let mut first = [0usize; HASH_BEST_SIZE];
+    // liblzf stores pointers; we store index+1 as u32 (0 == null).
+    let mut first = [0u32; HASH_BEST_SIZE];
     let mut prev = [0u16; MAX_OFFSET];
 
     let in_len = input.len();
@@ -170,24 +194,21 @@ fn compress_best_impl(input: &[u8], output: &mut [u8]) -> Result<usize> {
 
     while pos + 2 < in_len {
         let hash = hash_best3(input, pos);
-        let prev_head = first[hash];
+        let head = best_chain_head(pos, first[hash]);
         let slot = pos & (MAX_OFFSET - 1);
 
-        prev[slot] = if prev_head == 0 {
-            0
-        } else {
-            let p = prev_head - 1;
-            (pos - p).min(usize::from(u16::MAX)) as u16
+        prev[slot] = match head {
+            None => 0,
+            Some(p) => (pos - p).min(usize::from(u16::MAX)) as u16,
         };
-        first[hash] = pos + 1;
+        first[hash] = (pos as u32).wrapping_add(1);
 
         let mut best_len = 0usize;
         let mut best_pos = 0usize;
         let max_len = (in_len - pos).min(MAX_MATCH_LEN);
         let lower_bound = pos.saturating_sub(MAX_OFFSET);
 
-        if prev_head != 0 {
-            let mut p = prev_head - 1;
+        if let Some(mut p) = head {
             let pos0 = input[pos];
             let pos1 = input[pos + 1];
             let pos2 = input[pos + 2];
@@ -231,15 +252,12 @@ fn compress_best_impl(input: &[u8], output: &mut [u8]) -> Result<usize> {
             while scan + 2 < end {
                 let h = hash_best3(input, scan);
                 let s = scan & (MAX_OFFSET - 1);
-                let head = first[h];
 
-                prev[s] = if head == 0 {
-                    0
-                } else {
-                    let p = head - 1;
-                    (scan - p).min(usize::from(u16::MAX)) as u16
+                prev[s] = match best_chain_head(scan, first[h]) {
+                    None => 0,
+                    Some(p) => (scan - p).min(usize::from(u16::MAX)) as u16,
                 };
-                first[h] = scan + 1;
+                first[h] = (scan as u32).wrapping_add(1);
                 scan += 1;
             }
 
@@ -5,7 +5,15 @@
 mod decoder;
 #[cfg(feature = "encoder")]
 mod encoder;
+mod push;
+mod tokens;
 
-pub use decoder::{decompress, decompress_into_vec};
+pub use decoder::{
+    decompress, decompress_detailed, decompress_into_vec, decompress_partial,
+    decompress_partial_with_history, decompress_to_vec, decompress_to_vec_with_limit,
+    decompress_to_writer, decompress_windowed, decompressed_size, validate,
+};
 #[cfg(feature = "encoder")]
 pub use encoder::{CompressionMode, compress, compress_best, compress_with_mode};
+pub use push::RawDecoder;
+pub use tokens::{Token, Tokens};
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: ISC
+use alloc::vec::Vec;
+
+use crate::{Error, MAX_OFFSET, Result};
+
+/// Window size at which decoded bytes are handed to the caller mid-push.
+const SPILL_THRESHOLD: usize = 8 * MAX_OFFSET;
+
+/// Position within the token stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    /// Expecting a control byte.
+    Control,
+    /// Copying a literal run; the value is the number of bytes still due.
+    Literal(usize),
+    /// Long back-reference waiting for its length byte.
+    Length { off_hi: usize },
+    /// Back-reference waiting for its offset byte.
+    Offset { len: usize, off_hi: usize },
+}
+
+/// Push-based raw LZF decoder for token streams that arrive in pieces.
+///
+/// Compressed bytes are fed with [`push`](Self::push) in chunks of any size;
+/// control bytes, literal runs, and back-references may be split across
+/// chunks. The decoder keeps the last `MAX_OFFSET` bytes of output so
+/// back-references resolve across pushes. Call [`finish`](Self::finish) after
+/// the last chunk to detect a stream that ends mid-token.
+///
+/// The decoded output and error variants match one-shot `decompress` with a
+/// large enough output buffer.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{RawDecoder, compress, max_compressed_size};
+///
+/// let input = b"pushed pushed pushed pushed";
+/// let mut compressed = vec![0u8; max_compressed_size(input.len())];
+/// let clen = compress(input, &mut compressed).unwrap();
+///
+/// let mut decoder = RawDecoder::new();
+/// let mut out = Vec::new();
+/// for chunk in compressed[..clen].chunks(2) {
+///     decoder.push(chunk, &mut out).unwrap();
+/// }
+/// decoder.finish().unwrap();
+/// assert_eq!(out, input);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RawDecoder {
+    window: Vec<u8>,
+    discarded: usize,
+    state: State,
+}
+
+impl Default for RawDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RawDecoder {
+    /// Creates a decoder positioned at the start of a token stream.
+    pub fn new() -> Self {
+        Self { window: Vec::new(), discarded: 0, state: State::Control }
+    }
+
+    /// Returns the total number of decoded bytes produced so far.
+    pub fn total_out(&self) -> usize {
+        self.discarded + self.window.len()
+    }
+
+    /// Decodes `input`, appending the decoded bytes to `out`.
+    ///
+    /// Returns the number of input bytes consumed, which is always
+    /// `input.len()`: incomplete trailing tokens are buffered until the next
+    /// push.
+    ///
+    /// Returns `Error::InvalidData` for a back-reference reaching before the
+    /// start of the output. After an error the decoder must not be reused,
+    /// and the bytes appended to `out` by the failing call are unspecified.
+    pub fn push(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<usize> {
+        let mut flushed = self.window.len();
+        let mut ip = 0usize;
+
+        while ip < input.len() {
+            match self.state {
+                State::Control => {
+                    let ctrl = input[ip];
+                    ip += 1;
+                    let off_hi = usize::from(ctrl & 0x1f) << 8;
+                    self.state = match ctrl >> 5 {
+                        0 => State::Literal(usize::from(ctrl) + 1),
+                        7 => State::Length { off_hi },
+                        len => State::Offset { len: usize::from(len), off_hi },
+                    };
+                }
+                State::Literal(remaining) => {
+                    let take = remaining.min(input.len() - ip);
+                    self.window.extend_from_slice(&input[ip..ip + take]);
+                    ip += take;
+                    self.state = if take == remaining {
+                        State::Control
+                    } else {
+                        State::Literal(remaining - take)
+                    };
+                }
+                State::Length { off_hi } => {
+                    let len = 7 + usize::from(input[ip]);
+                    ip += 1;
+                    self.state = State::Offset { len, off_hi };
+                }
+                State::Offset { len, off_hi } => {
+                    let off = off_hi | usize::from(input[ip]);
+                    ip += 1;
+                    if off >= self.total_out() {
+                        return Err(Error::InvalidData);
+                    }
+
+                    let copy_len = len + 2;
+                    let ref_pos = self.window.len() - off - 1;
+                    if ref_pos + copy_len <= self.window.len() {
+                        self.window.extend_from_within(ref_pos..ref_pos + copy_len);
+                    } else {
+                        for src in ref_pos..ref_pos + copy_len {
+                            let b = self.window[src];
+                            self.window.push(b);
+                        }
+                    }
+                    self.state = State::Control;
+                }
+            }
+
+            if self.window.len() >= SPILL_THRESHOLD {
+                self.spill(out, &mut flushed);
+            }
+        }
+
+        self.spill(out, &mut flushed);
+        Ok(input.len())
+    }
+
+    /// Checks that the stream ended on a token boundary.
+    ///
+    /// Returns `Error::InvalidData` if the last push ended inside a token.
+    pub fn finish(&self) -> Result<()> {
+        if self.state == State::Control { Ok(()) } else { Err(Error::InvalidData) }
+    }
+
+    /// Hands window bytes past `flushed` to `out` and trims the window to the
+    /// back-reference history.
+    fn spill(&mut self, out: &mut Vec<u8>, flushed: &mut usize) {
+        out.extend_from_slice(&self.window[*flushed..]);
+        if self.window.len() > MAX_OFFSET {
+            let excess = self.window.len() - MAX_OFFSET;
+            self.window.drain(..excess);
+            self.discarded += excess;
+        }
+        *flushed = self.window.len();
+    }
+}
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: ISC
+use crate::{Error, Result};
+
+/// One token of a raw LZF stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    /// Literal run copied verbatim from the compressed input.
+    Literal {
+        /// Offset of the first literal byte in the compressed input.
+        input_offset: usize,
+        /// Number of literal bytes (1 to `MAX_LITERAL_LEN`).
+        len: usize,
+    },
+    /// Back-reference into previously decoded output.
+    Backref {
+        /// Distance back from the current output position (1 to `MAX_OFFSET`).
+        offset: usize,
+        /// Number of bytes copied (3 to `MAX_MATCH_LEN`).
+        len: usize,
+    },
+}
+
+impl Token {
+    /// Returns the number of decoded bytes this token produces.
+    pub fn decoded_len(&self) -> usize {
+        match *self {
+            Self::Literal { len, .. } | Self::Backref { len, .. } => len,
+        }
+    }
+}
+
+/// Iterator over the tokens of a raw LZF stream.
+///
+/// Performs the same structural checks as `decompress`, including rejecting
+/// back-references that reach before the start of the output, without
+/// producing any output. After yielding an error the iterator is exhausted.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{Token, Tokens};
+///
+/// // Literal "ab", then a 3-byte back-reference two bytes back.
+/// let tokens: Vec<Token> = Tokens::new(&[0x01, b'a', b'b', 0x20, 0x01])
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(
+///     tokens,
+///     [Token::Literal { input_offset: 1, len: 2 }, Token::Backref { offset: 2, len: 3 }]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    input: &'a [u8],
+    ip: usize,
+    op: usize,
+    failed: bool,
+}
+
+impl<'a> Tokens<'a> {
+    /// Creates an iterator over the tokens of `input`.
+    pub fn new(input: &'a [u8]) -> Self {
+        Self { input, ip: 0, op: 0, failed: false }
+    }
+
+    /// Returns the input offset of the next token's control byte.
+    pub fn input_pos(&self) -> usize {
+        self.ip
+    }
+
+    /// Returns the number of bytes decoded by the tokens yielded so far.
+    pub fn output_pos(&self) -> usize {
+        self.op
+    }
+
+    fn next_token(&mut self) -> Result<Token> {
+        let input = self.input;
+        let mut ip = self.ip;
+        let ctrl = input[ip];
+        ip += 1;
+
+        let token = if ctrl < 32 {
+            let len = usize::from(ctrl) + 1;
+            if input.len() - ip < len {
+                return Err(Error::InvalidData);
+            }
+            let token = Token::Literal { input_offset: ip, len };
+            ip += len;
+            token
+        } else {
+            let mut len = usize::from(ctrl >> 5);
+            if len == 7 {
+                let extra = *input.get(ip).ok_or(Error::InvalidData)?;
+                len += usize::from(extra);
+                ip += 1;
+            }
+            let lo = *input.get(ip).ok_or(Error::InvalidData)?;
+            ip += 1;
+
+            let offset = (usize::from(ctrl & 0x1f) << 8 | usize::from(lo)) + 1;
+            if offset > self.op {
+                return Err(Error::InvalidData);
+            }
+            Token::Backref { offset, len: len + 2 }
+        };
+
+        self.ip = ip;
+        self.op += token.decoded_len();
+        Ok(token)
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.ip >= self.input.len() {
+            return None;
+        }
+        let item = self.next_token();
+        self.failed = item.is_err();
+        Some(item)
+    }
+}
+
+impl core::iter::FusedIterator for Tokens<'_> {}
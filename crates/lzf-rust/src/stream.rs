@@ -1,24 +1,41 @@
 // SPDX-License-Identifier: ISC
+#[cfg(feature = "encoder")]
+use alloc::boxed::Box;
+#[cfg(feature = "encoder")]
+use alloc::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "encoder")]
 use alloc::vec;
 use alloc::vec::Vec;
 
+#[cfg(feature = "encoder")]
+use crate::CompressionMode;
 use crate::decompress;
+use crate::framed::{BlockType, FOOTER_LEN, FOOTER_MAGIC, StreamFooter};
 #[cfg(feature = "encoder")]
-use crate::{AutoFinish, AutoFinisher, Error, Result, Write};
+use crate::framed::{EncodeOptions, frame_block};
+#[cfg(feature = "encoder")]
+use crate::io::default_write_all;
 #[cfg(feature = "encoder")]
-use crate::{CompressionMode, compress_with_mode};
-use crate::{Read, Result as DecodeResult};
+use crate::{AutoFinish, AutoFinisher, Error, Result, Write};
+use crate::{FramedError, Read, Result as DecodeResult};
 
 const MAGIC_0: u8 = b'Z';
 const MAGIC_1: u8 = b'V';
 const TYPE_UNCOMPRESSED: u8 = 0;
 const TYPE_COMPRESSED: u8 = 1;
+const TYPE_CHECKED: u8 = 2;
 
 /// Reader that decodes framed LZF (`ZV` block stream).
 ///
 /// The reader consumes blocks lazily and yields decompressed bytes through the
 /// crate's `Read` trait.
 ///
+/// Interrupted reads of the underlying reader are retried. Running out of
+/// input between blocks ends the stream only for the current read, so a
+/// source that grows can be read again later; the `0` end-of-stream marker
+/// is final. A read that fails after producing some bytes returns those
+/// bytes, and the error is returned by the next read.
+///
 /// # Example
 ///
 /// ```
@@ -39,12 +56,182 @@ pub struct LzfReader<R: Read> {
     out_buf: Vec<u8>,
     out_pos: usize,
     finished: bool,
+    eof_marker: bool,
+    skip_unknown: bool,
+    zero_copy_bytes: u64,
+    /// Framed bytes consumed from `inner` by complete headers and payloads.
+    in_pos: u64,
+    /// Index and input offset of the block being read.
+    block_index: u64,
+    block_offset: u64,
+    last_error: Option<FramedError>,
+    /// Running footer of the decoded bytes when footer checking is enabled.
+    footer: Option<StreamFooter>,
+    /// Largest accepted block payload or decoded length.
+    max_block_len: usize,
+    /// Largest accepted total decoded length, and the length decoded so far.
+    max_total_len: u64,
+    decoded_len: u64,
+    /// Decoded bytes handed to callers so far.
+    delivered_len: u64,
+    concatenated: bool,
+    /// Byte read past a marker that starts the next concatenated member.
+    pending: Option<u8>,
+    /// Kind of the block held in `out_buf`.
+    out_block: DecodedBlock,
+    /// Error hit by a `read` that had already produced data, returned by the
+    /// next `read`.
+    deferred_error: Option<crate::Error>,
+}
+
+/// Describes a block returned by [`LzfReader::read_block`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedBlock {
+    /// Number of decoded bytes returned for the block.
+    pub len: usize,
+    /// Kind of the block header.
+    pub block_type: BlockType,
+    /// Whether the payload was stored LZF-compressed rather than verbatim.
+    pub compressed: bool,
+}
+
+/// Parsed `ZV` block header.
+#[derive(Clone, Copy)]
+struct BlockHeader {
+    /// Payload length for compressed blocks, `None` for uncompressed blocks.
+    compressed_len: Option<usize>,
+    uncompressed_len: usize,
+    /// CRC-32 of the decoded block for checked `ZV\2` blocks.
+    checksum: Option<u32>,
+}
+
+impl BlockHeader {
+    /// Describes the block as returned by [`LzfReader::read_block`].
+    fn describe(&self) -> DecodedBlock {
+        let block_type = match (self.checksum, self.compressed_len) {
+            (Some(_), _) => BlockType::Checked,
+            (None, Some(_)) => BlockType::Compressed,
+            (None, None) => BlockType::Uncompressed,
+        };
+        DecodedBlock {
+            len: self.uncompressed_len,
+            block_type,
+            compressed: self.compressed_len.is_some(),
+        }
+    }
 }
 
 impl<R: Read> LzfReader<R> {
     /// Creates a new framed LZF reader.
     pub fn new(inner: R) -> Self {
-        Self { inner, in_buf: Vec::new(), out_buf: Vec::new(), out_pos: 0, finished: false }
+        Self {
+            inner,
+            in_buf: Vec::new(),
+            out_buf: Vec::new(),
+            out_pos: 0,
+            finished: false,
+            eof_marker: false,
+            skip_unknown: false,
+            zero_copy_bytes: 0,
+            in_pos: 0,
+            block_index: 0,
+            block_offset: 0,
+            last_error: None,
+            footer: None,
+            max_block_len: usize::MAX,
+            max_total_len: u64::MAX,
+            decoded_len: 0,
+            delivered_len: 0,
+            concatenated: false,
+            pending: None,
+            out_block: DecodedBlock {
+                len: 0,
+                block_type: BlockType::Uncompressed,
+                compressed: false,
+            },
+            deferred_error: None,
+        }
+    }
+
+    /// Creates a framed LZF reader whose internal buffers are preallocated
+    /// for blocks of up to `block_size_hint` bytes (clamped to 65535).
+    ///
+    /// Decoding a stream whose blocks are no larger than the hint then never
+    /// grows the buffers, whereas [`new`](Self::new) sizes them lazily from
+    /// the first blocks read. Together with [`reset`](Self::reset), a reader
+    /// can decode any number of such streams without allocating.
+    pub fn with_capacity(inner: R, block_size_hint: usize) -> Self {
+        let capacity = block_size_hint.min(usize::from(u16::MAX));
+        let mut this = Self::new(inner);
+        this.in_buf = Vec::with_capacity(capacity);
+        this.out_buf = Vec::with_capacity(capacity);
+        this
+    }
+
+    /// Creates a framed LZF reader that refuses oversized input.
+    ///
+    /// Every block header is checked before anything is allocated for the
+    /// block: a block whose payload or decoded length exceeds
+    /// `max_block_decoded` bytes, or whose decoded length would take the
+    /// stream's total beyond `max_total_decoded` bytes, fails the read with
+    /// `Error::LimitExceeded`. The internal buffers therefore never grow past
+    /// `max_block_decoded` bytes each, however large the blocks a hostile
+    /// stream declares. In lenient mode, skipped unknown blocks are subject to
+    /// the block limit too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{Error, LzfRead, LzfReader, encode_blocks};
+    ///
+    /// let framed = encode_blocks(&[7u8; 10_000], 4096).unwrap();
+    /// let mut reader = LzfReader::with_limits(framed.as_slice(), 4096, 8192);
+    /// let mut out = vec![0u8; 10_000];
+    /// assert_eq!(reader.read_exact(&mut out), Err(Error::LimitExceeded));
+    /// ```
+    pub fn with_limits(inner: R, max_block_decoded: usize, max_total_decoded: u64) -> Self {
+        let mut this = Self::new(inner);
+        this.max_block_len = max_block_decoded;
+        this.max_total_len = max_total_decoded;
+        this
+    }
+
+    /// Starts decoding a new stream from `inner` and returns the previous
+    /// underlying reader.
+    ///
+    /// The reader then behaves like a freshly created one with the same
+    /// settings (lenient, concatenated, and footer-check modes and limits):
+    /// buffered data, counters, and [`last_error`](Self::last_error) are
+    /// cleared. The internal buffers keep their capacity, so decoding many
+    /// short streams with one reader does not allocate per stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{LzfRead, LzfReader, encode_blocks};
+    ///
+    /// let first = encode_blocks(b"first stream", 4096).unwrap();
+    /// let second = encode_blocks(b"second stream", 4096).unwrap();
+    /// let mut reader = LzfReader::new(first.as_slice());
+    /// let mut out = [0u8; 12];
+    /// reader.read_exact(&mut out).unwrap();
+    /// assert_eq!(&out, b"first stream");
+    ///
+    /// let rest = reader.reset(second.as_slice());
+    /// assert!(rest.is_empty());
+    /// let mut out = [0u8; 13];
+    /// reader.read_exact(&mut out).unwrap();
+    /// assert_eq!(&out, b"second stream");
+    /// ```
+    pub fn reset(&mut self, inner: R) -> R {
+        self.reposition(0, 0, 0);
+        self.in_buf.clear();
+        self.zero_copy_bytes = 0;
+        self.last_error = None;
+        if self.footer.is_some() {
+            self.footer = Some(StreamFooter::new());
+        }
+        core::mem::replace(&mut self.inner, inner)
     }
 
     /// Unwraps the reader and returns the underlying reader.
@@ -62,71 +249,180 @@ impl<R: Read> LzfReader<R> {
         &mut self.inner
     }
 
-    fn load_next_block(&mut self) -> DecodeResult<bool> {
-        if self.finished {
-            return Ok(false);
-        }
+    /// Switches the reader to lenient decoding.
+    ///
+    /// Blocks of unknown type are then skipped instead of failing with
+    /// `Error::UnknownBlockType`, using the same `ZV\0` length convention as
+    /// `decode_blocks_lenient`.
+    pub fn enable_lenient(&mut self) {
+        self.skip_unknown = true;
+    }
 
-        let mut first = [0u8; 1];
-        let n = self.inner.read(&mut first)?;
-        if n == 0 || first[0] == 0 {
-            self.finished = true;
-            return Ok(false);
-        }
+    /// Switches the reader to decoding concatenated streams.
+    ///
+    /// The reader then continues past each `0` end-of-stream marker and only
+    /// reports end of stream once the underlying reader is exhausted, like
+    /// `decode_blocks_multi`. Runs of consecutive markers are skipped, as is
+    /// the footer after a member's marker; with footer checking enabled each
+    /// member's footer is also checked against that member's output. Data
+    /// after the last member that does not start a new stream fails with
+    /// `Error::InvalidHeader` or `Error::UnknownBlockType` instead of being
+    /// ignored.
+    pub fn enable_concatenated(&mut self) {
+        self.concatenated = true;
+    }
 
-        let mut rest = [0u8; 4];
-        self.inner.read_exact(&mut rest)?;
+    /// Switches the reader to verifying stream footers.
+    ///
+    /// The reader then keeps a running CRC-32 of the decoded bytes and, after
+    /// the `0` end-of-stream marker, reads the footer written by
+    /// `encode_blocks_with_footer` and checks it, failing with
+    /// `Error::ChecksumMismatch` on a mismatch. A stream ending right after
+    /// the marker still decodes, but outside concatenated mode anything else
+    /// following the marker must be a complete footer: other data fails with
+    /// `Error::InvalidData`, and a cut-off footer with `Error::Eof`.
+    ///
+    /// Footer checking is off by default, since a reader cannot know in
+    /// advance whether a footer follows and would otherwise checksum every
+    /// stream, and since it consumes input beyond the marker. Call this
+    /// before the first read.
+    pub fn enable_footer_check(&mut self) {
+        self.footer = Some(StreamFooter::new());
+    }
 
-        if first[0] != MAGIC_0 || rest[0] != MAGIC_1 {
-            return Err(crate::Error::InvalidHeader);
-        }
+    /// Returns the position of the most recent error returned by this reader.
+    ///
+    /// The [`FramedError`] carries the error kind together with the index of
+    /// the block being read when it occurred and the offset of that block's
+    /// header in the framed input, counted from where the reader started.
+    /// Returns `None` if no read has failed yet.
+    pub fn last_error(&self) -> Option<FramedError> {
+        self.last_error
+    }
+
+    /// Returns the number of framed bytes consumed from the underlying reader.
+    ///
+    /// Block headers, end-of-stream markers, and footers are counted along
+    /// with payloads. A block whose payload has not been fully read yet is not.
+    pub fn compressed_bytes_read(&self) -> u64 {
+        self.in_pos
+    }
+
+    /// Returns the number of decoded bytes returned to the caller so far.
+    ///
+    /// Bytes of a decoded block that are still buffered for the next read are
+    /// not counted.
+    pub fn decompressed_bytes_read(&self) -> u64 {
+        self.delivered_len
+    }
+
+    /// Returns the number of blocks read so far, including unknown blocks
+    /// skipped in lenient mode.
+    pub fn blocks_read(&self) -> u64 {
+        self.block_index
+    }
+
+    /// Returns the number of decoded bytes that were written straight into
+    /// caller buffers by `read` or [`read_into_exact`](Self::read_into_exact),
+    /// bypassing the internal block buffer.
+    ///
+    /// Instrumentation for the tests and benchmarks, not part of the public
+    /// API.
+    #[doc(hidden)]
+    pub fn zero_copy_bytes(&self) -> u64 {
+        self.zero_copy_bytes
+    }
+
+    /// Returns the combined capacity of the reader's internal payload and
+    /// block buffers.
+    ///
+    /// Instrumentation for the tests, not part of the public API.
+    #[doc(hidden)]
+    pub fn buffered_capacity(&self) -> usize {
+        self.in_buf.capacity() + self.out_buf.capacity()
+    }
 
-        let block_type = rest[1];
-        match block_type {
-            TYPE_UNCOMPRESSED => {
-                let us = usize::from(u16::from_be_bytes([rest[2], rest[3]]));
-                self.out_buf.resize(us, 0);
-                self.inner.read_exact(&mut self.out_buf)?;
-                self.out_pos = 0;
-                Ok(true)
+    /// Reads exactly `buf.len()` decoded bytes into `buf`.
+    ///
+    /// Whole blocks that fit in the remaining part of `buf` are decoded (or,
+    /// for uncompressed blocks, read) directly into it. Only a block that
+    /// straddles the end of `buf` goes through the internal block buffer, so
+    /// at most one block per call is copied; its remainder is served to the
+    /// next read.
+    ///
+    /// Returns `Err(Error::Eof)` if the stream ends before `buf` is filled.
+    /// The contents of `buf` are unspecified on error.
+    pub fn read_into_exact(&mut self, buf: &mut [u8]) -> DecodeResult<()> {
+        let res = self.read_into_exact_inner(buf);
+        self.record(res)
+    }
+
+    fn read_into_exact_inner(&mut self, mut buf: &mut [u8]) -> DecodeResult<()> {
+        while !buf.is_empty() {
+            if self.out_pos < self.out_buf.len() {
+                let take = buf.len().min(self.out_buf.len() - self.out_pos);
+                buf[..take].copy_from_slice(&self.out_buf[self.out_pos..self.out_pos + take]);
+                self.out_pos += take;
+                self.delivered_len += take as u64;
+                buf = &mut buf[take..];
+                continue;
             }
-            TYPE_COMPRESSED => {
-                let cs = usize::from(u16::from_be_bytes([rest[2], rest[3]]));
-                let mut us_buf = [0u8; 2];
-                self.inner.read_exact(&mut us_buf)?;
-                let us = usize::from(u16::from_be_bytes(us_buf));
-
-                self.in_buf.resize(cs, 0);
-                self.inner.read_exact(&mut self.in_buf)?;
-
-                self.out_buf.resize(us, 0);
-                let written = decompress(&self.in_buf, &mut self.out_buf)?;
-                if written != us {
-                    return Err(crate::Error::InvalidData);
-                }
-                self.out_pos = 0;
-                Ok(true)
+
+            self.out_buf.clear();
+            self.out_pos = 0;
+            let Some(header) = self.read_block_header()? else {
+                return Err(crate::Error::Eof);
+            };
+
+            let us = header.uncompressed_len;
+            if us <= buf.len() {
+                let (head, tail) = core::mem::take(&mut buf).split_at_mut(us);
+                self.read_block_direct(header, head)?;
+                buf = tail;
+            } else {
+                self.fill_out_buf(header)?;
             }
-            other => Err(crate::Error::UnknownBlockType(other)),
         }
+        Ok(())
     }
-}
 
-impl<R: Read> Read for LzfReader<R> {
-    fn read(&mut self, buf: &mut [u8]) -> DecodeResult<usize> {
-        if buf.is_empty() {
-            return Ok(0);
-        }
+    /// Discards up to `n` decoded bytes and returns how many were discarded.
+    ///
+    /// Buffered bytes are dropped without copying, and blocks entirely
+    /// covered by the skip are decoded into the internal block buffer only
+    /// (checksums and footers are still verified) instead of into a scratch
+    /// buffer of the caller. The count is short only at end of stream.
+    /// Skipped bytes count towards
+    /// [`decompressed_bytes_read`](Self::decompressed_bytes_read).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{LzfRead, LzfReader, encode_blocks};
+    ///
+    /// let input: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+    /// let framed = encode_blocks(&input, 4096).unwrap();
+    /// let mut reader = LzfReader::new(framed.as_slice());
+    /// assert_eq!(reader.skip(9_000).unwrap(), 9_000);
+    /// let mut rest = [0u8; 1_000];
+    /// reader.read_exact(&mut rest).unwrap();
+    /// assert_eq!(rest, input[9_000..]);
+    /// assert_eq!(reader.skip(1).unwrap(), 0);
+    /// ```
+    pub fn skip(&mut self, n: u64) -> DecodeResult<u64> {
+        let res = self.skip_inner(n);
+        self.record(res)
+    }
 
-        let mut written = 0usize;
-        while written < buf.len() {
+    fn skip_inner(&mut self, n: u64) -> DecodeResult<u64> {
+        let mut skipped = 0u64;
+        while skipped < n {
             if self.out_pos < self.out_buf.len() {
-                let avail = self.out_buf.len() - self.out_pos;
-                let take = (buf.len() - written).min(avail);
-                buf[written..written + take]
-                    .copy_from_slice(&self.out_buf[self.out_pos..self.out_pos + take]);
-                self.out_pos += take;
-                written += take;
+                let avail = (self.out_buf.len() - self.out_pos) as u64;
+                let take = avail.min(n - skipped);
+                self.out_pos += take as usize;
+                self.delivered_len += take;
+                skipped += take;
                 continue;
             }
 
@@ -136,193 +432,1686 @@ impl<R: Read> Read for LzfReader<R> {
                 break;
             }
         }
-
-        Ok(written)
+        Ok(skipped)
     }
-}
 
-/// Writer that encodes framed LZF (`ZV` block stream).
-///
-/// Data written into this adapter is chunked into blocks and emitted as either
-/// compressed or uncompressed `ZV` blocks.
-#[cfg(feature = "encoder")]
-pub struct LzfWriter<W: Write> {
-    inner: W,
-    block_size: usize,
-    mode: CompressionMode,
-    in_buf: Vec<u8>,
-    comp_buf: Vec<u8>,
-    write_eof_marker: bool,
-}
+    /// Decodes the block described by `header` straight into `out`, a caller
+    /// buffer of exactly the block's decoded length.
+    fn read_block_direct(&mut self, header: BlockHeader, out: &mut [u8]) -> DecodeResult<()> {
+        Self::read_block_body(&mut self.inner, &mut self.in_buf, header, out)?;
+        if let Some(footer) = &mut self.footer {
+            footer.update(out);
+        }
+        self.end_block(header);
+        self.zero_copy_bytes += out.len() as u64;
+        self.delivered_len += out.len() as u64;
+        Ok(())
+    }
 
-#[cfg(feature = "encoder")]
-impl<W: Write> LzfWriter<W> {
-    /// Creates a new framed LZF writer with the given block size (`1..=65535`).
-    pub fn new(inner: W, block_size: usize) -> Result<Self> {
-        Self::new_with_mode(inner, block_size, CompressionMode::Normal)
+    /// Reads the next block, replacing the contents of `out` with its decoded
+    /// bytes.
+    ///
+    /// Unlike `read`, this preserves the block boundaries of the stream, for
+    /// streams that carry one record per block: each call returns exactly one
+    /// block, including empty ones, decoded straight into `out`. If `read`
+    /// left part of a block unread, the rest of that block is returned first.
+    /// Returns `None` at end of stream.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{CompressionMode, LzfReader, encode_block};
+    ///
+    /// let mut framed = Vec::new();
+    /// for record in [&b"first record"[..], b"", &[b'x'; 300]] {
+    ///     framed.extend(encode_block(record, CompressionMode::Normal).unwrap());
+    /// }
+    /// let mut reader = LzfReader::new(framed.as_slice());
+    /// let mut record = Vec::new();
+    /// let mut lens = Vec::new();
+    /// while let Some(block) = reader.read_block(&mut record).unwrap() {
+    ///     lens.push((block.len, block.compressed));
+    /// }
+    /// assert_eq!(lens, [(12, false), (0, false), (300, true)]);
+    /// ```
+    pub fn read_block(&mut self, out: &mut Vec<u8>) -> DecodeResult<Option<DecodedBlock>> {
+        let res = self.read_block_inner(out);
+        self.record(res)
     }
 
-    /// Creates a new framed LZF writer with an explicit compression mode.
-    pub fn new_with_mode(inner: W, block_size: usize, mode: CompressionMode) -> Result<Self> {
-        if block_size == 0 || block_size > usize::from(u16::MAX) {
-            return Err(Error::InvalidParameter);
+    fn read_block_inner(&mut self, out: &mut Vec<u8>) -> DecodeResult<Option<DecodedBlock>> {
+        out.clear();
+        if self.out_pos < self.out_buf.len() {
+            out.extend_from_slice(&self.out_buf[self.out_pos..]);
+            self.delivered_len += out.len() as u64;
+            self.out_pos = self.out_buf.len();
+            return Ok(Some(DecodedBlock { len: out.len(), ..self.out_block }));
         }
-        Ok(Self {
-            inner,
-            block_size,
-            mode,
-            in_buf: Vec::with_capacity(block_size),
-            comp_buf: vec![0u8; block_size.saturating_sub(4)],
-            write_eof_marker: false,
-        })
-    }
 
-    /// Creates a writer and enables writing a trailing zero byte EOF marker on finish.
-    ///
-    /// The marker matches the historical `lzf` utility stream behavior.
-    pub fn new_with_eof_marker(inner: W, block_size: usize) -> Result<Self> {
-        Self::new_with_eof_marker_and_mode(inner, block_size, CompressionMode::Normal)
+        let Some(header) = self.read_block_header()? else {
+            return Ok(None);
+        };
+        out.resize(header.uncompressed_len, 0);
+        if let Err(err) = self.read_block_direct(header, out) {
+            out.clear();
+            return Err(err);
+        }
+        Ok(Some(header.describe()))
     }
 
-    /// Creates a writer and enables writing a trailing zero byte EOF marker on finish.
-    ///
-    /// Compression mode is explicitly selected.
-    pub fn new_with_eof_marker_and_mode(
-        inner: W,
-        block_size: usize,
-        mode: CompressionMode,
-    ) -> Result<Self> {
-        let mut this = Self::new_with_mode(inner, block_size, mode)?;
-        this.write_eof_marker = true;
-        Ok(this)
+    fn load_next_block(&mut self) -> DecodeResult<bool> {
+        let Some(header) = self.read_block_header()? else {
+            return Ok(false);
+        };
+        self.fill_out_buf(header)?;
+        Ok(true)
     }
 
-    /// Unwraps the writer and returns the underlying writer.
-    pub fn into_inner(self) -> W {
-        self.inner
+    /// Decodes the block described by `header` into the internal block buffer.
+    fn fill_out_buf(&mut self, header: BlockHeader) -> DecodeResult<()> {
+        self.out_buf.resize(header.uncompressed_len, 0);
+        self.out_pos = 0;
+        self.out_block = header.describe();
+        let res =
+            Self::read_block_body(&mut self.inner, &mut self.in_buf, header, &mut self.out_buf);
+        match res {
+            Ok(()) => {
+                if let Some(footer) = &mut self.footer {
+                    footer.update(&self.out_buf);
+                }
+                self.end_block(header);
+            }
+            Err(_) => self.out_buf.clear(),
+        }
+        res
     }
 
-    /// Returns a shared reference to the underlying writer.
-    pub fn inner(&self) -> &W {
-        &self.inner
+    /// Advances the block position past the block described by `header`.
+    fn end_block(&mut self, header: BlockHeader) {
+        self.in_pos += header.compressed_len.unwrap_or(header.uncompressed_len) as u64;
+        self.decoded_len += header.uncompressed_len as u64;
+        self.block_index += 1;
     }
 
-    /// Returns a mutable reference to the underlying writer.
-    pub fn inner_mut(&mut self) -> &mut W {
-        &mut self.inner
+    /// Restarts block parsing after the underlying reader was moved to the
+    /// header of block `block_index`, at framed offset `in_pos` and decoded
+    /// offset `decoded_len`.
+    fn reposition(&mut self, in_pos: u64, block_index: u64, decoded_len: u64) {
+        self.out_buf.clear();
+        self.out_pos = 0;
+        self.finished = false;
+        self.eof_marker = false;
+        self.pending = None;
+        self.deferred_error = None;
+        self.in_pos = in_pos;
+        self.block_offset = in_pos;
+        self.block_index = block_index;
+        self.decoded_len = decoded_len;
+        self.delivered_len = decoded_len;
     }
 
-    /// Finishes the stream and returns the underlying writer.
-    ///
-    /// This flushes any pending input block. If EOF marker mode is enabled, a
-    /// trailing zero byte is appended after the final block.
-    pub fn finish(mut self) -> Result<W> {
-        self.flush_pending()?;
-        if self.write_eof_marker {
-            self.inner.write_all(&[0])?;
+    /// Records the position of a failed read for [`last_error`](Self::last_error).
+    fn record<T>(&mut self, res: DecodeResult<T>) -> DecodeResult<T> {
+        if let Err(kind) = res {
+            self.last_error = Some(FramedError {
+                kind,
+                block_index: usize::try_from(self.block_index).unwrap_or(usize::MAX),
+                input_offset: usize::try_from(self.block_offset).unwrap_or(usize::MAX),
+            });
         }
-        self.inner.flush()?;
-        Ok(self.inner)
+        res
     }
 
-    /// Returns a wrapper that will call `finish()` on drop.
-    ///
-    /// This is useful for best-effort stream finalization in scopes with early
-    /// returns.
-    pub fn auto_finish(self) -> AutoFinisher<Self> {
-        AutoFinisher(Some(self))
+    /// Reads the next block header, returning `None` at end of stream.
+    fn read_block_header(&mut self) -> DecodeResult<Option<BlockHeader>> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+
+            self.block_offset = self.in_pos;
+            // The lead byte comes first on its own, so that a `0` marker
+            // ends the stream without reading anything after it.
+            let mut head = [0u8; 5];
+            let lead = match self.pending.take() {
+                Some(byte) => {
+                    head[0] = byte;
+                    1
+                }
+                None => self.inner.read_exact_or_eof(&mut head[..1])?,
+            };
+            if lead == 0 {
+                // Not final: a growing source may deliver more blocks to
+                // later reads.
+                return Ok(None);
+            }
+            if head[0] == 0 {
+                self.eof_marker = true;
+                self.in_pos += 1;
+                // Concatenated members may carry footers even when they are
+                // not checked; those are skipped.
+                let decoded = self.footer;
+                if decoded.is_some() || self.concatenated {
+                    self.block_offset = self.in_pos;
+                    self.read_footer(decoded)?;
+                }
+                if decoded.is_some() {
+                    self.footer = Some(StreamFooter::new());
+                }
+                if self.concatenated {
+                    continue;
+                }
+                self.finished = true;
+                return Ok(None);
+            }
+            self.eof_marker = false;
+
+            // Bytes that cannot start a header are reported as such even
+            // when the input ends before a full header.
+            let filled = 1 + self.inner.read_exact_or_eof(&mut head[1..])?;
+            if head[0] != MAGIC_0 || (filled > 1 && head[1] != MAGIC_1) {
+                return Err(crate::Error::InvalidHeader);
+            }
+            if filled < head.len() {
+                return Err(crate::Error::Eof);
+            }
+            self.in_pos += 5;
+
+            let block_type = head[2];
+            let len = usize::from(u16::from_be_bytes([head[3], head[4]]));
+            let header = match block_type {
+                TYPE_UNCOMPRESSED => {
+                    BlockHeader { compressed_len: None, uncompressed_len: len, checksum: None }
+                }
+                TYPE_COMPRESSED => {
+                    let mut us_buf = [0u8; 2];
+                    self.read_header_tail(&mut us_buf)?;
+                    let us = usize::from(u16::from_be_bytes(us_buf));
+                    BlockHeader { compressed_len: Some(len), uncompressed_len: us, checksum: None }
+                }
+                TYPE_CHECKED => {
+                    let mut tail = [0u8; 6];
+                    self.read_header_tail(&mut tail)?;
+                    let us = usize::from(u16::from_be_bytes([tail[0], tail[1]]));
+                    let crc = u32::from_be_bytes([tail[2], tail[3], tail[4], tail[5]]);
+                    BlockHeader {
+                        compressed_len: (len != us).then_some(len),
+                        uncompressed_len: us,
+                        checksum: Some(crc),
+                    }
+                }
+                _ if self.skip_unknown => {
+                    // Unknown blocks follow the `ZV\0` layout; discard the payload.
+                    if len > self.max_block_len {
+                        return Err(crate::Error::LimitExceeded);
+                    }
+                    self.in_buf.resize(len, 0);
+                    self.inner.read_exact(&mut self.in_buf)?;
+                    self.in_pos += len as u64;
+                    self.block_index += 1;
+                    continue;
+                }
+                other => return Err(crate::Error::UnknownBlockType(other)),
+            };
+
+            let payload_len = header.compressed_len.unwrap_or(header.uncompressed_len);
+            if payload_len.max(header.uncompressed_len) > self.max_block_len
+                || header.uncompressed_len as u64 > self.max_total_len - self.decoded_len
+            {
+                return Err(crate::Error::LimitExceeded);
+            }
+            return Ok(Some(header));
+        }
     }
 
-    fn flush_pending(&mut self) -> Result<()> {
-        if !self.in_buf.is_empty() {
-            Self::write_block_into(&mut self.inner, self.mode, &mut self.comp_buf, &self.in_buf)?;
-            self.in_buf.clear();
+    /// Reads the part of a header that follows its first five bytes, failing
+    /// with `Error::Eof` if the input ends first.
+    fn read_header_tail(&mut self, buf: &mut [u8]) -> DecodeResult<()> {
+        if self.inner.read_exact_or_eof(buf)? < buf.len() {
+            return Err(crate::Error::Eof);
         }
+        self.in_pos += buf.len() as u64;
         Ok(())
     }
 
-    fn write_block_into(
-        inner: &mut W,
-        mode: CompressionMode,
-        comp_buf: &mut Vec<u8>,
-        block: &[u8],
-    ) -> Result<()> {
-        let max_try = block.len().saturating_sub(4);
-        if max_try > 0 {
-            if comp_buf.len() < max_try {
-                comp_buf.resize(max_try, 0);
-            }
-            match compress_with_mode(block, &mut comp_buf[..max_try], mode) {
-                Ok(cs) => {
-                    let cs_u16 =
-                        u16::try_from(cs).map_err(|_| Error::InvalidParameter)?.to_be_bytes();
-                    let us_u16 = u16::try_from(block.len())
-                        .map_err(|_| Error::InvalidParameter)?
-                        .to_be_bytes();
-                    inner.write_all(&[MAGIC_0, MAGIC_1, TYPE_COMPRESSED])?;
-                    inner.write_all(&cs_u16)?;
-                    inner.write_all(&us_u16)?;
-                    inner.write_all(&comp_buf[..cs])?;
-                    return Ok(());
+    /// Reads the footer following the end-of-stream marker, if there is one,
+    /// and checks it against `decoded`.
+    ///
+    /// In concatenated mode, a byte that cannot start a footer is kept for
+    /// [`read_block_header`](Self::read_block_header) as the start of the
+    /// next member.
+    /// Reads the footer after an end-of-stream marker, if there is one, and
+    /// checks it against `decoded` unless that is `None`.
+    fn read_footer(&mut self, decoded: Option<StreamFooter>) -> DecodeResult<()> {
+        let mut bytes = [0u8; FOOTER_LEN];
+        if self.inner.read_exact_or_eof(&mut bytes[..1])? == 0 {
+            return Ok(());
+        }
+        if self.concatenated && bytes[0] != FOOTER_MAGIC[0] {
+            self.pending = Some(bytes[0]);
+            return Ok(());
+        }
+        let filled = 1 + self.inner.read_exact_or_eof(&mut bytes[1..])?;
+        self.in_pos += filled as u64;
+        match StreamFooter::parse(&bytes[..filled]) {
+            Ok(Some(footer)) => decoded.map_or(Ok(()), |decoded| footer.verify(decoded)),
+            Ok(None) if FOOTER_MAGIC.starts_with(&bytes[..filled]) => Err(crate::Error::Eof),
+            Ok(None) => Err(crate::Error::InvalidData),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn read_inner(&mut self, buf: &mut [u8]) -> DecodeResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(err) = self.deferred_error.take() {
+            return Err(err);
+        }
+
+        let mut written = 0usize;
+        while written < buf.len() {
+            match self.read_step(buf, &mut written) {
+                Ok(true) => {}
+                Ok(false) => break,
+                // Hand out the bytes already copied; the error comes next.
+                Err(err) if written > 0 => {
+                    self.deferred_error = Some(err);
+                    break;
                 }
-                Err(Error::OutputTooSmall) => {}
                 Err(err) => return Err(err),
             }
         }
 
-        let us_u16 = u16::try_from(block.len()).map_err(|_| Error::InvalidParameter)?.to_be_bytes();
-        inner.write_all(&[MAGIC_0, MAGIC_1, TYPE_UNCOMPRESSED])?;
-        inner.write_all(&us_u16)?;
-        inner.write_all(block)?;
-        Ok(())
-    }
-}
-
-#[cfg(feature = "encoder")]
-impl<W: Write> AutoFinish for LzfWriter<W> {
-    fn finish_ignore_error(self) {
-        let _ = self.finish();
+        Ok(written)
     }
-}
 
-#[cfg(feature = "encoder")]
-impl<W: Write> Write for LzfWriter<W> {
-    fn write(&mut self, buf: &[u8]) -> Result<usize> {
-        let mut input = buf;
+    /// Copies buffered bytes into `buf[*written..]` or decodes the next block,
+    /// returning `false` at end of stream.
+    fn read_step(&mut self, buf: &mut [u8], written: &mut usize) -> DecodeResult<bool> {
+        if self.out_pos < self.out_buf.len() {
+            let avail = self.out_buf.len() - self.out_pos;
+            let take = (buf.len() - *written).min(avail);
+            buf[*written..*written + take]
+                .copy_from_slice(&self.out_buf[self.out_pos..self.out_pos + take]);
+            self.out_pos += take;
+            self.delivered_len += take as u64;
+            *written += take;
+            return Ok(true);
+        }
 
-        if !self.in_buf.is_empty() {
-            let need = self.block_size - self.in_buf.len();
-            let take = need.min(input.len());
-            self.in_buf.extend_from_slice(&input[..take]);
-            input = &input[take..];
+        self.out_buf.clear();
+        self.out_pos = 0;
+        let Some(header) = self.read_block_header()? else {
+            return Ok(false);
+        };
+        // Blocks that fit in the rest of `buf` skip the block buffer.
+        let rest = &mut buf[*written..];
+        if header.uncompressed_len <= rest.len() {
+            self.read_block_direct(header, &mut rest[..header.uncompressed_len])?;
+            *written += header.uncompressed_len;
+        } else {
+            self.fill_out_buf(header)?;
+        }
+        Ok(true)
+    }
 
-            if self.in_buf.len() == self.block_size {
-                Self::write_block_into(
-                    &mut self.inner,
-                    self.mode,
-                    &mut self.comp_buf,
-                    &self.in_buf,
-                )?;
-                self.in_buf.clear();
+    /// Reads the payload described by `header` and decodes it into `out`,
+    /// which must be exactly `header.uncompressed_len` bytes long.
+    fn read_block_body(
+        inner: &mut R,
+        in_buf: &mut Vec<u8>,
+        header: BlockHeader,
+        out: &mut [u8],
+    ) -> DecodeResult<()> {
+        debug_assert_eq!(out.len(), header.uncompressed_len);
+        match header.compressed_len {
+            None => inner.read_exact(out)?,
+            Some(cs) => {
+                in_buf.resize(cs, 0);
+                inner.read_exact(in_buf)?;
+                let written = decompress(in_buf, out)?;
+                if written != out.len() {
+                    return Err(crate::Error::InvalidData);
+                }
             }
         }
-
-        let mut consumed = 0usize;
-        while input.len() - consumed >= self.block_size {
-            let block = &input[consumed..consumed + self.block_size];
-            Self::write_block_into(&mut self.inner, self.mode, &mut self.comp_buf, block)?;
-            consumed += self.block_size;
+        match header.checksum {
+            Some(expected) if crate::crc32::crc32(out) != expected => {
+                Err(crate::Error::ChecksumMismatch)
+            }
+            _ => Ok(()),
         }
+    }
+}
+
+/// Builder collecting the options of an [`LzfReader`].
+///
+/// Every option defaults to the behavior of [`LzfReader::new`]; `build`
+/// applies the chosen ones as the matching `with_*` constructors and
+/// `enable_*` methods would.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, LzfRead, LzfReaderBuilder, encode_blocks_with_footer};
+///
+/// let framed = encode_blocks_with_footer(b"built reader", 4096, CompressionMode::Normal).unwrap();
+/// let mut reader = LzfReaderBuilder::new().footer_check(true).build(framed.as_slice());
+/// let mut out = [0u8; 12];
+/// reader.read_exact(&mut out).unwrap();
+/// assert_eq!(&out, b"built reader");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LzfReaderBuilder {
+    capacity: usize,
+    max_block_decoded: usize,
+    max_total_decoded: u64,
+    lenient: bool,
+    concatenated: bool,
+    footer_check: bool,
+}
 
-        if consumed < input.len() {
-            self.in_buf.extend_from_slice(&input[consumed..]);
+impl LzfReaderBuilder {
+    /// Returns a builder with the defaults of [`LzfReader::new`].
+    pub const fn new() -> Self {
+        Self {
+            capacity: 0,
+            max_block_decoded: usize::MAX,
+            max_total_decoded: u64::MAX,
+            lenient: false,
+            concatenated: false,
+            footer_check: false,
         }
+    }
 
-        Ok(buf.len())
+    /// Preallocates the internal buffers, as [`LzfReader::with_capacity`] does.
+    pub const fn capacity(mut self, block_size_hint: usize) -> Self {
+        self.capacity = block_size_hint;
+        self
     }
 
-    fn flush(&mut self) -> Result<()> {
-        self.flush_pending()?;
+    /// Sets the limits described at [`LzfReader::with_limits`].
+    pub const fn limits(mut self, max_block_decoded: usize, max_total_decoded: u64) -> Self {
+        self.max_block_decoded = max_block_decoded;
+        self.max_total_decoded = max_total_decoded;
+        self
+    }
+
+    /// Selects lenient decoding, see [`LzfReader::enable_lenient`].
+    pub const fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Selects decoding of concatenated streams, see
+    /// [`LzfReader::enable_concatenated`].
+    pub const fn concatenated(mut self, enabled: bool) -> Self {
+        self.concatenated = enabled;
+        self
+    }
+
+    /// Selects footer verification, see [`LzfReader::enable_footer_check`].
+    pub const fn footer_check(mut self, enabled: bool) -> Self {
+        self.footer_check = enabled;
+        self
+    }
+
+    /// Creates a reader over `inner` with the configured options.
+    pub fn build<R: Read>(&self, inner: R) -> LzfReader<R> {
+        let mut reader = LzfReader::with_capacity(inner, self.capacity);
+        reader.max_block_len = self.max_block_decoded;
+        reader.max_total_len = self.max_total_decoded;
+        reader.skip_unknown = self.lenient;
+        reader.concatenated = self.concatenated;
+        if self.footer_check {
+            reader.enable_footer_check();
+        }
+        reader
+    }
+}
+
+impl Default for LzfReaderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Read> Read for LzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> DecodeResult<usize> {
+        let res = self.read_inner(buf);
+        self.record(res)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> LzfReader<R> {
+    /// Wraps the reader in a [`StdLzfReader`], which implements
+    /// `std::io::Read` and `std::io::BufRead`.
+    pub fn into_std(self) -> StdLzfReader<R> {
+        StdLzfReader { inner: self }
+    }
+}
+
+/// [`LzfReader`] adapter implementing `std::io::Read` and `std::io::BufRead`.
+///
+/// `LzfReader` implements the crate's `Read` trait, which is blanket
+/// implemented for every `std::io::Read` type, so it cannot implement
+/// `std::io::Read` itself. This wrapper, obtained from
+/// [`LzfReader::into_std`], hands decoded bytes to std consumers such as
+/// `std::io::copy`, `BufReader`, or deserializers reading from a
+/// `std::io::Read`. Errors are converted with `From<Error> for
+/// std::io::Error`. As `BufRead`, it serves each decoded block straight from
+/// the reader's block buffer.
+///
+/// # Example
+///
+/// ```
+/// use std::io::BufRead;
+///
+/// use lzf_rust::{LzfReader, encode_blocks};
+///
+/// let framed = encode_blocks(b"first line\nsecond line\n", 4096).unwrap();
+/// let reader = LzfReader::new(framed.as_slice()).into_std();
+/// let lines: Vec<String> = reader.lines().collect::<Result<_, _>>().unwrap();
+/// assert_eq!(lines, ["first line", "second line"]);
+/// ```
+#[cfg(feature = "std")]
+pub struct StdLzfReader<R: Read> {
+    inner: LzfReader<R>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> StdLzfReader<R> {
+    /// Unwraps the adapter and returns the framed reader.
+    pub fn into_inner(self) -> LzfReader<R> {
+        self.inner
+    }
+
+    /// Returns a shared reference to the framed reader.
+    pub fn inner(&self) -> &LzfReader<R> {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the framed reader.
+    pub fn inner_mut(&mut self) -> &mut LzfReader<R> {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> std::io::Read for StdLzfReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        Ok(Read::read(&mut self.inner, buf)?)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> std::io::BufRead for StdLzfReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        let reader = &mut self.inner;
+        // Loop past empty blocks, which would otherwise signal end of stream.
+        while reader.out_pos == reader.out_buf.len() {
+            reader.out_buf.clear();
+            reader.out_pos = 0;
+            let res = reader.load_next_block();
+            if !reader.record(res)? {
+                break;
+            }
+        }
+        Ok(&reader.out_buf[reader.out_pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let reader = &mut self.inner;
+        let amt = amt.min(reader.out_buf.len() - reader.out_pos);
+        reader.out_pos += amt;
+        reader.delivered_len += amt as u64;
+    }
+}
+
+/// Position of one block in a framed stream, as recorded by
+/// [`LzfIndexedReader`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockIndexEntry {
+    /// Offset of the block's first decoded byte in the decoded stream.
+    pub decoded_offset: u64,
+    /// Position of the block header in the underlying source.
+    pub input_offset: u64,
+    /// Length of the block once decoded.
+    pub decoded_len: usize,
+}
+
+/// Framed LZF reader with random access over a seekable source.
+///
+/// [`open`](Self::open) walks the block headers once, seeking over the
+/// payloads, and records where each block starts in both the source and the
+/// decoded stream. [`seek_to`](Self::seek_to) then seeks the source to the
+/// block containing the requested decoded offset and decodes forward from
+/// there, so a seek costs at most one block of decoding. Reads go through the
+/// crate's `Read` trait and behave like [`LzfReader`], reporting end of
+/// stream at the `0` end-of-stream marker or the end of the source.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use lzf_rust::{LzfIndexedReader, LzfRead, encode_blocks};
+///
+/// let input: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+/// let framed = encode_blocks(&input, 4096).unwrap();
+/// let mut reader = LzfIndexedReader::open(Cursor::new(framed)).unwrap();
+/// assert_eq!(reader.index().len(), 3);
+///
+/// reader.seek_to(5_000).unwrap();
+/// let mut out = [0u8; 100];
+/// reader.read_exact(&mut out).unwrap();
+/// assert_eq!(out, input[5_000..5_100]);
+/// ```
+#[cfg(feature = "std")]
+pub struct LzfIndexedReader<R: std::io::Read + std::io::Seek> {
+    reader: LzfReader<R>,
+    index: Vec<BlockIndexEntry>,
+    /// Source positions where the stream starts and where its last block
+    /// ends, before any end-of-stream marker.
+    start: u64,
+    end: u64,
+    decoded_len: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + std::io::Seek> LzfIndexedReader<R> {
+    /// Indexes the framed stream starting at the current position of `inner`
+    /// and positions the reader at its start.
+    ///
+    /// Fails like [`LzfReader`] on a malformed block header, and with
+    /// `Error::Eof` if the last block is cut off.
+    pub fn open(mut inner: R) -> DecodeResult<Self> {
+        let start = inner.stream_position()?;
+        let mut reader = LzfReader::new(inner);
+        let mut index = Vec::new();
+        let mut end = start;
+        while let Some(header) = reader.read_block_header()? {
+            index.push(BlockIndexEntry {
+                decoded_offset: reader.decoded_len,
+                input_offset: start + reader.block_offset,
+                decoded_len: header.uncompressed_len,
+            });
+            let payload_len = header.compressed_len.unwrap_or(header.uncompressed_len);
+            reader.inner.seek(std::io::SeekFrom::Current(payload_len as i64))?;
+            reader.end_block(header);
+            end = start + reader.in_pos;
+        }
+        if reader.inner.seek(std::io::SeekFrom::End(0))? < end {
+            return Err(crate::Error::Eof);
+        }
+
+        let decoded_len = reader.decoded_len;
+        reader.inner.seek(std::io::SeekFrom::Start(start))?;
+        reader.reposition(0, 0, 0);
+        Ok(Self { reader, index, start, end, decoded_len })
+    }
+
+    /// Returns the block index built by [`open`](Self::open), in stream order.
+    pub fn index(&self) -> &[BlockIndexEntry] {
+        &self.index
+    }
+
+    /// Returns the total decoded length of the stream.
+    pub fn decoded_len(&self) -> u64 {
+        self.decoded_len
+    }
+
+    /// Returns the decoded offset the next read starts at.
+    pub fn position(&self) -> u64 {
+        self.reader.delivered_len
+    }
+
+    /// Moves the reader to `offset` in the decoded stream.
+    ///
+    /// Seeking to the end of the stream is allowed, after which reads return
+    /// `0`; seeking past it fails with `Error::InvalidParameter` and leaves
+    /// the position unchanged.
+    pub fn seek_to(&mut self, offset: u64) -> DecodeResult<()> {
+        if offset > self.decoded_len {
+            return Err(crate::Error::InvalidParameter);
+        }
+        // The first block ending past `offset` holds it; empty blocks never do.
+        let i = self.index.partition_point(|e| e.decoded_offset + e.decoded_len as u64 <= offset);
+        let (input_offset, decoded_offset) = match self.index.get(i) {
+            Some(entry) => (entry.input_offset, entry.decoded_offset),
+            None => (self.end, self.decoded_len),
+        };
+        self.reader.inner.seek(std::io::SeekFrom::Start(input_offset))?;
+        self.reader.reposition(input_offset - self.start, i as u64, decoded_offset);
+        self.reader.skip(offset - decoded_offset)?;
+        Ok(())
+    }
+
+    /// Returns a shared reference to the framed reader used for decoding.
+    pub fn reader(&self) -> &LzfReader<R> {
+        &self.reader
+    }
+
+    /// Unwraps the reader and returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.reader.inner
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + std::io::Seek> Read for LzfIndexedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> DecodeResult<usize> {
+        Read::read(&mut self.reader, buf)
+    }
+}
+
+/// Writer that encodes framed LZF (`ZV` block stream).
+///
+/// Data written into this adapter is chunked into blocks and emitted as either
+/// compressed or uncompressed `ZV` blocks.
+///
+/// Frames are never split between calls except when the underlying writer
+/// fails: the part of the frame it did not take is then kept and written
+/// before anything else by the next call, which reports the error again if
+/// it persists. A stream stays intact through transient failures as long as
+/// the failed calls are retried; a writer abandoned after an error leaves a
+/// truncated stream behind.
+#[cfg(feature = "encoder")]
+pub struct LzfWriter<W: Write> {
+    inner: W,
+    block_size: usize,
+    options: EncodeOptions,
+    in_buf: Vec<u8>,
+    comp_buf: Vec<u8>,
+    write_eof_marker: bool,
+    /// Running footer of the input when a footer is written on finish.
+    footer: Option<StreamFooter>,
+    block_jobs: bool,
+    ready_blocks: VecDeque<Vec<u8>>,
+    early_frames: BTreeMap<u64, Vec<u8>>,
+    /// Identity stamped on block jobs, so frames of other writers are refused.
+    owner: usize,
+    /// Count of `reset` calls, stamped on block jobs so that frames of jobs
+    /// taken before a reset are refused too.
+    generation: u64,
+    next_job_seq: u64,
+    next_frame_seq: u64,
+    /// Rest of a frame `inner` failed to take, written before anything else.
+    unsent: Vec<u8>,
+    /// Whether `finish` has written the end-of-stream marker and footer.
+    trailer_written: bool,
+}
+
+/// Returns a writer identity not handed out before in this process.
+///
+/// Targets without pointer-sized atomics share one identity, so there only
+/// the sequence checks of `accept_frame` apply.
+#[cfg(feature = "encoder")]
+fn next_writer_id() -> usize {
+    #[cfg(target_has_atomic = "ptr")]
+    {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT: AtomicUsize = AtomicUsize::new(1);
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    }
+    #[cfg(not(target_has_atomic = "ptr"))]
+    {
+        0
+    }
+}
+
+/// A single block compression step detached from an [`LzfWriter`].
+///
+/// Obtained from [`LzfWriter::take_block_job`]. The job owns its input and is
+/// `Send`, so [`run`](Self::run) can execute on another thread (for example
+/// `tokio::task::spawn_blocking` or a rayon pool).
+#[cfg(feature = "encoder")]
+#[derive(Debug)]
+pub struct BlockJob {
+    owner: usize,
+    generation: u64,
+    seq: u64,
+    options: EncodeOptions,
+    block: Vec<u8>,
+}
+
+#[cfg(feature = "encoder")]
+impl BlockJob {
+    /// Returns the position of this block in the writer's output order.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Compresses the block into a complete `ZV` frame.
+    pub fn run(self) -> CompressedFrame {
+        let mut comp_buf = vec![0u8; self.block.len().saturating_sub(4)];
+        let (header, payload) = frame_block(&self.block, &self.options, &mut comp_buf);
+        let mut bytes = Vec::with_capacity(header.len() + payload.len());
+        bytes.extend_from_slice(header.as_slice());
+        bytes.extend_from_slice(payload);
+        CompressedFrame { owner: self.owner, generation: self.generation, seq: self.seq, bytes }
+    }
+}
+
+/// A serialized `ZV` frame produced by [`BlockJob::run`].
+///
+/// Hand it back to the originating writer with [`LzfWriter::accept_frame`].
+#[cfg(feature = "encoder")]
+#[derive(Debug)]
+pub struct CompressedFrame {
+    owner: usize,
+    generation: u64,
+    seq: u64,
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "encoder")]
+impl CompressedFrame {
+    /// Returns the position of this frame in the writer's output order.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Returns the encoded frame bytes (header and payload).
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// Builder collecting the options of an [`LzfWriter`].
+///
+/// The defaults are 65535-byte blocks, as used by the historical `lzf`
+/// utility, `EncodeOptions::new()`, and no end-of-stream marker or footer.
+/// The block size is validated by [`build`](Self::build), which fails with
+/// `Error::InvalidParameter` unless it is in `1..=65535`.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, LzfWrite, LzfWriterBuilder, decode_blocks};
+///
+/// let mut writer = LzfWriterBuilder::new()
+///     .block_size(4096)
+///     .mode(CompressionMode::Best)
+///     .eof_marker(true)
+///     .build(Vec::new())
+///     .unwrap();
+/// writer.write_all(b"built writer").unwrap();
+/// let framed = writer.finish().unwrap();
+/// assert_eq!(framed.last(), Some(&0));
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"built writer");
+/// ```
+#[cfg(feature = "encoder")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LzfWriterBuilder {
+    block_size: usize,
+    options: EncodeOptions,
+    eof_marker: bool,
+    footer: bool,
+}
+
+#[cfg(feature = "encoder")]
+impl LzfWriterBuilder {
+    /// Returns a builder with the default options.
+    pub const fn new() -> Self {
+        Self {
+            block_size: u16::MAX as usize,
+            options: EncodeOptions::new(),
+            eof_marker: false,
+            footer: false,
+        }
+    }
+
+    /// Sets the block size, which must be in `1..=65535`.
+    pub const fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Replaces all encoding options at once.
+    pub const fn options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Selects the raw compressor mode, see `EncodeOptions::mode`.
+    pub const fn mode(mut self, mode: CompressionMode) -> Self {
+        self.options = self.options.mode(mode);
+        self
+    }
+
+    /// Selects checked `ZV\2` blocks, see [`LzfWriter::enable_checksums`].
+    pub const fn checksums(mut self, enabled: bool) -> Self {
+        self.options = self.options.checksums(enabled);
+        self
+    }
+
+    /// Controls whether blocks are compressed at all, see
+    /// `EncodeOptions::compress`.
+    pub const fn compress(mut self, enabled: bool) -> Self {
+        self.options = self.options.compress(enabled);
+        self
+    }
+
+    /// Sets the minimum number of bytes compression must save before a block
+    /// is stored compressed, see `EncodeOptions::min_savings_bytes`.
+    pub const fn min_savings_bytes(mut self, bytes: usize) -> Self {
+        self.options = self.options.min_savings_bytes(bytes);
+        self
+    }
+
+    /// Sets the minimum fraction of a block, in thousandths, that compression
+    /// must save, see `EncodeOptions::min_savings_per_mille`.
+    pub const fn min_savings_per_mille(mut self, per_mille: u16) -> Self {
+        self.options = self.options.min_savings_per_mille(per_mille);
+        self
+    }
+
+    /// Controls the trailing zero byte end-of-stream marker written by
+    /// `finish`.
+    pub const fn eof_marker(mut self, enabled: bool) -> Self {
+        self.eof_marker = enabled;
+        self
+    }
+
+    /// Controls the whole-stream footer, see [`LzfWriter::enable_footer`].
+    ///
+    /// A footer always follows an end-of-stream marker, so enabling it writes
+    /// the marker regardless of [`eof_marker`](Self::eof_marker).
+    pub const fn footer(mut self, enabled: bool) -> Self {
+        self.footer = enabled;
+        self
+    }
+
+    /// Creates a writer over `inner` with the configured options.
+    pub fn build<W: Write>(&self, inner: W) -> Result<LzfWriter<W>> {
+        if self.block_size == 0 || self.block_size > usize::from(u16::MAX) {
+            return Err(Error::InvalidParameter);
+        }
+        let block_size = self.block_size;
+        let mut writer = LzfWriter {
+            inner,
+            block_size,
+            options: self.options,
+            in_buf: Vec::with_capacity(block_size),
+            comp_buf: vec![0u8; block_size.saturating_sub(4)],
+            write_eof_marker: self.eof_marker,
+            footer: None,
+            block_jobs: false,
+            ready_blocks: VecDeque::new(),
+            early_frames: BTreeMap::new(),
+            owner: next_writer_id(),
+            generation: 0,
+            next_job_seq: 0,
+            next_frame_seq: 0,
+            unsent: Vec::new(),
+            trailer_written: false,
+        };
+        if self.footer {
+            writer.enable_footer();
+        }
+        Ok(writer)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl Default for LzfWriterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> LzfWriter<W> {
+    /// Creates a new framed LZF writer with the given block size (`1..=65535`).
+    pub fn new(inner: W, block_size: usize) -> Result<Self> {
+        Self::new_with_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates a new framed LZF writer with an explicit compression mode.
+    pub fn new_with_mode(inner: W, block_size: usize, mode: CompressionMode) -> Result<Self> {
+        Self::new_with_options(inner, block_size, EncodeOptions::new().mode(mode))
+    }
+
+    /// Creates a new framed LZF writer with explicit encoding options.
+    ///
+    /// Blocks are framed exactly as `encode_blocks_with_options` frames them.
+    pub fn new_with_options(inner: W, block_size: usize, options: EncodeOptions) -> Result<Self> {
+        LzfWriterBuilder::new().block_size(block_size).options(options).build(inner)
+    }
+
+    /// Creates a writer that frames its input without compressing it.
+    ///
+    /// Every block is stored as `ZV\0`, as with
+    /// `EncodeOptions::new().compress(false)`; the output is readable by
+    /// `LzfReader`, `decode_blocks` and the historical `lzf` utility.
+    pub fn new_uncompressed(inner: W, block_size: usize) -> Result<Self> {
+        Self::new_with_options(inner, block_size, EncodeOptions::new().compress(false))
+    }
+
+    /// Creates a writer and enables writing a trailing zero byte EOF marker on finish.
+    ///
+    /// The marker matches the historical `lzf` utility stream behavior.
+    pub fn new_with_eof_marker(inner: W, block_size: usize) -> Result<Self> {
+        Self::new_with_eof_marker_and_mode(inner, block_size, CompressionMode::Normal)
+    }
+
+    /// Creates a writer and enables writing a trailing zero byte EOF marker on finish.
+    ///
+    /// Compression mode is explicitly selected.
+    pub fn new_with_eof_marker_and_mode(
+        inner: W,
+        block_size: usize,
+        mode: CompressionMode,
+    ) -> Result<Self> {
+        LzfWriterBuilder::new().block_size(block_size).mode(mode).eof_marker(true).build(inner)
+    }
+
+    /// Unwraps the writer and returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns a shared reference to the underlying writer.
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Switches the writer to checked `ZV\2` blocks.
+    ///
+    /// Every block written afterwards carries a CRC-32 of its contents, as
+    /// produced by `encode_blocks_checked`. Blocks already buffered are
+    /// checked too. Other `lzf` decoders cannot read checked blocks.
+    pub fn enable_checksums(&mut self) {
+        self.options = self.options.checksums(true);
+    }
+
+    /// Returns the compression mode used for blocks emitted from now on.
+    pub fn mode(&self) -> CompressionMode {
+        self.options.compression_mode()
+    }
+
+    /// Switches the compression mode for blocks emitted from now on.
+    ///
+    /// Blocks are compressed independently, so the mode can change between
+    /// any two blocks and the stream still decodes with any reader. The new
+    /// mode applies to every block compressed after the call, including
+    /// input already buffered for the current block; call
+    /// [`flush_block`](Self::flush_block) first to emit that input with the
+    /// previous mode. With block jobs, jobs already taken keep the mode they
+    /// were taken with.
+    pub fn set_mode(&mut self, mode: CompressionMode) {
+        self.options = self.options.mode(mode);
+    }
+
+    /// Makes `finish` end the stream with the end-of-stream marker and a
+    /// whole-stream footer.
+    ///
+    /// The footer holds the total length and CRC-32 of everything written,
+    /// laid out as documented for `encode_blocks_with_footer`, whose output
+    /// the writer then reproduces. Call this before the first write.
+    pub fn enable_footer(&mut self) {
+        self.write_eof_marker = true;
+        self.footer = Some(StreamFooter::new());
+    }
+
+    /// Switches the writer to deferred block compression.
+    ///
+    /// Afterwards, `write` only buffers input; each complete block becomes a
+    /// [`BlockJob`] retrieved with [`take_block_job`](Self::take_block_job).
+    /// Run the jobs anywhere and feed the resulting frames back through
+    /// [`accept_frame`](Self::accept_frame), which restores job order. Blocks that were never
+    /// taken as jobs, and the final partial block, are compressed inline by
+    /// `flush` or `finish`, which fail with `Error::InvalidParameter` while
+    /// taken jobs are still outstanding.
+    ///
+    /// The emitted stream is byte-identical to the sequential writer's.
+    pub fn enable_block_jobs(&mut self) {
+        self.block_jobs = true;
+    }
+
+    /// Takes the next complete buffered block as a compression job.
+    ///
+    /// Returns `None` when no complete block is buffered or block jobs are not
+    /// enabled.
+    pub fn take_block_job(&mut self) -> Option<BlockJob> {
+        let block = self.ready_blocks.pop_front()?;
+        let seq = self.next_job_seq;
+        self.next_job_seq += 1;
+        let (owner, generation, options) = (self.owner, self.generation, self.options);
+        Some(BlockJob { owner, generation, seq, options, block })
+    }
+
+    /// Accepts a frame produced by [`BlockJob::run`].
+    ///
+    /// Frames may arrive in any order: a frame that completes ahead of its
+    /// predecessors is held back until they have been accepted, and frames are
+    /// always written to the underlying writer in the order their jobs were
+    /// taken. A frame that does not belong to an outstanding job of this
+    /// writer (taken from another writer or before a `reset`, or already
+    /// accepted) is rejected with `Error::InvalidParameter`.
+    ///
+    /// If writing to the underlying writer fails, the unwritten frames stay
+    /// queued and are retried by the next `accept_frame`, `flush`, or
+    /// `finish` call.
+    pub fn accept_frame(&mut self, frame: CompressedFrame) -> Result<()> {
+        if frame.owner != self.owner
+            || frame.generation != self.generation
+            || frame.seq < self.next_frame_seq
+            || frame.seq >= self.next_job_seq
+            || self.early_frames.contains_key(&frame.seq)
+        {
+            return Err(Error::InvalidParameter);
+        }
+        self.early_frames.insert(frame.seq, frame.bytes);
+        self.write_accepted_frames()
+    }
+
+    /// Finishes the stream and returns the underlying writer.
+    ///
+    /// This flushes any pending input block. If EOF marker mode is enabled, a
+    /// trailing zero byte is appended after the final block, followed by the
+    /// footer if one was enabled.
+    ///
+    /// On failure the writer is dropped along with any data it still buffers;
+    /// use [`try_finish`](Self::try_finish) to get it back instead.
+    pub fn finish(self) -> Result<W> {
+        self.try_finish().map_err(|err| err.error)
+    }
+
+    /// Finishes the stream like [`finish`](Self::finish), but returns the
+    /// writer along with the error on failure.
+    ///
+    /// The writer keeps whatever the underlying writer has not taken,
+    /// including the rest of a frame cut off by the failure, so calling
+    /// `try_finish` again resumes where the failed call stopped and no byte
+    /// is written twice. Alternatively, recover the
+    /// underlying writer with [`FinishError::into_writer`] and
+    /// [`into_inner`](Self::into_inner).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{LzfWrite, LzfWriter, decode_blocks};
+    ///
+    /// let mut writer = LzfWriter::new(Vec::new(), 4096).unwrap();
+    /// writer.write_all(b"retry me").unwrap();
+    /// let framed = match writer.try_finish() {
+    ///     Ok(framed) => framed,
+    ///     Err(err) => err.into_writer().try_finish().map_err(|err| err.error).unwrap(),
+    /// };
+    /// assert_eq!(decode_blocks(&framed).unwrap(), b"retry me");
+    /// ```
+    pub fn try_finish(mut self) -> core::result::Result<W, FinishError<W>> {
+        match self.finish_in_place() {
+            Ok(()) => Ok(self.inner),
+            Err(error) => Err(FinishError { error, writer: Box::new(self) }),
+        }
+    }
+
+    fn finish_in_place(&mut self) -> Result<()> {
+        if !self.trailer_written {
+            self.flush_pending()?;
+            self.trailer_written = true;
+            let marker: &[u8] = if self.write_eof_marker { &[0] } else { &[] };
+            let footer = self.footer.map(|footer| footer.to_bytes());
+            let footer = footer.as_ref().map_or(&[][..], |bytes| &bytes[..]);
+            write_frame(&mut self.inner, &mut self.unsent, &[marker, footer])?;
+        }
+        self.send_unsent()?;
+        self.inner.flush()
+    }
+
+    /// Starts a new stream on `inner` and returns the previous underlying
+    /// writer.
+    ///
+    /// Nothing is written: finish the current stream first, or use
+    /// [`finish_and_reset`](Self::finish_and_reset). Buffered input, queued
+    /// block jobs and frames, and the running footer are discarded, while the
+    /// block size, encoding options, and end-of-stream marker, footer, and
+    /// block-job settings are kept. Frames of jobs taken before the reset
+    /// are refused by [`accept_frame`](Self::accept_frame). The internal
+    /// buffers keep their capacity, so encoding many short streams with one
+    /// writer does not allocate per stream.
+    pub fn reset(&mut self, inner: W) -> W {
+        self.in_buf.clear();
+        self.ready_blocks.clear();
+        self.early_frames.clear();
+        self.generation += 1;
+        self.next_job_seq = 0;
+        self.next_frame_seq = 0;
+        self.unsent.clear();
+        self.trailer_written = false;
+        if self.footer.is_some() {
+            self.footer = Some(StreamFooter::new());
+        }
+        core::mem::replace(&mut self.inner, inner)
+    }
+
+    /// Finishes the current stream as [`finish`](Self::finish) does, then
+    /// starts a new one on `next` and returns the finished underlying writer.
+    ///
+    /// If finishing fails, the writer is left as it was, still writing to the
+    /// old underlying writer, and the call can be retried.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{LzfWrite, LzfWriter, decode_blocks};
+    ///
+    /// let mut writer = LzfWriter::new(Vec::new(), 4096).unwrap();
+    /// writer.write_all(b"first").unwrap();
+    /// let first = writer.finish_and_reset(Vec::new()).unwrap();
+    /// writer.write_all(b"second").unwrap();
+    /// let second = writer.finish().unwrap();
+    /// assert_eq!(decode_blocks(&first).unwrap(), b"first");
+    /// assert_eq!(decode_blocks(&second).unwrap(), b"second");
+    /// ```
+    pub fn finish_and_reset(&mut self, next: W) -> Result<W> {
+        self.finish_in_place()?;
+        Ok(self.reset(next))
+    }
+
+    /// Ends the current block: compresses and writes the buffered partial
+    /// block, if any, without flushing the underlying writer.
+    ///
+    /// Data written afterwards starts a new block, so a record written
+    /// between two calls occupies whole blocks of its own and can be located
+    /// and decoded independently. Each early block costs a header and
+    /// compresses worse than a full one, as matches cannot reach into
+    /// earlier blocks, so frequent calls with little data in between reduce
+    /// the compression ratio noticeably.
+    ///
+    /// Like `flush`, this fails with `Error::InvalidParameter` while taken
+    /// block jobs are outstanding.
+    pub fn flush_block(&mut self) -> Result<()> {
+        self.flush_pending()
+    }
+
+    /// Writes `record` as a block of its own.
+    ///
+    /// The partial block buffered by earlier `write` calls is emitted first,
+    /// as [`flush_block`](Self::flush_block) does, and `record` then becomes
+    /// exactly one block regardless of the configured block size, so a
+    /// consumer can find each record at a block boundary. Fails with
+    /// `Error::InvalidParameter` if `record` is longer than 65535 bytes or
+    /// while taken block jobs are outstanding. An empty record becomes an
+    /// empty block.
+    ///
+    /// Past those checks `record` is taken even if the underlying writer
+    /// fails: the error is returned, and the part of the stream the writer
+    /// did not take, `record` included, stays buffered. Retry with `flush`
+    /// or `finish` then; calling `write_block` again with the same record
+    /// would write it twice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use lzf_rust::{FrameIter, LzfWriter, decode_blocks};
+    ///
+    /// let mut writer = LzfWriter::new(Vec::new(), 4096).unwrap();
+    /// writer.write_block(b"first record").unwrap();
+    /// writer.write_block(b"second record").unwrap();
+    /// let framed = writer.finish().unwrap();
+    /// assert_eq!(FrameIter::new(&framed).count(), 2);
+    /// assert_eq!(decode_blocks(&framed).unwrap(), b"first recordsecond record");
+    /// ```
+    pub fn write_block(&mut self, record: &[u8]) -> Result<()> {
+        // Every taken block job must have had its frame accepted.
+        let accepted = self.next_frame_seq + self.early_frames.len() as u64;
+        if record.len() > usize::from(u16::MAX) || accepted != self.next_job_seq {
+            return Err(Error::InvalidParameter);
+        }
+        if let Some(footer) = &mut self.footer {
+            footer.update(record);
+        }
+        if let Err(err) = self.flush_pending() {
+            // Frame everything still pending behind the cut-off frame, in
+            // stream order, so that the record is taken all the same.
+            for (_, bytes) in core::mem::take(&mut self.early_frames) {
+                self.unsent.extend_from_slice(&bytes);
+            }
+            self.next_frame_seq = self.next_job_seq;
+            let mut queue = |block: &[u8]| {
+                let (header, payload) = frame_block(block, &self.options, &mut self.comp_buf);
+                self.unsent.extend_from_slice(header.as_slice());
+                self.unsent.extend_from_slice(payload);
+            };
+            for block in core::mem::take(&mut self.ready_blocks) {
+                queue(&block);
+            }
+            if !self.in_buf.is_empty() {
+                queue(&self.in_buf);
+            }
+            queue(record);
+            self.in_buf.clear();
+            return Err(err);
+        }
+        // A failure leaves the rest of the frame in `unsent`.
+        Self::write_block_into(
+            &mut self.inner,
+            &self.options,
+            &mut self.comp_buf,
+            &mut self.unsent,
+            record,
+        )
+    }
+
+    /// Returns a wrapper that will call `finish()` on drop.
+    ///
+    /// This is useful for best-effort stream finalization in scopes with early
+    /// returns.
+    pub fn auto_finish(self) -> AutoFinisher<Self> {
+        AutoFinisher(Some(self))
+    }
+
+    fn flush_pending(&mut self) -> Result<()> {
+        self.write_accepted_frames()?;
+        if self.next_job_seq != self.next_frame_seq {
+            return Err(Error::InvalidParameter);
+        }
+        while let Some(block) = self.ready_blocks.pop_front() {
+            Self::write_block_into(
+                &mut self.inner,
+                &self.options,
+                &mut self.comp_buf,
+                &mut self.unsent,
+                &block,
+            )?;
+        }
+        if !self.in_buf.is_empty() {
+            let res = Self::write_block_into(
+                &mut self.inner,
+                &self.options,
+                &mut self.comp_buf,
+                &mut self.unsent,
+                &self.in_buf,
+            );
+            self.in_buf.clear();
+            res?;
+        }
+        Ok(())
+    }
+
+    /// Writes the rest of a cut-off frame, then the accepted block-job frames
+    /// that are next in job order.
+    fn write_accepted_frames(&mut self) -> Result<()> {
+        self.send_unsent()?;
+        while let Some(bytes) = self.early_frames.remove(&self.next_frame_seq) {
+            self.next_frame_seq += 1;
+            write_frame(&mut self.inner, &mut self.unsent, &[&bytes])?;
+        }
+        Ok(())
+    }
+
+    /// Writes the rest of a frame the underlying writer failed to take.
+    fn send_unsent(&mut self) -> Result<()> {
+        if self.unsent.is_empty() {
+            return Ok(());
+        }
+        let mut rest = self.unsent.as_slice();
+        let res = default_write_all(&mut self.inner, &mut rest);
+        let sent = self.unsent.len() - rest.len();
+        self.unsent.drain(..sent);
+        res
+    }
+
+    /// Frames `block` and writes the frame; on failure, what `inner` did not
+    /// take is left in `unsent`, so the block counts as written either way.
+    fn write_block_into(
+        inner: &mut W,
+        options: &EncodeOptions,
+        comp_buf: &mut Vec<u8>,
+        unsent: &mut Vec<u8>,
+        block: &[u8],
+    ) -> Result<()> {
+        let (header, payload) = frame_block(block, options, comp_buf);
+        write_frame(inner, unsent, &[header.as_slice(), payload])
+    }
+}
+
+/// Writes the concatenation of `parts`.
+///
+/// If `inner` fails, the bytes it did not take are appended to `unsent`, so
+/// that the frame can be resumed without framing its block again.
+#[cfg(feature = "encoder")]
+fn write_frame<W: Write>(inner: &mut W, unsent: &mut Vec<u8>, parts: &[&[u8]]) -> Result<()> {
+    debug_assert!(unsent.is_empty());
+    for (i, part) in parts.iter().enumerate() {
+        let mut rest = *part;
+        if let Err(err) = default_write_all(inner, &mut rest) {
+            unsent.extend_from_slice(rest);
+            for part in &parts[i + 1..] {
+                unsent.extend_from_slice(part);
+            }
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Error returned by [`LzfWriter::try_finish`], holding the writer back.
+#[cfg(feature = "encoder")]
+pub struct FinishError<W: Write> {
+    /// The error that stopped `try_finish`.
+    pub error: Error,
+    // Boxed to keep `Result<W, FinishError<W>>` small.
+    writer: Box<LzfWriter<W>>,
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> FinishError<W> {
+    /// Returns the writer, ready for another `try_finish` call.
+    pub fn into_writer(self) -> LzfWriter<W> {
+        *self.writer
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> core::fmt::Debug for FinishError<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FinishError").field("error", &self.error).finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> core::fmt::Display for FinishError<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "finishing the stream failed: {}", self.error)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "encoder"))]
+impl<W: Write> std::error::Error for FinishError<W> {}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> LzfWriter<W> {
+    /// Queues the complete blocks of `buf` for block jobs.
+    fn buffer_blocks(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            let take = (self.block_size - self.in_buf.len()).min(input.len());
+            self.in_buf.extend_from_slice(&input[..take]);
+            input = &input[take..];
+            if self.in_buf.len() == self.block_size {
+                let full =
+                    core::mem::replace(&mut self.in_buf, Vec::with_capacity(self.block_size));
+                self.ready_blocks.push_back(full);
+            }
+        }
+    }
+
+    /// Writes the frames of the blocks `buf` completes and buffers the rest,
+    /// stopping after a frame the underlying writer failed to take. Returns
+    /// how much of `buf` was taken.
+    fn write_blocks(&mut self, buf: &[u8]) -> usize {
+        let mut taken = 0usize;
+        if !self.in_buf.is_empty() {
+            taken = (self.block_size - self.in_buf.len()).min(buf.len());
+            self.in_buf.extend_from_slice(&buf[..taken]);
+            if self.in_buf.len() == self.block_size {
+                let res = Self::write_block_into(
+                    &mut self.inner,
+                    &self.options,
+                    &mut self.comp_buf,
+                    &mut self.unsent,
+                    &self.in_buf,
+                );
+                self.in_buf.clear();
+                if res.is_err() {
+                    return taken;
+                }
+            }
+        }
+
+        while buf.len() - taken >= self.block_size {
+            let block = &buf[taken..taken + self.block_size];
+            taken += self.block_size;
+            let res = Self::write_block_into(
+                &mut self.inner,
+                &self.options,
+                &mut self.comp_buf,
+                &mut self.unsent,
+                block,
+            );
+            if res.is_err() {
+                return taken;
+            }
+        }
+
+        self.in_buf.extend_from_slice(&buf[taken..]);
+        buf.len()
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> AutoFinish for LzfWriter<W> {
+    type Output = W;
+
+    fn finish(self) -> Result<W> {
+        LzfWriter::finish(self)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "encoder"))]
+impl<W: Write> LzfWriter<W> {
+    /// Wraps the writer in a [`StdLzfWriter`], which implements
+    /// `std::io::Write`.
+    pub fn into_std(self) -> StdLzfWriter<W> {
+        StdLzfWriter { inner: self }
+    }
+}
+
+/// [`LzfWriter`] adapter implementing `std::io::Write`.
+///
+/// The counterpart of [`StdLzfReader`]: it lets std consumers such as
+/// `std::io::copy` or serializers writing to a `std::io::Write` produce a
+/// framed stream. Errors are converted with `From<Error> for
+/// std::io::Error`. As with `LzfWriter`, the stream must be completed with
+/// [`finish`](Self::finish), or [`auto_finish`](Self::auto_finish) on drop.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+///
+/// use lzf_rust::{LzfWriter, decode_blocks};
+///
+/// let mut writer = LzfWriter::new(Vec::new(), 4096).unwrap().into_std();
+/// writeln!(writer, "formatted {}", 42).unwrap();
+/// let framed = writer.finish().unwrap();
+/// assert_eq!(decode_blocks(&framed).unwrap(), b"formatted 42\n");
+/// ```
+#[cfg(all(feature = "std", feature = "encoder"))]
+pub struct StdLzfWriter<W: Write> {
+    inner: LzfWriter<W>,
+}
+
+#[cfg(all(feature = "std", feature = "encoder"))]
+impl<W: Write> StdLzfWriter<W> {
+    /// Finishes the stream and returns the underlying writer, as
+    /// [`LzfWriter::finish`] does.
+    pub fn finish(self) -> std::io::Result<W> {
+        Ok(self.inner.finish()?)
+    }
+
+    /// Returns a wrapper that will call `finish()` on drop.
+    pub fn auto_finish(self) -> AutoFinisher<Self> {
+        AutoFinisher(Some(self))
+    }
+
+    /// Unwraps the adapter and returns the framed writer.
+    pub fn into_inner(self) -> LzfWriter<W> {
+        self.inner
+    }
+
+    /// Returns a shared reference to the framed writer.
+    pub fn inner(&self) -> &LzfWriter<W> {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the framed writer.
+    pub fn inner_mut(&mut self) -> &mut LzfWriter<W> {
+        &mut self.inner
+    }
+}
+
+#[cfg(all(feature = "std", feature = "encoder"))]
+impl<W: Write> AutoFinish for StdLzfWriter<W> {
+    type Output = W;
+
+    fn finish(self) -> Result<W> {
+        self.inner.finish()
+    }
+}
+
+#[cfg(all(feature = "std", feature = "encoder"))]
+impl<W: Write> std::io::Write for StdLzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(Write::write(&mut self.inner, buf)?)
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        Ok(Write::write_vectored(&mut self.inner, bufs)?)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(Write::flush(&mut self.inner)?)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<W: Write> Write for LzfWriter<W> {
+    /// Takes input, writing each block's frame once the block is complete.
+    ///
+    /// If the underlying writer fails partway through a frame, the input up
+    /// to the end of that block is reported as written and the error is
+    /// returned by the next call, unless the rest of the frame then goes
+    /// through.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let taken = if self.block_jobs {
+            self.buffer_blocks(buf);
+            buf.len()
+        } else {
+            self.send_unsent()?;
+            self.write_blocks(buf)
+        };
+        if let Some(footer) = &mut self.footer {
+            footer.update(&buf[..taken]);
+        }
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.flush_pending()?;
+        self.inner.flush()
+    }
+}
+
+/// Statistics of a [`recompress_blocks`] run.
+#[cfg(feature = "encoder")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TranscodeStats {
+    /// Framed bytes read from the input, including the end-of-stream marker.
+    pub bytes_in: u64,
+    /// Framed bytes written to the output, including the end-of-stream marker.
+    pub bytes_out: u64,
+    /// Decoded bytes carried from the input stream to the output stream.
+    pub decoded_len: u64,
+    /// Whether the input ended with a `0` end-of-stream marker, and the
+    /// output was given one.
+    pub eof_marker: bool,
+}
+
+/// Transcodes a framed stream to a new block size and compression mode.
+///
+/// Input blocks are decoded one at a time and their contents re-framed into
+/// `block_size`-byte blocks (`1..=65535`) compressed with `mode`, so memory
+/// use is bounded by a few block-sized buffers regardless of the stream
+/// length. The output has an end-of-stream marker exactly when the input had
+/// one; reading stops at the marker, leaving any bytes after it unread.
+///
+/// Malformed input fails as with `LzfReader`, after the blocks preceding the
+/// malformed one have been written. Errors from `output` are returned
+/// unchanged.
+///
+/// # Example
+///
+/// ```
+/// use lzf_rust::{CompressionMode, decode_blocks, encode_blocks, recompress_blocks};
+///
+/// let input = vec![b'r'; 100_000];
+/// let framed = encode_blocks(&input, 4096).unwrap();
+/// let mut out = Vec::new();
+/// let stats =
+///     recompress_blocks(&mut framed.as_slice(), &mut out, 65535, CompressionMode::Best).unwrap();
+/// assert_eq!((stats.bytes_in, stats.bytes_out), (framed.len() as u64, out.len() as u64));
+/// assert!(out.len() < framed.len());
+/// assert_eq!(decode_blocks(&out).unwrap(), input);
+/// ```
+#[cfg(feature = "encoder")]
+pub fn recompress_blocks<R: Read + ?Sized, W: Write + ?Sized>(
+    input: &mut R,
+    output: &mut W,
+    block_size: usize,
+    mode: CompressionMode,
+) -> Result<TranscodeStats> {
+    let mut writer =
+        LzfWriter::new_with_mode(Counting { inner: output, count: 0 }, block_size, mode)?;
+    let mut reader = LzfReader::new(Counting { inner: input, count: 0 });
+    let mut buf = vec![0u8; block_size];
+    let mut decoded_len = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        decoded_len += n as u64;
+    }
+
+    writer.write_eof_marker = reader.eof_marker;
+    let bytes_out = writer.finish()?.count;
+    Ok(TranscodeStats {
+        bytes_in: reader.inner.count,
+        bytes_out,
+        decoded_len,
+        eof_marker: reader.eof_marker,
+    })
+}
+
+/// Adapter counting the bytes read or written through it.
+#[cfg(feature = "encoder")]
+struct Counting<'a, T: ?Sized> {
+    inner: &'a mut T,
+    count: u64,
+}
+
+#[cfg(feature = "encoder")]
+impl<T: Read + ?Sized> Read for Counting<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "encoder")]
+impl<T: Write + ?Sized> Write for Counting<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
         self.inner.flush()
     }
 }
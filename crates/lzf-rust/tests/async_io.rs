@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: ISC
+#![cfg(feature = "async")]
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::executor::block_on;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use lzf_rust::{
+    AsyncLzfReader, AsyncLzfWriter, CompressionMode, EncodeOptions, LzfWriter, Write,
+    encode_blocks, encode_blocks_checked,
+};
+
+fn pattern_data(size: usize) -> Vec<u8> {
+    let mut x = 0xa5f0_0c5eu32;
+    (0..size)
+        .map(|i| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            if x & 0x3000 == 0 { (x >> 24) as u8 } else { b"async"[i % 5] }
+        })
+        .collect()
+}
+
+/// Transport that goes pending every other poll and moves at most a few
+/// bytes per ready poll.
+struct Dribble {
+    data: Vec<u8>,
+    pos: usize,
+    polls: usize,
+    max_chunk: usize,
+}
+
+impl Dribble {
+    fn new(data: Vec<u8>, max_chunk: usize) -> Self {
+        Self { data, pos: 0, polls: 0, max_chunk }
+    }
+
+    fn pending(&mut self, cx: &mut Context<'_>) -> bool {
+        self.polls += 1;
+        if self.polls % 2 == 1 {
+            cx.waker().wake_by_ref();
+            return true;
+        }
+        false
+    }
+
+    fn chunk(&self) -> usize {
+        1 + self.polls % self.max_chunk
+    }
+}
+
+impl AsyncRead for Dribble {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.pending(cx) {
+            return Poll::Pending;
+        }
+        let n = buf.len().min(this.chunk()).min(this.data.len() - this.pos);
+        buf[..n].copy_from_slice(&this.data[this.pos..this.pos + n]);
+        this.pos += n;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for Dribble {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.pending(cx) {
+            return Poll::Pending;
+        }
+        let n = buf.len().min(this.chunk());
+        this.data.extend_from_slice(&buf[..n]);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.get_mut().pending(cx) { Poll::Pending } else { Poll::Ready(Ok(())) }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+fn sync_encode(input: &[u8], block_size: usize, options: EncodeOptions, marker: bool) -> Vec<u8> {
+    let mut writer = if marker {
+        LzfWriter::new_with_eof_marker(Vec::new(), block_size)
+    } else {
+        LzfWriter::new_with_options(Vec::new(), block_size, options)
+    }
+    .expect("writer");
+    writer.write_all(input).expect("write");
+    writer.finish().expect("finish")
+}
+
+#[test]
+fn async_writer_matches_sync_writer_through_a_dribbling_sink() {
+    let input = pattern_data(12_000);
+    for (block_size, max_chunk) in [(4096, 3), (1000, 7), (65_535, 64)] {
+        for options in [EncodeOptions::new(), EncodeOptions::new().checksums(true)] {
+            let expected = sync_encode(&input, block_size, options, false);
+            let framed = block_on(async {
+                let sink = Dribble::new(Vec::new(), max_chunk);
+                let mut writer =
+                    AsyncLzfWriter::new_with_options(sink, block_size, options).expect("writer");
+                for piece in input.chunks(777) {
+                    writer.write_all(piece).await.expect("write");
+                }
+                writer.finish().await.expect("finish").data
+            });
+            assert_eq!(framed, expected);
+        }
+    }
+
+    let expected = sync_encode(&input, 4096, EncodeOptions::new(), true);
+    let framed = block_on(async {
+        let mut writer =
+            AsyncLzfWriter::new_with_eof_marker(Dribble::new(Vec::new(), 5), 4096).expect("writer");
+        writer.write_all(&input).await.expect("write");
+        writer.close().await.expect("close");
+        assert!(writer.write(b"late").await.is_err());
+        writer.into_inner().data
+    });
+    assert_eq!(framed, expected);
+}
+
+#[test]
+fn async_writer_flush_emits_the_partial_block() {
+    block_on(async {
+        let mut writer = AsyncLzfWriter::new(Dribble::new(Vec::new(), 2), 4096).expect("writer");
+        writer.write_all(b"first").await.expect("write");
+        writer.flush().await.expect("flush");
+        assert_eq!(writer.inner().data, encode_blocks(b"first", 4096).expect("encode"));
+        writer.write_all(b"second").await.expect("write");
+        let framed = writer.finish().await.expect("finish").data;
+        let mut expected = encode_blocks(b"first", 4096).expect("encode");
+        expected.extend(encode_blocks(b"second", 4096).expect("encode"));
+        assert_eq!(framed, expected);
+    });
+}
+
+#[test]
+fn async_reader_decodes_through_a_dribbling_source() {
+    let input = pattern_data(12_000);
+    let mut marked = sync_encode(&input, 4096, EncodeOptions::new(), true);
+    marked.extend_from_slice(b"trailing data");
+    let streams = [
+        encode_blocks(&input, 4096).expect("encode"),
+        encode_blocks_checked(&input, 1000, CompressionMode::Normal).expect("encode"),
+        marked,
+    ];
+    for framed in streams {
+        for max_chunk in [1, 3, 100] {
+            let mut reader = AsyncLzfReader::new(Dribble::new(framed.clone(), max_chunk));
+            let mut out = Vec::new();
+            block_on(reader.read_to_end(&mut out)).expect("read");
+            assert_eq!(out, input);
+        }
+    }
+}
+
+#[test]
+fn async_reader_reports_errors_as_io_errors() {
+    let input = pattern_data(10_000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+
+    let mut reader = AsyncLzfReader::new(Dribble::new(framed[..framed.len() - 3].to_vec(), 4));
+    let err = block_on(reader.read_to_end(&mut Vec::new())).expect_err("truncated");
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let mut corrupt = framed.clone();
+    corrupt[1] = b'X';
+    let mut reader = AsyncLzfReader::new(corrupt.as_slice());
+    let err = block_on(reader.read_to_end(&mut Vec::new())).expect_err("corrupt");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.into_inner().expect("inner").downcast_ref(),
+        Some(&lzf_rust::Error::InvalidHeader)
+    );
+}
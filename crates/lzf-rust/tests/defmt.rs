@@ -0,0 +1,15 @@
+// SPDX-License-Identifier: ISC
+//! Build check for the `defmt` feature; run with
+//! `--no-default-features --features defmt,encoder`. Formatting needs a
+//! `defmt` logger, so only the impls are checked here.
+#![cfg(feature = "defmt")]
+
+fn assert_format<T: defmt::Format>(_: &T) {}
+
+#[test]
+fn errors_and_modes_implement_format() {
+    assert_format(&lzf_rust::Error::UnknownBlockType(7));
+    assert_format(&lzf_rust::Error::Eof);
+    #[cfg(feature = "encoder")]
+    assert_format(&lzf_rust::CompressionMode::Best);
+}
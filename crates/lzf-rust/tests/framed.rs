@@ -0,0 +1,975 @@
+// SPDX-License-Identifier: ISC
+use std::fs;
+use std::path::Path;
+
+use lzf_rust::{
+    BlockStats, BlockType, CompressionMode, EncodeOptions, EofMarker, Error, FrameInfo, FrameIter,
+    SalvageReport, Write, analyze_blocks, compress, decode_block, decode_blocks,
+    decode_blocks_consumed, decode_blocks_into, decode_blocks_lenient, decode_blocks_multi,
+    decode_blocks_salvage, decode_blocks_strict, decode_blocks_to_writer, decode_blocks_with_limit,
+    decoded_len_of_blocks, encode_block, encode_blocks, encode_blocks_checked,
+    encode_blocks_to_writer, encode_blocks_with_footer, encode_blocks_with_mode,
+    encode_blocks_with_options, find_stream_end, max_compressed_size, max_encoded_blocks_size,
+    starts_with_block_header, validate_blocks,
+};
+
+fn pattern_data(size: usize) -> Vec<u8> {
+    let mut x = 0x51f1_5eedu32;
+    (0..size)
+        .map(|i| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            if x & 0x3000 == 0 { (x >> 24) as u8 } else { b"framed"[i % 6] }
+        })
+        .collect()
+}
+
+/// Framed regression inputs paired with their `decode_blocks` results.
+fn fixture_inputs() -> Vec<Vec<u8>> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/regression/framed");
+    let mut inputs = Vec::new();
+    for entry in fs::read_dir(&dir).expect("read_dir") {
+        let path = entry.expect("entry").path();
+        if path.extension().is_some_and(|ext| ext == "in") {
+            inputs.push(fs::read(&path).expect("read fixture"));
+        }
+    }
+    assert!(!inputs.is_empty());
+    inputs
+}
+
+/// Writer failing once more than `limit` bytes have been written.
+struct FailAfter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for FailAfter {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        let room = self.limit - self.written.len();
+        if room == 0 {
+            return Err(Error::Other);
+        }
+        let n = buf.len().min(room);
+        self.written.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn decode_blocks_to_writer_matches_decode_blocks() {
+    let mut inputs = fixture_inputs();
+    for size in [0usize, 1, 4096, 200_000] {
+        let mut framed = encode_blocks(&pattern_data(size), 4096).expect("encode");
+        inputs.push(framed.clone());
+        framed.push(0);
+        framed.extend_from_slice(b"trailing bytes after the end marker");
+        inputs.push(framed);
+    }
+
+    for input in inputs {
+        let expected = decode_blocks(&input);
+        let mut out = Vec::new();
+        let got = decode_blocks_to_writer(&input, &mut out);
+        match expected {
+            Ok(bytes) => {
+                assert_eq!(got, Ok(bytes.len() as u64));
+                assert_eq!(out, bytes);
+            }
+            Err(err) => assert_eq!(got, Err(err)),
+        }
+    }
+}
+
+#[test]
+fn decode_blocks_to_writer_reports_writer_errors() {
+    let input = pattern_data(50_000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+    for limit in [0usize, 1, 4095, 4096, 30_000] {
+        let mut out = FailAfter { written: Vec::new(), limit };
+        assert_eq!(decode_blocks_to_writer(&framed, &mut out), Err(Error::Other));
+        assert_eq!(out.written, input[..limit]);
+    }
+}
+
+/// Writer accepting whole writes until its `fail_at`-th call, which fails.
+struct FailOnCall {
+    written: Vec<u8>,
+    calls: usize,
+    fail_at: usize,
+}
+
+impl Write for FailOnCall {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        self.calls += 1;
+        if self.calls == self.fail_at {
+            return Err(Error::Other);
+        }
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn encode_blocks_to_writer_matches_encode_blocks_with_mode() {
+    for size in [0usize, 1, 5, 4096, 100_000] {
+        let input = pattern_data(size);
+        for block_size in [1usize, 6, 4096, 65535] {
+            for mode in [CompressionMode::Normal, CompressionMode::Best] {
+                let expected = encode_blocks_with_mode(&input, block_size, mode).expect("encode");
+                let mut out = Vec::new();
+                let n = encode_blocks_to_writer(&input, block_size, mode, &mut out).expect("write");
+                assert_eq!(n, out.len() as u64);
+                assert_eq!(out, expected, "size {size} block {block_size} {mode:?}");
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for block_size in [0usize, 65536] {
+        let err = encode_blocks_to_writer(b"x", block_size, CompressionMode::Normal, &mut out);
+        assert_eq!(err, Err(Error::InvalidParameter));
+    }
+}
+
+#[test]
+fn encode_blocks_to_writer_never_tears_frames() {
+    let input = pattern_data(40_000);
+    let full = encode_blocks(&input, 4096).expect("encode");
+    for fail_at in 1..=10 {
+        let mut out = FailOnCall { written: Vec::new(), calls: 0, fail_at };
+        let err = encode_blocks_to_writer(&input, 4096, CompressionMode::Normal, &mut out);
+        assert_eq!(err, Err(Error::Other));
+        assert!(full.starts_with(&out.written));
+        let decoded = decode_blocks(&out.written).expect("whole frames only");
+        assert_eq!(decoded, input[..(fail_at - 1) * 4096]);
+    }
+}
+
+#[test]
+fn decode_blocks_with_limit_stops_decompression_bombs() {
+    // One highly compressed 64 KiB block of zeros, chained many times: about
+    // 760 bytes of input per 64 KiB of output.
+    let block = encode_blocks(&[0u8; 65535], 65535).expect("encode");
+    assert!(block.len() < 1024);
+    let bomb = block.repeat(10_000);
+
+    assert_eq!(decode_blocks_with_limit(&bomb, 1 << 20), Err(Error::LimitExceeded));
+    assert_eq!(decode_blocks_with_limit(&bomb, 0), Err(Error::LimitExceeded));
+
+    let three = block.repeat(3);
+    assert_eq!(decode_blocks_with_limit(&three, 3 * 65535), Ok(vec![0u8; 3 * 65535]));
+    assert_eq!(decode_blocks_with_limit(&three, 3 * 65535 - 1), Err(Error::LimitExceeded));
+    assert_eq!(decode_blocks_with_limit(&[], 0), Ok(Vec::new()));
+}
+
+#[test]
+fn decode_blocks_with_limit_agrees_with_decode_blocks() {
+    for input in fixture_inputs() {
+        match decode_blocks(&input) {
+            Ok(bytes) => assert_eq!(decode_blocks_with_limit(&input, bytes.len()), Ok(bytes)),
+            Err(err) => assert_eq!(decode_blocks_with_limit(&input, usize::MAX), Err(err)),
+        }
+    }
+}
+
+#[test]
+fn decoded_len_of_blocks_agrees_with_decode_blocks() {
+    let mut inputs = fixture_inputs();
+    for size in [0usize, 1, 4096, 100_000] {
+        inputs.push(encode_blocks(&pattern_data(size), 4096).expect("encode"));
+    }
+    for input in inputs {
+        if let Ok(bytes) = decode_blocks(&input) {
+            assert_eq!(decoded_len_of_blocks(&input), Ok(bytes.len() as u64));
+        }
+    }
+}
+
+#[test]
+fn decoded_len_of_blocks_validates_structure() {
+    let framed = encode_blocks(&pattern_data(10_000), 4096).expect("encode");
+    // Truncated headers: 1..4 bytes of a block header, and a short `ZV\1`.
+    for cut in 1..5 {
+        assert_eq!(decoded_len_of_blocks(&framed[..cut]), Err(Error::InvalidHeader));
+    }
+    assert_eq!(decoded_len_of_blocks(b"ZV\x01\x00\x04\x00"), Err(Error::InvalidHeader));
+    assert_eq!(decoded_len_of_blocks(b"XV\x00\x00\x00"), Err(Error::InvalidHeader));
+    assert_eq!(decoded_len_of_blocks(b"ZV\x07\x00\x00"), Err(Error::UnknownBlockType(7)));
+
+    // Truncated payloads.
+    assert_eq!(decoded_len_of_blocks(&framed[..framed.len() - 1]), Err(Error::InvalidData));
+    assert_eq!(decoded_len_of_blocks(b"ZV\x00\x00\x03ab"), Err(Error::InvalidData));
+
+    // The end-of-stream marker stops the walk, even before garbage.
+    let mut marked = encode_blocks(b"before the marker", 4096).expect("encode");
+    marked.push(0);
+    marked.extend_from_slice(&framed[..3]);
+    assert_eq!(decoded_len_of_blocks(&marked), Ok(17));
+    assert_eq!(decoded_len_of_blocks(&[0]), Ok(0));
+}
+
+#[test]
+fn frame_iter_reports_block_layout() {
+    let mut input = Vec::new();
+    input.extend_from_slice(b"ZV\x00\x00\x03abc");
+    input.extend_from_slice(b"ZV\x01\x00\x06\x00\x06");
+    input.extend_from_slice(&[0x02, b'x', b'y', b'z', 0x20, 0x02]);
+    input.push(0);
+    input.extend_from_slice(b"ignored after the end marker");
+
+    let mut iter = FrameIter::new(&input);
+    assert_eq!(
+        iter.next(),
+        Some(Ok(FrameInfo {
+            offset: 0,
+            block_type: BlockType::Uncompressed,
+            compressed_len: 3,
+            uncompressed_len: 3,
+            payload_range: 5..8,
+            payload: b"abc",
+            checksum: None,
+        }))
+    );
+    assert_eq!(iter.position(), 8);
+    let second = iter.next().expect("second frame").expect("valid header");
+    assert_eq!(
+        (second.offset, second.block_type, second.compressed_len, second.uncompressed_len),
+        (8, BlockType::Compressed, 6, 6)
+    );
+    assert_eq!(second.payload_range, 15..21);
+    assert_eq!(second.payload, &input[15..21]);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.position(), 21);
+    assert_eq!(iter.next(), None);
+
+    assert_eq!(decode_blocks(&input).expect("decode"), b"abcxyzxyz");
+}
+
+#[test]
+fn frame_iter_rejects_malformed_headers() {
+    let valid = b"ZV\x00\x00\x01a";
+    let cases: [(&[u8], Error); 6] = [
+        (b"ZV\x00\x00", Error::InvalidHeader),
+        (b"ZX\x00\x00\x01a", Error::InvalidHeader),
+        (b"ZV\x01\x00\x01\x00", Error::InvalidHeader),
+        (b"ZV\x09\x00\x01a", Error::UnknownBlockType(9)),
+        (b"ZV\x00\x00\x02a", Error::InvalidData),
+        (b"ZV\x01\x00\x02\x00\x02\x00", Error::InvalidData),
+    ];
+    for (bad, err) in cases {
+        let mut input = valid.to_vec();
+        input.extend_from_slice(bad);
+        let items: Vec<_> = FrameIter::new(&input).collect();
+        assert_eq!(items.len(), 2, "{bad:?}");
+        assert!(items[0].is_ok());
+        assert_eq!(items[1], Err(err), "{bad:?}");
+        assert_eq!(decode_blocks(&input), Err(err));
+    }
+}
+
+#[test]
+fn encode_block_roundtrips_single_blocks() {
+    for size in [0usize, 1, 4, 5, 6, 100, 4096, 65535] {
+        let input = pattern_data(size);
+        for mode in [CompressionMode::Normal, CompressionMode::Best] {
+            let frame = encode_block(&input, mode).expect("encode_block");
+            if size > 0 {
+                assert_eq!(frame, encode_blocks_with_mode(&input, 65535, mode).expect("encode"));
+            }
+            let mut trailing = frame.clone();
+            trailing.extend_from_slice(b"ZV\x7f not examined");
+            assert_eq!(decode_block(&trailing), Ok((input.clone(), frame.len())));
+        }
+    }
+    assert_eq!(encode_block(&[0; 65536], CompressionMode::Normal), Err(Error::InvalidParameter));
+    assert_eq!(decode_block(&[]), Err(Error::Eof));
+    assert_eq!(decode_block(&[0, b'Z']), Err(Error::Eof));
+    assert_eq!(decode_block(b"ZV\x01\x00"), Err(Error::InvalidHeader));
+}
+
+#[test]
+fn concatenated_encode_block_outputs_form_a_stream() {
+    let input = pattern_data(20_000);
+    let mut framed = Vec::new();
+    let mut expected = Vec::new();
+    for (i, chunk) in input.chunks(3000).enumerate() {
+        let chunk = &chunk[..chunk.len() - i % 3];
+        framed.extend(encode_block(chunk, CompressionMode::Normal).expect("encode_block"));
+        expected.extend_from_slice(chunk);
+    }
+    assert_eq!(decode_blocks(&framed).expect("decode_blocks"), expected);
+
+    let mut rest = &framed[..];
+    let mut walked = Vec::new();
+    while !rest.is_empty() {
+        let (block, consumed) = decode_block(rest).expect("decode_block");
+        walked.extend(block);
+        rest = &rest[consumed..];
+    }
+    assert_eq!(walked, expected);
+}
+
+#[test]
+fn decode_blocks_multi_decodes_concatenated_streams() {
+    let parts = [pattern_data(5000), Vec::new(), pattern_data(1), pattern_data(70_000)];
+    let expected = parts.concat();
+    let encoded: Vec<Vec<u8>> =
+        parts.iter().map(|p| encode_blocks(p, 4096).expect("encode")).collect();
+
+    // Without end-of-stream markers the concatenation is a single stream.
+    let plain = encoded.concat();
+    assert_eq!(decode_blocks(&plain), Ok(expected.clone()));
+    assert_eq!(decode_blocks_multi(&plain), Ok(expected.clone()));
+    assert_eq!(decode_blocks_consumed(&plain), Ok((expected.clone(), plain.len())));
+
+    // With markers, `decode_blocks` stops after the first member.
+    let mut marked = Vec::new();
+    let mut boundaries = Vec::new();
+    for member in &encoded {
+        marked.extend_from_slice(member);
+        marked.push(0);
+        boundaries.push(marked.len());
+    }
+    marked.push(0);
+    assert_eq!(decode_blocks(&marked), Ok(parts[0].clone()));
+    assert_eq!(decode_blocks_multi(&marked), Ok(expected.clone()));
+
+    let mut ip = 0;
+    for (part, end) in parts.iter().zip(boundaries) {
+        let (decoded, consumed) = decode_blocks_consumed(&marked[ip..]).expect("member");
+        assert_eq!(&decoded, part);
+        ip += consumed;
+        assert_eq!(ip, end);
+    }
+    assert_eq!(decode_blocks_consumed(&marked[ip..]), Ok((Vec::new(), 1)));
+}
+
+#[test]
+fn decode_blocks_multi_rejects_trailing_garbage() {
+    let mut input = encode_blocks(b"member", 4096).expect("encode");
+    input.push(0);
+    input.extend_from_slice(b"garbage");
+    assert_eq!(decode_blocks(&input), Ok(b"member".to_vec()));
+    assert_eq!(decode_blocks_multi(&input), Err(Error::InvalidHeader));
+    assert_eq!(decode_blocks_multi(&[]), Ok(Vec::new()));
+    assert_eq!(decode_blocks_multi(&[0, 0, 0]), Ok(Vec::new()));
+}
+
+#[test]
+fn decode_blocks_into_appends_and_restores_on_error() {
+    let input = pattern_data(10_000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+
+    let mut arena = b"existing record".to_vec();
+    assert_eq!(decode_blocks_into(&framed, &mut arena), Ok(input.len()));
+    assert_eq!(arena[..15], b"existing record"[..]);
+    assert_eq!(arena[15..], input[..]);
+
+    // Corrupt the second block's header so decoding fails after the first
+    // block has been appended.
+    let second = FrameIter::new(&framed).nth(1).expect("second block").expect("valid").offset;
+    let mut broken = framed.clone();
+    broken[second + 2] = 0x7f;
+    assert_eq!(decode_blocks(&broken), Err(Error::UnknownBlockType(0x7f)));
+
+    let before = arena.clone();
+    assert_eq!(decode_blocks_into(&broken, &mut arena), Err(Error::UnknownBlockType(0x7f)));
+    assert_eq!(arena, before);
+
+    // Same for a corrupt compressed payload in the second block.
+    let mut broken = framed.clone();
+    let payload_start = second + 7;
+    broken[payload_start] = 0x1f;
+    broken.truncate(payload_start + 3);
+    broken[second + 3..second + 5].copy_from_slice(&3u16.to_be_bytes());
+    assert_eq!(decode_blocks_into(&broken, &mut arena), Err(Error::InvalidData));
+    assert_eq!(arena, before);
+}
+
+#[test]
+fn max_encoded_blocks_size_is_never_exceeded() {
+    let mut x = 0x0bad_5eedu32;
+    let noise: Vec<u8> = (0..70_000)
+        .map(|_| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            (x >> 24) as u8
+        })
+        .collect();
+
+    for input in [noise, pattern_data(70_000), vec![0u8; 70_000]] {
+        for len in [0usize, 1, 4, 5, 6, 4095, 4096, 4097, 70_000] {
+            for block_size in [1usize, 5, 6, 100, 4096, 65535] {
+                for mode in [CompressionMode::Normal, CompressionMode::Best] {
+                    let framed =
+                        encode_blocks_with_mode(&input[..len], block_size, mode).expect("encode");
+                    let bound = max_encoded_blocks_size(len, block_size);
+                    assert!(framed.len() <= bound, "len {len} block {block_size}");
+                }
+            }
+        }
+    }
+
+    // Blocks too small to compress are all stored, reaching the bound.
+    let framed = encode_blocks(&pattern_data(1000), 5).expect("encode");
+    assert_eq!(framed.len(), max_encoded_blocks_size(1000, 5));
+    assert_eq!(max_encoded_blocks_size(10, 0), max_encoded_blocks_size(10, 1));
+    assert_eq!(max_encoded_blocks_size(200_000, 1 << 20), 200_000 + 4 * 5);
+}
+
+#[test]
+fn checked_blocks_roundtrip_and_report_checksums() {
+    let input = pattern_data(3 * 4096 + 17);
+    for mode in [CompressionMode::Normal, CompressionMode::Best] {
+        let framed = encode_blocks_checked(&input, 4096, mode).expect("encode");
+        assert_eq!(decode_blocks(&framed).expect("decode"), input);
+        assert_eq!(decoded_len_of_blocks(&framed), Ok(input.len() as u64));
+
+        let frames: Vec<FrameInfo<'_>> =
+            FrameIter::new(&framed).collect::<Result<_, _>>().expect("frames");
+        assert_eq!(frames.len(), 4);
+        for (frame, block) in frames.iter().zip(input.chunks(4096)) {
+            assert_eq!(frame.block_type, BlockType::Checked);
+            assert_eq!(frame.checksum, Some(crc32_reference(block)));
+            assert_eq!(frame.uncompressed_len, block.len());
+        }
+    }
+
+    // Incompressible blocks are stored verbatim with cs == us.
+    let noise: Vec<u8> = (0..64u32).map(|i| (i.wrapping_mul(0x9e37_79b9) >> 24) as u8).collect();
+    let framed = encode_blocks_checked(&noise, 4096, CompressionMode::Normal).expect("encode");
+    let frame = FrameIter::new(&framed).next().expect("frame").expect("valid");
+    assert!(!frame.is_compressed());
+    assert_eq!(frame.payload, noise.as_slice());
+    assert_eq!(decode_blocks(&framed).expect("decode"), noise);
+}
+
+#[test]
+fn checked_blocks_detect_corrupt_payloads() {
+    let compressible = pattern_data(4096);
+    let noise: Vec<u8> = (0..64u32).map(|i| (i.wrapping_mul(0x9e37_79b9) >> 24) as u8).collect();
+    for input in [compressible, noise] {
+        let framed = encode_blocks_checked(&input, 4096, CompressionMode::Normal).expect("encode");
+        let mut corrupt = framed.clone();
+        // Flip a bit in the final payload byte, which is a literal in both
+        // the stored and compressed layouts.
+        *corrupt.last_mut().expect("payload") ^= 0x10;
+
+        assert_eq!(decode_blocks(&corrupt), Err(Error::ChecksumMismatch));
+        assert_eq!(
+            decode_blocks_to_writer(&corrupt, &mut Vec::new()),
+            Err(Error::ChecksumMismatch)
+        );
+
+        let mut header = framed.clone();
+        header[7] ^= 1;
+        assert_eq!(decode_blocks(&header), Err(Error::ChecksumMismatch));
+    }
+}
+
+/// Bitwise CRC-32 (IEEE) used to cross-check frame checksums.
+fn crc32_reference(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Builds `ZV` blocks of the given vendor type in the `ZV\0` layout.
+fn unknown_block(block_type: u8, payload: &[u8]) -> Vec<u8> {
+    let mut block = vec![b'Z', b'V', block_type];
+    block.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    block.extend_from_slice(payload);
+    block
+}
+
+#[test]
+fn lenient_decode_skips_unknown_blocks() {
+    let input = pattern_data(3 * 4096 + 9);
+    let standard = encode_blocks(&input, 4096).expect("encode");
+
+    let mut interleaved = unknown_block(0x80, b"vendor prologue");
+    for frame in FrameIter::new(&standard) {
+        let frame = frame.expect("frame");
+        interleaved.extend_from_slice(&standard[frame.offset..frame.end()]);
+        interleaved.extend(unknown_block(0x42, &[0xaa; 300]));
+        interleaved.extend(unknown_block(0xff, b""));
+    }
+
+    assert_eq!(decode_blocks(&interleaved), Err(Error::UnknownBlockType(0x80)));
+    assert_eq!(decode_blocks_lenient(&interleaved).expect("lenient"), input);
+    assert_eq!(decode_blocks_lenient(&standard).expect("standard"), input);
+
+    // Skipped blocks still honour the end-of-stream marker.
+    interleaved.push(0);
+    interleaved.extend_from_slice(b"trailing");
+    assert_eq!(decode_blocks_lenient(&interleaved).expect("marker"), input);
+}
+
+#[test]
+fn lenient_decode_rejects_truncated_unknown_blocks() {
+    let mut input = encode_blocks(b"kept", 4096).expect("encode");
+    let valid_len = input.len();
+    input.extend(unknown_block(0x33, b"cut short"));
+
+    assert_eq!(decode_blocks_lenient(&input[..valid_len + 4]), Err(Error::InvalidHeader));
+    assert_eq!(decode_blocks_lenient(&input[..input.len() - 1]), Err(Error::InvalidData));
+    assert_eq!(decode_blocks_lenient(b"ZX\x33\x00\x00"), Err(Error::InvalidHeader));
+}
+
+#[test]
+fn analyze_blocks_counts_a_known_block_mix() {
+    let mut input = Vec::new();
+    input.extend_from_slice(b"ZV\x00\x00\x04abcd");
+    input.extend_from_slice(b"ZV\x01\x00\x06\x00\x06");
+    input.extend_from_slice(&[0x02, b'x', b'y', b'z', 0x20, 0x02]);
+    input.extend_from_slice(b"ZV\x00\x00\x00");
+    input.extend_from_slice(b"ZV\x01\x00\x03\x00\x0c");
+    input.extend_from_slice(&[0x00, b'q', 0xe0]);
+    input.push(0);
+    input.extend_from_slice(b"not part of the stream");
+
+    let stats = analyze_blocks(&input).expect("analyze");
+    assert_eq!(
+        stats,
+        BlockStats {
+            blocks: 4,
+            compressed_blocks: 2,
+            uncompressed_blocks: 2,
+            payload_len: 4 + 6 + 3,
+            header_len: 5 + 7 + 5 + 7,
+            decoded_len: 4 + 6 + 12,
+            stream_len: 9 + 13 + 5 + 10 + 1,
+            eof_marker: true,
+            min_block_ratio: Some(0.25),
+            max_block_ratio: Some(1.0),
+            avg_block_ratio: Some((1.0 + 1.0 + 0.25) / 3.0),
+        }
+    );
+    assert_eq!(stats.ratio(), Some(38.0 / 22.0));
+}
+
+#[test]
+fn analyze_blocks_handles_uncompressed_and_empty_streams() {
+    let mut x = 0x2545_f491u32;
+    let noise: Vec<u8> = (0..10_000)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect();
+    let framed = encode_blocks(&noise, 1000).expect("encode");
+    let stats = analyze_blocks(&framed).expect("analyze");
+    assert_eq!((stats.blocks, stats.compressed_blocks, stats.uncompressed_blocks), (10, 0, 10));
+    assert_eq!((stats.payload_len, stats.header_len, stats.decoded_len), (10_000, 50, 10_000));
+    assert_eq!((stats.stream_len, stats.eof_marker), (framed.len(), false));
+    assert_eq!(stats.min_block_ratio, Some(1.0));
+    assert_eq!(stats.max_block_ratio, Some(1.0));
+    assert_eq!(stats.avg_block_ratio, Some(1.0));
+    assert_eq!(stats.ratio(), Some(1.005));
+
+    let empty = analyze_blocks(&[]).expect("empty");
+    assert_eq!(empty, BlockStats::default());
+    assert_eq!(empty.ratio(), None);
+    let marker = analyze_blocks(&[0]).expect("marker only");
+    assert_eq!((marker.blocks, marker.stream_len, marker.eof_marker), (0, 1, true));
+
+    assert_eq!(analyze_blocks(b"ZV\x01\x00"), Err(Error::InvalidHeader));
+    assert_eq!(analyze_blocks(b"ZV\x00\x00\x05ab"), Err(Error::InvalidData));
+}
+
+#[test]
+fn decode_blocks_strict_applies_the_marker_policy() {
+    let input = pattern_data(10_000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+    let mut marked = framed.clone();
+    marked.push(0);
+
+    for (stream, policy, expected) in [
+        (&framed, EofMarker::Optional, Ok(())),
+        (&framed, EofMarker::Forbidden, Ok(())),
+        (&framed, EofMarker::Required, Err(Error::Eof)),
+        (&marked, EofMarker::Optional, Ok(())),
+        (&marked, EofMarker::Required, Ok(())),
+        (&marked, EofMarker::Forbidden, Err(Error::InvalidData)),
+    ] {
+        let got = decode_blocks_strict(stream, policy);
+        assert_eq!(got.map(|out| assert_eq!(out, input)), expected, "{policy:?}");
+    }
+
+    assert_eq!(decode_blocks_strict(&[], EofMarker::Optional), Ok(Vec::new()));
+    assert_eq!(decode_blocks_strict(&[0], EofMarker::Required), Ok(Vec::new()));
+    assert_eq!(decode_blocks_strict(&[0, 0], EofMarker::Optional), Err(Error::InvalidData));
+}
+
+#[test]
+fn decode_blocks_strict_reports_truncation_as_eof() {
+    let framed =
+        encode_blocks_checked(&pattern_data(5000), 4096, CompressionMode::Normal).expect("encode");
+    let second = FrameIter::new(&framed).nth(1).expect("second").expect("valid").offset;
+    for end in second + 1..framed.len() {
+        assert_eq!(
+            decode_blocks_strict(&framed[..end], EofMarker::Optional),
+            Err(Error::Eof),
+            "cut at {end}"
+        );
+    }
+
+    // Malformed rather than cut-off headers keep their errors.
+    let mut bad = framed[..second].to_vec();
+    bad.extend_from_slice(b"ZX\x00");
+    assert_eq!(decode_blocks_strict(&bad, EofMarker::Optional), Err(Error::InvalidHeader));
+    bad.truncate(second);
+    bad.extend_from_slice(b"ZV\x09\x00\x01");
+    assert_eq!(decode_blocks_strict(&bad, EofMarker::Optional), Err(Error::UnknownBlockType(9)));
+}
+
+#[test]
+fn find_stream_end_locates_embedded_streams() {
+    let mut x = 0x1234_5678u32;
+    let mut random = |len: usize| -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    };
+
+    for (size, block_size) in [(0usize, 4096usize), (1, 4096), (5000, 1000), (70_000, 65535)] {
+        let input = pattern_data(size);
+        for checked in [false, true] {
+            let framed = if checked {
+                encode_blocks_checked(&input, block_size, CompressionMode::Normal)
+            } else {
+                encode_blocks(&input, block_size)
+            }
+            .expect("encode");
+
+            assert_eq!(find_stream_end(&framed), Ok(framed.len()));
+            for trailer_len in [0usize, 1, 17, 1000] {
+                let mut marked = framed.clone();
+                marked.push(0);
+                marked.extend(random(trailer_len));
+                assert_eq!(find_stream_end(&marked), Ok(framed.len() + 1));
+            }
+        }
+    }
+}
+
+#[test]
+fn find_stream_end_distinguishes_truncation_from_bad_headers() {
+    let framed = encode_blocks(&pattern_data(5000), 4096).expect("encode");
+    let second = FrameIter::new(&framed).nth(1).expect("second").expect("valid").offset;
+    for end in second + 2..framed.len() {
+        assert_eq!(find_stream_end(&framed[..end]), Err(Error::Eof), "cut at {end}");
+    }
+    assert_eq!(find_stream_end(&framed[..second + 1]), Err(Error::Eof));
+
+    let mut bad = framed[..second].to_vec();
+    bad.extend_from_slice(b"ZV\x07\x00\x01x");
+    assert_eq!(find_stream_end(&bad), Err(Error::UnknownBlockType(7)));
+
+    // Only the marker or the end of the input ends the stream.
+    for trailer in [&b"\xff"[..], b"Zx", b"zV\x00\x00\x01x", b"unrelated trailer"] {
+        let mut bad = framed.clone();
+        bad.extend_from_slice(trailer);
+        assert_eq!(find_stream_end(&bad), Err(Error::InvalidHeader), "{trailer:?}");
+    }
+}
+
+#[test]
+fn validate_blocks_agrees_with_decode_blocks() {
+    for input in fixture_inputs() {
+        let expected = decode_blocks(&input).map(|out| out.len() as u64);
+        assert_eq!(validate_blocks(&input).map(|report| report.decoded_len), expected);
+    }
+
+    let input = pattern_data(3 * 4096 + 1);
+    let framed = encode_blocks_checked(&input, 4096, CompressionMode::Normal).expect("encode");
+    let report = validate_blocks(&framed).expect("validate");
+    assert_eq!((report.blocks, report.decoded_len), (4, input.len() as u64));
+    let mut corrupt = framed.clone();
+    *corrupt.last_mut().expect("payload") ^= 0x10;
+    assert_eq!(validate_blocks(&corrupt), Err(Error::ChecksumMismatch));
+}
+
+#[test]
+fn validate_blocks_catches_payloads_not_matching_their_headers() {
+    let framed = encode_blocks(&pattern_data(4096), 4096).expect("encode");
+    assert_eq!(&framed[..3], b"ZV\x01");
+
+    // Declared uncompressed length one byte too short or too long: the
+    // headers still parse, so only decoding the payload notices.
+    let mut short = framed.clone();
+    short[6] = short[6].wrapping_sub(1);
+    short[5] -= u8::from(short[6] == 0xff);
+    let mut long = framed.clone();
+    long[6] = long[6].wrapping_add(1);
+    long[5] += u8::from(long[6] == 0);
+
+    for (stream, err) in [(short, Error::OutputTooSmall), (long, Error::InvalidData)] {
+        assert!(decoded_len_of_blocks(&stream).is_ok());
+        assert_eq!(validate_blocks(&stream), Err(err));
+        assert_eq!(decode_blocks(&stream), Err(err));
+    }
+}
+
+fn noise(size: usize) -> Vec<u8> {
+    let mut x = 0x2545_f491_4f6c_dd1du64;
+    (0..size)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+#[test]
+fn skip_incompressible_stores_noise_and_compresses_patterns() {
+    let options = EncodeOptions::new().skip_incompressible(true);
+    let mut input = noise(3 * 4096);
+    input.extend_from_slice(&pattern_data(3 * 4096));
+
+    let framed = encode_blocks_with_options(&input, 4096, options).unwrap();
+    assert_eq!(framed, encode_blocks(&input, 4096).unwrap());
+    let types: Vec<BlockType> = FrameIter::new(&framed).map(|f| f.unwrap().block_type).collect();
+    assert_eq!(types[..3], [BlockType::Uncompressed; 3]);
+    assert_eq!(types[3..], [BlockType::Compressed; 3]);
+    assert_eq!(decode_blocks(&framed).unwrap(), input);
+
+    // Short blocks are not probed.
+    let short = noise(512);
+    assert_eq!(
+        encode_blocks_with_options(&short, 4096, options).unwrap(),
+        encode_blocks(&short, 4096).unwrap()
+    );
+}
+
+#[test]
+fn encode_blocks_with_options_defaults_match_existing_encoders() {
+    let input = pattern_data(40_000);
+    let defaults = EncodeOptions::default();
+    assert_eq!(
+        encode_blocks_with_options(&input, 4096, defaults).unwrap(),
+        encode_blocks(&input, 4096).unwrap()
+    );
+    assert_eq!(
+        encode_blocks_with_options(&input, 4096, defaults.mode(CompressionMode::Best)).unwrap(),
+        encode_blocks_with_mode(&input, 4096, CompressionMode::Best).unwrap()
+    );
+    assert_eq!(
+        encode_blocks_with_options(&input, 4096, defaults.checksums(true)).unwrap(),
+        encode_blocks_checked(&input, 4096, CompressionMode::Normal).unwrap()
+    );
+    assert_eq!(
+        encode_blocks_with_options(&input, 0, defaults).unwrap_err(),
+        Error::InvalidParameter
+    );
+}
+
+fn block_type_with(block: &[u8], options: EncodeOptions) -> BlockType {
+    let framed = encode_blocks_with_options(block, 65535, options).unwrap();
+    assert_eq!(decode_blocks(&framed).unwrap(), block);
+    FrameIter::new(&framed).next().unwrap().unwrap().block_type
+}
+
+#[test]
+fn min_savings_selects_block_type_at_the_threshold() {
+    let block = pattern_data(1000);
+    let mut compressed = vec![0u8; max_compressed_size(block.len())];
+    let savings = block.len() - compress(&block, &mut compressed).unwrap();
+    assert!(savings > 4);
+
+    let options = EncodeOptions::new();
+    assert_eq!(block_type_with(&block, options), BlockType::Compressed);
+    assert_eq!(block_type_with(&block, options.min_savings_bytes(savings)), BlockType::Compressed);
+    assert_eq!(
+        block_type_with(&block, options.min_savings_bytes(savings + 1)),
+        BlockType::Uncompressed
+    );
+
+    // For a 1000-byte block, per mille and bytes coincide.
+    let per_mille = u16::try_from(savings).unwrap();
+    assert_eq!(
+        block_type_with(&block, options.min_savings_per_mille(per_mille)),
+        BlockType::Compressed
+    );
+    assert_eq!(
+        block_type_with(&block, options.min_savings_per_mille(per_mille + 1)),
+        BlockType::Uncompressed
+    );
+    assert_eq!(
+        block_type_with(&block, options.min_savings_per_mille(1).min_savings_bytes(savings + 1)),
+        BlockType::Uncompressed
+    );
+    assert_eq!(
+        block_type_with(&block, options.min_savings_per_mille(u16::MAX)),
+        BlockType::Uncompressed
+    );
+}
+
+#[test]
+fn min_savings_never_drops_below_the_header_overhead() {
+    assert_eq!(EncodeOptions::new().min_savings_bytes(0), EncodeOptions::new());
+    // Blocks no longer than the 4-byte minimum are always stored.
+    for block in [&b"a"[..], b"aaaa", b"aaaaaaaaaaaa"] {
+        let expected =
+            if block.len() > 4 { BlockType::Compressed } else { BlockType::Uncompressed };
+        assert_eq!(block_type_with(block, EncodeOptions::new().min_savings_bytes(0)), expected);
+    }
+}
+
+#[test]
+fn stream_footer_is_verified_by_every_slice_decoder() {
+    let input = pattern_data(20_000);
+    let framed = encode_blocks_with_footer(&input, 4096, CompressionMode::Normal).unwrap();
+    let body = encode_blocks(&input, 4096).unwrap();
+    assert_eq!(&framed[..body.len()], body);
+    assert_eq!(framed.len(), body.len() + 1 + 16);
+
+    assert_eq!(decode_blocks(&framed).unwrap(), input);
+    assert_eq!(decode_blocks_consumed(&framed).unwrap(), (input.clone(), framed.len()));
+    assert_eq!(find_stream_end(&framed).unwrap(), framed.len());
+    assert_eq!(validate_blocks(&framed).unwrap().decoded_len, input.len() as u64);
+    assert_eq!(decode_blocks_strict(&framed, EofMarker::Optional).unwrap(), input);
+    let mut out = Vec::new();
+    assert_eq!(decode_blocks_to_writer(&framed, &mut out).unwrap(), input.len() as u64);
+    assert_eq!(out, input);
+
+    let mut twice = framed.clone();
+    twice.extend_from_slice(&framed);
+    assert_eq!(decode_blocks_multi(&twice).unwrap(), [&input[..], &input[..]].concat());
+
+    // A wrong length fails just like a wrong CRC.
+    let mut bad_len = framed.clone();
+    let len_at = body.len() + 1 + 11;
+    bad_len[len_at] ^= 1;
+    assert_eq!(decode_blocks(&bad_len), Err(Error::ChecksumMismatch));
+    assert_eq!(validate_blocks(&bad_len), Err(Error::ChecksumMismatch));
+    assert_eq!(decode_blocks_to_writer(&bad_len, &mut Vec::new()), Err(Error::ChecksumMismatch));
+    assert_eq!(decode_blocks_multi(&bad_len), Err(Error::ChecksumMismatch));
+    let mut arena = b"kept".to_vec();
+    assert_eq!(decode_blocks_into(&bad_len, &mut arena), Err(Error::ChecksumMismatch));
+    assert_eq!(arena, b"kept");
+
+    let truncated = &framed[..framed.len() - 1];
+    assert_eq!(decode_blocks(truncated), Err(Error::Eof));
+    assert_eq!(find_stream_end(truncated), Err(Error::Eof));
+}
+
+#[test]
+fn streams_without_footers_decode_unchanged() {
+    let input = pattern_data(10_000);
+    let mut framed = encode_blocks(&input, 4096).unwrap();
+    framed.push(0);
+    let end = framed.len();
+    // Trailing data that is not a footer is ignored, as before.
+    framed.extend_from_slice(b"LZF trailer");
+    assert_eq!(decode_blocks(&framed).unwrap(), input);
+    assert_eq!(decode_blocks_consumed(&framed).unwrap().1, end);
+    assert_eq!(find_stream_end(&framed).unwrap(), end);
+}
+
+#[test]
+fn salvage_recovers_blocks_around_a_damaged_one() {
+    let input = pattern_data(5 * 4096);
+    let framed = encode_blocks_checked(&input, 4096, CompressionMode::Normal).expect("encode");
+    let frames: Vec<FrameInfo<'_>> =
+        FrameIter::new(&framed).collect::<Result<_, _>>().expect("frames");
+    assert_eq!(frames.len(), 5);
+    let expected = [&input[..8192], &input[12_288..]].concat();
+
+    // Payload damage, caught by the block checksum.
+    let mut damaged = framed.clone();
+    damaged[frames[2].payload_range.start + 10] ^= 0x55;
+    assert!(decode_blocks(&damaged).is_err());
+    let (output, report) = decode_blocks_salvage(&damaged);
+    assert_eq!(output, expected);
+    assert_eq!(report.blocks, 4);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0], frames[2].offset..frames[3].offset);
+
+    // Header damage that makes the block swallow part of the next one.
+    let mut damaged = framed.clone();
+    damaged[frames[2].offset + 4] = damaged[frames[2].offset + 4].wrapping_add(40);
+    let (output, report) = decode_blocks_salvage(&damaged);
+    assert_eq!(output, expected);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0], frames[2].offset..frames[3].offset);
+
+    // Garbage with stray magic bytes spliced in between blocks.
+    let mut garbage = noise(3000);
+    for at in (50..garbage.len() - 20).step_by(97) {
+        garbage[at..at + 5].copy_from_slice(&[b'Z', b'V', 0, 0, 3]);
+    }
+    let mut damaged = framed[..frames[1].offset].to_vec();
+    damaged.extend_from_slice(&garbage);
+    damaged.extend_from_slice(&framed[frames[1].offset..]);
+    let (output, report) = decode_blocks_salvage(&damaged);
+    assert_eq!(output, input);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0], frames[1].offset..frames[1].offset + garbage.len());
+    assert_eq!(report.skipped_bytes(), garbage.len());
+
+    let (output, report) = decode_blocks_salvage(&framed);
+    assert_eq!((output, report.blocks, report.skipped_bytes()), (input, 5, 0));
+}
+
+#[test]
+fn salvage_terminates_on_garbage() {
+    let garbage = noise(50_000);
+    let (output, report) = decode_blocks_salvage(&garbage);
+    assert!(output.is_empty());
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0], 0..garbage.len());
+
+    let (output, report) = decode_blocks_salvage(b"ZVZVZVZVZV");
+    assert!(output.is_empty());
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0], 0..10);
+    assert_eq!(decode_blocks_salvage(&[]), (Vec::new(), SalvageReport::default()));
+}
+
+#[test]
+fn block_header_sniffing() {
+    let input = noise(10_000);
+    for framed in [
+        encode_blocks(&input, 4096).unwrap(),
+        encode_blocks(b"abcabcabcabcabcabcabc", 4096).unwrap(),
+        encode_blocks_checked(&input, 4096, CompressionMode::Normal).unwrap(),
+    ] {
+        assert!(starts_with_block_header(&framed));
+        let header_len = match framed[2] {
+            0 => 5,
+            1 => 7,
+            _ => 11,
+        };
+        assert!(starts_with_block_header(&framed[..header_len]));
+        assert!(!starts_with_block_header(&framed[..header_len - 1]));
+    }
+    // Nothing past the header is checked.
+    assert!(starts_with_block_header(b"ZV\x00\xff\xff"));
+    assert!(!starts_with_block_header(b"ZV\x03\x00\x01\x00\x01"));
+    assert!(!starts_with_block_header(b"zv\x00\x00\x01a"));
+    assert!(!starts_with_block_header(&[0]));
+    assert!(!starts_with_block_header(&[]));
+    assert!(!starts_with_block_header(&input));
+}
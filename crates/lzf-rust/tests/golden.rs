@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: ISC
+//! Cross-platform encoder determinism check.
+//!
+//! Encodes the vendored corpus in every mode (raw and framed) and compares a
+//! digest of each output with `tests/data/golden_digests.txt`. The encoder is
+//! required to produce identical bytes on every target, so this test must
+//! pass unchanged on big-endian and 32-bit targets as well.
+//!
+//! Digests only change on intentional encoder output changes; re-bless with
+//! `LZF_BLESS_GOLDEN=1 cargo test -p lzf-rust --test golden`.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lzf_rust::{CompressionMode, compress_with_mode, encode_blocks_with_mode, max_compressed_size};
+
+const BLESS_ENV: &str = "LZF_BLESS_GOLDEN";
+
+fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data")
+}
+
+/// 64-bit FNV-1a, spelled out so the digest itself is platform independent.
+fn fnv1a64(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for &b in data {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn encoded_outputs() -> Vec<(String, Vec<u8>)> {
+    let corpus = data_dir().join("corpus");
+    let mut names: Vec<String> = fs::read_dir(&corpus)
+        .unwrap_or_else(|e| panic!("read_dir {}: {e}", corpus.display()))
+        .map(|entry| entry.expect("read_dir entry").file_name().into_string().expect("UTF-8 name"))
+        .filter(|name| name != "baselines.txt")
+        .collect();
+    names.sort();
+
+    let mut outputs = Vec::new();
+    for name in names {
+        let input = fs::read(corpus.join(&name)).unwrap_or_else(|e| panic!("read {name}: {e}"));
+        for (mode, mode_name) in
+            [(CompressionMode::Normal, "normal"), (CompressionMode::Best, "best")]
+        {
+            let mut raw = vec![0u8; max_compressed_size(input.len())];
+            let n = compress_with_mode(&input, &mut raw, mode).expect("compress");
+            raw.truncate(n);
+            outputs.push((format!("{name} raw {mode_name}"), raw));
+
+            let framed = encode_blocks_with_mode(&input, 4096, mode).expect("encode_blocks");
+            outputs.push((format!("{name} framed {mode_name}"), framed));
+        }
+    }
+    outputs
+}
+
+#[test]
+fn encoder_output_matches_golden_digests() {
+    let path = data_dir().join("golden_digests.txt");
+    let rendered: String = encoded_outputs()
+        .into_iter()
+        .map(|(key, out)| format!("{key} {} {:016x}\n", out.len(), fnv1a64(&out)))
+        .collect();
+
+    if std::env::var_os(BLESS_ENV).is_some() {
+        fs::write(&path, &rendered).unwrap_or_else(|e| panic!("write {}: {e}", path.display()));
+        return;
+    }
+
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {e}", path.display()));
+    for (got, want) in rendered.lines().zip(expected.lines()) {
+        assert_eq!(got, want, "encoder output diverged from the golden digest");
+    }
+    assert_eq!(rendered.lines().count(), expected.lines().count(), "golden digest count");
+}
@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: ISC
+use lzf_rust::{
+    Error, LzfDecodeWriter, LzfEncodeReader, Read, Write, decode_blocks, encode_blocks,
+};
+
+fn pattern_data(size: usize) -> Vec<u8> {
+    let mut out = vec![0u8; size];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = ((i * 17) ^ (i >> 3) ^ 0x5a) as u8;
+    }
+    out
+}
+
+fn decode_in_chunks(framed: &[u8], splits: &[usize]) -> lzf_rust::Result<Vec<u8>> {
+    let mut writer = LzfDecodeWriter::new(Vec::new());
+    let mut start = 0;
+    for &end in splits.iter().chain([&framed.len()]) {
+        writer.write_all(&framed[start..end])?;
+        start = end;
+    }
+    writer.finish()
+}
+
+#[test]
+fn decode_writer_matches_decode_blocks_at_every_split_point() {
+    let input = pattern_data(3000);
+    let mut framed = encode_blocks(&input, 700).expect("encode");
+    framed.extend_from_slice(
+        &lzf_rust::encode_blocks_checked(&input[..900], 400, lzf_rust::CompressionMode::Normal)
+            .expect("encode"),
+    );
+    framed.push(0);
+    let expected = decode_blocks(&framed).expect("decode");
+
+    for split in 0..=framed.len() {
+        assert_eq!(decode_in_chunks(&framed, &[split]).expect("decode"), expected, "{split}");
+    }
+    for first in (0..framed.len()).step_by(7) {
+        for second in (first..=framed.len()).step_by(5) {
+            let decoded = decode_in_chunks(&framed, &[first, second]).expect("decode");
+            assert_eq!(decoded, expected, "{first} {second}");
+        }
+    }
+    let every_byte: Vec<usize> = (1..framed.len()).collect();
+    assert_eq!(decode_in_chunks(&framed, &every_byte).expect("decode"), expected);
+}
+
+#[test]
+fn decode_writer_stops_at_the_end_of_stream_marker() {
+    let input = pattern_data(5000);
+    let mut framed =
+        lzf_rust::encode_blocks_with_footer(&input, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode");
+    let mut writer = LzfDecodeWriter::new(Vec::new());
+    writer.write_all(&framed[..framed.len() - 17]).expect("write");
+    assert!(!writer.is_finished());
+    framed.extend_from_slice(b"trailing garbage");
+    writer.write_all(&framed[framed.len() - 33..]).expect("write");
+    assert!(writer.is_finished());
+    assert_eq!(writer.finish().expect("finish"), input);
+}
+
+#[test]
+fn decode_writer_reports_truncated_and_corrupt_input() {
+    let input = pattern_data(5000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+    for cut in [1, 3, 6, 100, framed.len() - 1] {
+        let mut writer = LzfDecodeWriter::new(Vec::new());
+        writer.write_all(&framed[..cut]).expect("write");
+        assert!(matches!(writer.finish(), Err(Error::Eof)), "{cut}");
+    }
+
+    let mut writer = LzfDecodeWriter::new(Vec::new());
+    assert_eq!(writer.write_all(b"XV\x00\x00\x01a"), Err(Error::InvalidHeader));
+    let mut writer = LzfDecodeWriter::new(Vec::new());
+    assert_eq!(writer.write_all(b"ZV\x07\x00\x01a"), Err(Error::UnknownBlockType(7)));
+}
+
+fn drain(reader: &mut impl Read, buf_len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; buf_len];
+    loop {
+        match reader.read(&mut buf).expect("read") {
+            0 => return out,
+            n => out.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn encode_reader_matches_encode_blocks_for_any_read_size() {
+    let input = pattern_data(3 * 4096 + 1234);
+    let expected = encode_blocks(&input, 4096).expect("encode");
+    for buf_len in [1, 2, 5, 11, 4096, 4097, 100_000] {
+        let mut reader = LzfEncodeReader::new(input.as_slice(), 4096).expect("reader");
+        assert_eq!(drain(&mut reader, buf_len), expected, "{buf_len}");
+    }
+
+    let mut reader = LzfEncodeReader::new(&b""[..], 4096).expect("reader");
+    assert!(drain(&mut reader, 7).is_empty());
+    let mut reader = LzfEncodeReader::new_with_eof_marker(&b""[..], 4096).expect("reader");
+    assert_eq!(drain(&mut reader, 7), [0]);
+
+    let mut reader = LzfEncodeReader::new_with_eof_marker_and_mode(
+        input.as_slice(),
+        1000,
+        lzf_rust::CompressionMode::Best,
+    )
+    .expect("reader");
+    let mut expected =
+        lzf_rust::encode_blocks_with_mode(&input, 1000, lzf_rust::CompressionMode::Best)
+            .expect("encode");
+    expected.push(0);
+    assert_eq!(drain(&mut reader, 333), expected);
+
+    assert!(matches!(LzfEncodeReader::new(&b""[..], 0), Err(Error::InvalidParameter)));
+    assert!(matches!(LzfEncodeReader::new(&b""[..], 65536), Err(Error::InvalidParameter)));
+}
+
+#[test]
+fn encode_reader_handles_short_source_reads() {
+    /// Source handing out at most three bytes per read, interrupted every
+    /// other call.
+    struct Dribble<'a>(&'a [u8], bool);
+
+    impl Read for Dribble<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> lzf_rust::Result<usize> {
+            self.1 = !self.1;
+            if self.1 {
+                return Err(Error::Interrupted);
+            }
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    let input = pattern_data(10_000);
+    let mut reader = LzfEncodeReader::new(Dribble(&input, false), 4096).expect("reader");
+    let framed = drain(&mut reader, 100);
+    assert_eq!(framed, encode_blocks(&input, 4096).expect("encode"));
+    assert_eq!(decode_in_chunks(&framed, &[framed.len() / 2]).expect("decode"), input);
+}
@@ -0,0 +1,415 @@
+// SPDX-License-Identifier: ISC
+//! Tests of the io adapters and helpers. They run against the `std::io`
+//! impls by default and against the crate's own with
+//! `--no-default-features --features encoder`; `Ring` implements only the
+//! crate traits, so it works the same either way.
+use std::collections::VecDeque;
+
+use lzf_rust::{
+    Cursor, Error, LzfReader, LzfWriter, Read, ReadExt, Write, copy, copy_with_buffer,
+    decode_blocks_to_writer, empty, encode_blocks, sink,
+};
+
+#[test]
+fn cursor_reads_from_its_position() {
+    let mut cursor = Cursor::new(b"0123456789".to_vec());
+    let mut buf = [0u8; 4];
+    assert_eq!(cursor.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf, b"0123");
+    assert_eq!(cursor.position(), 4);
+
+    cursor.set_position(8);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"89");
+    cursor.set_position(100);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+    assert_eq!(cursor.position(), 100);
+
+    cursor.set_position(3);
+    let mut rest = Vec::new();
+    assert_eq!(cursor.read_to_end(&mut rest).unwrap(), 7);
+    assert_eq!(rest, b"3456789");
+    assert_eq!(cursor.get_ref().len(), 10);
+}
+
+#[test]
+fn cursor_over_a_slice_stops_at_its_end() {
+    let mut storage = [0u8; 6];
+    let mut cursor = Cursor::new(&mut storage[..]);
+    assert_eq!(cursor.write(b"abcd").unwrap(), 4);
+    assert_eq!(cursor.write(b"efgh").unwrap(), 2);
+    assert_eq!(cursor.write(b"ij").unwrap(), 0);
+    assert_eq!(cursor.position(), 6);
+    cursor.set_position(1);
+    cursor.write_all(b"XY").unwrap();
+    assert_eq!(cursor.position(), 3);
+    assert!(cursor.write_all(b"0123").is_err());
+    assert_eq!(&storage, b"aXY012");
+}
+
+#[test]
+fn cursor_over_a_vec_overwrites_and_extends() {
+    let mut cursor = Cursor::new(b"hello".to_vec());
+    cursor.set_position(3);
+    cursor.write_all(b"p me").unwrap();
+    assert_eq!(cursor.get_ref(), b"help me");
+    cursor.set_position(9);
+    cursor.write_all(b"!").unwrap();
+    assert_eq!(cursor.position(), 10);
+    assert_eq!(cursor.into_inner(), b"help me\0\0!");
+}
+
+#[test]
+fn cursor_carries_framed_streams() {
+    let input: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+    let mut writer = LzfWriter::new(Cursor::new(Vec::new()), 4096).unwrap();
+    writer.write_all(&input).unwrap();
+    let mut cursor = writer.finish().unwrap();
+    assert_eq!(cursor.get_ref(), &encode_blocks(&input, 4096).unwrap());
+
+    cursor.set_position(0);
+    let mut reader = LzfReader::new(cursor);
+    let mut decoded = Vec::new();
+    reader.read_to_end(&mut decoded).unwrap();
+    assert_eq!(decoded, input);
+    let cursor = reader.into_inner();
+    assert_eq!(cursor.position(), cursor.get_ref().len() as u64);
+}
+
+#[test]
+fn take_stops_at_its_limit() {
+    let mut take = (&b"0123456789"[..]).take(4);
+    assert_eq!(take.limit(), 4);
+    let mut buf = [0u8; 3];
+    assert_eq!(take.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf, b"012");
+    assert_eq!(take.limit(), 1);
+    assert_eq!(take.read(&mut buf).unwrap(), 1);
+    assert_eq!(take.read(&mut buf).unwrap(), 0);
+    assert_eq!(*take.get_ref(), b"456789");
+
+    take.set_limit(100);
+    let mut rest = Vec::new();
+    assert_eq!(take.read_to_end(&mut rest).unwrap(), 6);
+    assert_eq!(rest, b"456789");
+    assert_eq!(take.limit(), 94);
+
+    let mut empty = (&b"abc"[..]).take(0);
+    assert_eq!(empty.read(&mut buf).unwrap(), 0);
+    assert_eq!(empty.into_inner(), b"abc");
+}
+
+#[test]
+fn lzf_reader_over_take_leaves_trailing_bytes_unread() {
+    let data: Vec<u8> = (0..50_000u32).map(|i| (i % 97) as u8).collect();
+    let framed = encode_blocks(&data, 4096).unwrap();
+    let mut container = framed.clone();
+    container.extend_from_slice(b"trailing garbage");
+
+    let mut source: &[u8] = &container;
+    let mut reader = LzfReader::new((&mut source).take(framed.len() as u64));
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).unwrap();
+    assert_eq!(output, data);
+    assert_eq!(reader.into_inner().limit(), 0);
+    assert_eq!(source, b"trailing garbage");
+}
+
+fn frame_len(framed: &[u8], at: usize) -> usize {
+    let len = usize::from(u16::from_be_bytes([framed[at + 3], framed[at + 4]]));
+    if framed[at + 2] == 0 { 5 + len } else { 7 + len }
+}
+
+#[test]
+fn chain_reads_both_sources_in_order() {
+    let mut chain = (&b"abc"[..]).chain(&b"defg"[..]);
+    let mut buf = [0u8; 5];
+    chain.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"abcde");
+    assert_eq!(chain.read(&mut buf).unwrap(), 2);
+    assert_eq!(&buf[..2], b"fg");
+    assert_eq!(chain.read(&mut buf).unwrap(), 0);
+    assert_eq!(chain.read_exact(&mut buf[..1]), Err(Error::Eof));
+
+    let (first, second) = chain.into_inner();
+    assert!(first.is_empty() && second.is_empty());
+}
+
+#[test]
+fn chain_keeps_reading_the_first_source_after_empty_reads() {
+    let mut chain = (&b"ab"[..]).chain(&b"cd"[..]);
+    assert_eq!(chain.read(&mut []).unwrap(), 0);
+    let mut buf = [0u8; 4];
+    chain.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"abcd");
+}
+
+#[test]
+fn lzf_reader_over_chained_pieces_matches_contiguous_input() {
+    let data: Vec<u8> = (0..30_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let framed = encode_blocks(&data, 4096).unwrap();
+    let second = frame_len(&framed, 0);
+
+    let splits =
+        [[2, second + 4], [10, second + 1], [second - 3, second + 20], [1, framed.len() - 1]];
+    for [a, b] in splits {
+        let pieces = (&framed[..a]).chain(&framed[a..b]).chain(&framed[b..]);
+        let mut output = Vec::new();
+        LzfReader::new(pieces).read_to_end(&mut output).unwrap();
+        assert_eq!(output, data, "split at {a} and {b}");
+    }
+}
+
+fn wrapped(bytes: &[u8], wrap_after: usize) -> VecDeque<u8> {
+    let mut deque = VecDeque::with_capacity(bytes.len());
+    for _ in 0..deque.capacity() - wrap_after {
+        deque.push_back(0xff);
+        deque.pop_front();
+    }
+    deque.extend(bytes);
+    assert_eq!(deque.as_slices().0.len(), wrap_after);
+    deque
+}
+
+#[test]
+fn deque_reads_straddle_the_wrap_around() {
+    let bytes: Vec<u8> = (0..16).collect();
+    let mut deque = wrapped(&bytes, 5);
+    let mut buf = [0u8; 8];
+    deque.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(deque.len(), 8);
+
+    let mut rest = Vec::new();
+    assert_eq!(deque.read_to_end(&mut rest).unwrap(), 8);
+    assert_eq!(rest, (8..16).collect::<Vec<u8>>());
+    assert_eq!(deque.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn deque_write_appends_to_the_back() {
+    let mut deque = wrapped(&[0, 1, 2, 3], 2);
+    deque.write_all(b"xyz").unwrap();
+    deque.flush().unwrap();
+    assert_eq!(deque.iter().copied().collect::<Vec<u8>>(), [0, 1, 2, 3, b'x', b'y', b'z']);
+}
+
+#[test]
+fn deque_carries_a_framed_stream() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| (i % 29) as u8).collect();
+    let mut writer = LzfWriter::new(VecDeque::new(), 1024).unwrap();
+    writer.write_all(&data).unwrap();
+    let framed = writer.finish().unwrap();
+
+    // Re-queue the stream so it wraps around inside the first header.
+    let deque = wrapped(&framed.iter().copied().collect::<Vec<u8>>(), 3);
+
+    let mut output = Vec::new();
+    LzfReader::new(deque).read_to_end(&mut output).unwrap();
+    assert_eq!(output, data);
+}
+
+struct Ring {
+    buf: [u8; 64],
+    start: usize,
+    len: usize,
+}
+
+impl Ring {
+    fn new() -> Self {
+        Self { buf: [0; 64], start: 0, len: 0 }
+    }
+}
+
+impl Read for Ring {
+    fn read(&mut self, out: &mut [u8]) -> lzf_rust::Result<usize> {
+        let n = out.len().min(self.len).min(self.buf.len() - self.start);
+        out[..n].copy_from_slice(&self.buf[self.start..self.start + n]);
+        self.start = (self.start + n) % self.buf.len();
+        self.len -= n;
+        Ok(n)
+    }
+}
+
+impl Write for Ring {
+    fn write(&mut self, data: &[u8]) -> lzf_rust::Result<usize> {
+        let end = (self.start + self.len) % self.buf.len();
+        let room = (self.buf.len() - self.len).min(self.buf.len() - end);
+        let n = data.len().min(room);
+        self.buf[end..end + n].copy_from_slice(&data[..n]);
+        self.len += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_types_serve_as_stream_source_and_sink() {
+    let data = b"ring ring ring ring ring ring ring ring";
+    let mut writer = LzfWriter::new(Ring::new(), 1024).unwrap();
+    writer.write_all(data).unwrap();
+    let ring = writer.finish().unwrap();
+    assert!(ring.len > 0);
+
+    let mut reader = LzfReader::new(ring);
+    let mut output = Vec::new();
+    reader.read_to_end(&mut output).unwrap();
+    assert_eq!(output, data);
+    assert_eq!(reader.into_inner().len, 0);
+}
+
+#[test]
+fn copy_relays_through_a_custom_type() {
+    let data: Vec<u8> = (0..5_000u32).map(|i| (i % 13) as u8).collect();
+    let mut ring = Ring::new();
+    let mut relayed = Vec::new();
+    for chunk in data.chunks(40) {
+        ring.write_all(chunk).unwrap();
+        assert_eq!(copy(&mut ring, &mut relayed).unwrap(), chunk.len() as u64);
+    }
+    assert_eq!(relayed, data);
+}
+
+struct Stuttering<'a> {
+    data: &'a [u8],
+    chunk: usize,
+    interrupt: bool,
+}
+
+impl Read for Stuttering<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> lzf_rust::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(Error::Interrupted);
+        }
+        let n = self.data.len().min(buf.len()).min(self.chunk);
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+/// Takes at most `max` bytes per write, interrupting every other write.
+struct Trickle {
+    out: Vec<u8>,
+    max: usize,
+    interrupt: bool,
+}
+
+impl Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(Error::Interrupted);
+        }
+        let n = buf.len().min(self.max);
+        self.out.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+struct Broken;
+
+impl Read for Broken {
+    fn read(&mut self, _buf: &mut [u8]) -> lzf_rust::Result<usize> {
+        Err(Error::Other)
+    }
+}
+
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn copy_of_empty_input_writes_nothing() {
+    let mut input: &[u8] = &[];
+    let mut output = Vec::new();
+    assert_eq!(copy(&mut input, &mut output).unwrap(), 0);
+    assert!(output.is_empty());
+
+    let mut reader = Stuttering { data: &[], chunk: 1, interrupt: false };
+    let mut buf = [0u8; 1];
+    assert_eq!(copy_with_buffer(&mut reader, &mut output, &mut buf).unwrap(), 0);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn copy_retries_interrupted_reads_and_short_writes() {
+    let data = sample(40_000);
+    let mut reader = Stuttering { data: &data, chunk: 3_000, interrupt: false };
+    let mut writer = Trickle { out: Vec::new(), max: 7, interrupt: false };
+    assert_eq!(copy(&mut reader, &mut writer).unwrap(), data.len() as u64);
+    assert_eq!(writer.out, data);
+
+    let mut reader = Stuttering { data: &data, chunk: usize::MAX, interrupt: false };
+    let mut writer = Trickle { out: Vec::new(), max: 1_000, interrupt: false };
+    let mut buf = [0u8; 5];
+    assert_eq!(copy_with_buffer(&mut reader, &mut writer, &mut buf).unwrap(), data.len() as u64);
+    assert_eq!(writer.out, data);
+}
+
+#[test]
+fn copy_propagates_errors_and_rejects_empty_buffers() {
+    let mut output = Vec::new();
+    assert_eq!(copy(&mut Broken, &mut output), Err(Error::Other));
+
+    let mut input: &[u8] = b"abc";
+    let mut storage = [0u8; 2];
+    let mut short: &mut [u8] = &mut storage;
+    assert_eq!(copy(&mut input, &mut short), Err(Error::WriteZero));
+    assert_eq!(&storage, b"ab");
+
+    let mut input: &[u8] = b"abc";
+    assert_eq!(copy_with_buffer(&mut input, &mut output, &mut []), Err(Error::InvalidParameter));
+    assert_eq!(input, b"abc");
+}
+
+#[test]
+fn copy_connects_stream_reader_and_writer() {
+    let data = sample(100_000);
+    let mut writer = LzfWriter::new(Vec::new(), 4096).unwrap();
+    let mut input: &[u8] = &data;
+    assert_eq!(copy(&mut input, &mut writer).unwrap(), data.len() as u64);
+    let framed = writer.finish().unwrap();
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    let mut output = Vec::new();
+    assert_eq!(copy(&mut reader, &mut output).unwrap(), data.len() as u64);
+    assert_eq!(output, data);
+}
+
+#[test]
+fn sink_counts_a_decoded_fixture() {
+    let records = include_bytes!("data/corpus/records.json").repeat(20);
+    let framed = encode_blocks(&records, 65535).unwrap();
+
+    let mut out = sink();
+    assert_eq!(decode_blocks_to_writer(&framed, &mut out).unwrap(), records.len() as u64);
+    assert_eq!(out.bytes_written(), records.len() as u64);
+
+    let mut out = sink();
+    let mut reader = LzfReader::new(framed.as_slice());
+    assert_eq!(copy(&mut reader, &mut out).unwrap(), records.len() as u64);
+    assert_eq!(out.bytes_written(), records.len() as u64);
+
+    let slices = [lzf_rust::IoSlice::new(b"ab"), lzf_rust::IoSlice::new(b"cde")];
+    assert_eq!(out.write_vectored(&slices).unwrap(), 5);
+    assert_eq!(out.bytes_written(), records.len() as u64 + 5);
+}
+
+#[test]
+fn empty_reader_is_an_empty_stream() {
+    let mut buf = [0u8; 8];
+    assert_eq!(empty().read(&mut buf).unwrap(), 0);
+    assert_eq!(copy(&mut empty(), &mut sink()).unwrap(), 0);
+
+    let mut reader = LzfReader::new(empty());
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+}
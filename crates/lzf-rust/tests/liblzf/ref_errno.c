@@ -0,0 +1,6 @@
+// SPDX-License-Identifier: ISC
+// Exposes the C library's errno to the differential test, which cannot read
+// it portably from Rust.
+#include <errno.h>
+
+int lzf_ref_errno(void) { return errno; }
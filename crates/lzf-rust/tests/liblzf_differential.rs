@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: ISC
+//! Differential tests of `decompress` against liblzf's `lzf_decompress`.
+//!
+//! The C decoder is vendored in `tests/liblzf` and built by the testing-only
+//! `liblzf-differential` feature:
+//!
+//! ```text
+//! cargo test -p lzf-rust --features liblzf-differential --test liblzf_differential
+//! ```
+//!
+//! The vendored copy keeps liblzf's default `CHECK_INPUT` setting; without it
+//! the C decoder reads past the end of malformed input.
+#![cfg(feature = "liblzf-differential")]
+
+use std::ffi::{c_int, c_uint, c_void};
+
+use lzf_rust::{Error, compress, decompress, max_compressed_size};
+
+unsafe extern "C" {
+    fn lzf_decompress(
+        in_data: *const c_void,
+        in_len: c_uint,
+        out_data: *mut c_void,
+        out_len: c_uint,
+    ) -> c_uint;
+    fn lzf_ref_errno() -> c_int;
+}
+
+// liblzf reports failures through `errno`; both values are the same on Linux,
+// macOS and Windows.
+const E2BIG: c_int = 7;
+const EINVAL: c_int = 22;
+
+fn c_decompress(input: &[u8], out_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = vec![0u8; out_len];
+    // SAFETY: both pointers are valid for the lengths passed alongside them.
+    let n = unsafe {
+        lzf_decompress(
+            input.as_ptr().cast(),
+            input.len() as c_uint,
+            out.as_mut_ptr().cast(),
+            out.len() as c_uint,
+        )
+    };
+    if n == 0 {
+        // SAFETY: reads the calling thread's `errno`.
+        return match unsafe { lzf_ref_errno() } {
+            E2BIG => Err(Error::OutputTooSmall),
+            EINVAL => Err(Error::InvalidData),
+            other => panic!("lzf_decompress failed with unexpected errno {other:?}"),
+        };
+    }
+    out.truncate(n as usize);
+    Ok(out)
+}
+
+fn rust_decompress(input: &[u8], out_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = vec![0u8; out_len];
+    let n = decompress(input, &mut out)?;
+    out.truncate(n);
+    Ok(out)
+}
+
+/// Known, intentional divergences from liblzf.
+#[derive(Debug)]
+enum Divergence {
+    /// A literal run that is both truncated and longer than the remaining
+    /// output: liblzf checks the output first (`E2BIG`), we check the input
+    /// first so corruption is never reported as a sizing problem.
+    TruncatedLiteralReportedAsInvalid,
+}
+
+fn allowed(input: &[u8], out_len: usize) -> Option<Divergence> {
+    // Replay the token stream to find the failing token.
+    let (mut ip, mut op) = (0usize, 0usize);
+    while ip < input.len() {
+        let ctrl = input[ip];
+        ip += 1;
+        if ctrl < 32 {
+            let len = usize::from(ctrl) + 1;
+            if ip + len > input.len() {
+                return (op + len > out_len)
+                    .then_some(Divergence::TruncatedLiteralReportedAsInvalid);
+            }
+            if op + len > out_len {
+                return None;
+            }
+            ip += len;
+            op += len;
+        } else {
+            let mut len = usize::from(ctrl >> 5);
+            if len == 7 {
+                len += usize::from(*input.get(ip)?);
+                ip += 1;
+            }
+            let off = usize::from(ctrl & 0x1f) << 8 | usize::from(*input.get(ip)?);
+            ip += 1;
+            if op + len + 2 > out_len || off >= op {
+                return None;
+            }
+            op += len + 2;
+        }
+    }
+    None
+}
+
+fn check(input: &[u8], out_len: usize, divergences: &mut Vec<Divergence>) {
+    // liblzf reads the first control byte unconditionally.
+    if input.is_empty() {
+        return;
+    }
+    let ours = rust_decompress(input, out_len);
+    let theirs = c_decompress(input, out_len);
+    if ours == theirs {
+        return;
+    }
+    match allowed(input, out_len) {
+        Some(divergence) => divergences.push(divergence),
+        None => panic!(
+            "divergence for input {input:02x?} (output {out_len} bytes):\n  \
+             lzf-rust: {ours:?}\n  liblzf:   {theirs:?}"
+        ),
+    }
+}
+
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn random_buffers_match_liblzf() {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut divergences = Vec::new();
+    for _ in 0..20_000 {
+        let len = (next(&mut state) % 64) as usize;
+        let input: Vec<u8> = (0..len).map(|_| next(&mut state) as u8).collect();
+        for out_len in [0usize, 16, 256, 4096] {
+            check(&input, out_len, &mut divergences);
+        }
+    }
+    println!("allowed divergences: {}", divergences.len());
+}
+
+#[test]
+fn mutated_streams_match_liblzf() {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut divergences = Vec::new();
+    let plain: Vec<u8> =
+        (0..8192u32).map(|i| b"lzf differential "[(i % 17) as usize] ^ (i / 700) as u8).collect();
+
+    for _ in 0..2_000 {
+        let len = (next(&mut state) as usize) % plain.len();
+        let mut compressed = vec![0u8; max_compressed_size(len)];
+        let n = compress(&plain[..len], &mut compressed).expect("compress");
+        compressed.truncate(n);
+
+        let mut mutated = compressed.clone();
+        match next(&mut state) % 3 {
+            0 if !mutated.is_empty() => {
+                let at = next(&mut state) as usize % mutated.len();
+                mutated[at] ^= 1 << (next(&mut state) % 8);
+            }
+            1 => mutated.truncate(next(&mut state) as usize % (mutated.len() + 1)),
+            _ if !mutated.is_empty() => {
+                let at = next(&mut state) as usize % mutated.len();
+                mutated[at] = next(&mut state) as u8;
+            }
+            _ => {}
+        }
+
+        for out_len in [len, len.saturating_sub(1), len + 300] {
+            check(&compressed, out_len, &mut divergences);
+            check(&mutated, out_len, &mut divergences);
+        }
+    }
+    println!("allowed divergences: {}", divergences.len());
+}
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: ISC
+#![cfg(feature = "rayon")]
+
+use std::fs;
+use std::path::Path;
+
+use lzf_rust::{
+    CompressionMode, Error, FrameIter, decode_blocks, decode_blocks_parallel,
+    encode_blocks_checked, encode_blocks_parallel, encode_blocks_with_mode,
+};
+
+fn pattern_data(size: usize) -> Vec<u8> {
+    let mut x = 0x7a11_e1edu32;
+    (0..size)
+        .map(|i| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            if x & 0x7000 == 0 { (x >> 24) as u8 } else { b"parallel"[i % 8] }
+        })
+        .collect()
+}
+
+#[test]
+fn encode_blocks_parallel_matches_sequential() {
+    let input = pattern_data(1 << 20);
+    for mode in [CompressionMode::Normal, CompressionMode::Best] {
+        for block_size in [1usize, 7, 4096, 65535] {
+            let len = if block_size < 64 { 10_000 } else { input.len() };
+            let expected = encode_blocks_with_mode(&input[..len], block_size, mode).expect("seq");
+            let got = encode_blocks_parallel(&input[..len], block_size, mode).expect("par");
+            assert_eq!(got, expected, "block_size {block_size}, {mode:?}");
+        }
+    }
+    assert_eq!(encode_blocks_parallel(&[], 4096, CompressionMode::Normal), Ok(Vec::new()));
+}
+
+#[test]
+fn encode_blocks_parallel_matches_sequential_on_any_pool_size() {
+    let input = pattern_data(300_000);
+    let expected = encode_blocks_with_mode(&input, 4096, CompressionMode::Normal).expect("seq");
+    for threads in [1, 3, 8] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("pool");
+        let got = pool.install(|| encode_blocks_parallel(&input, 4096, CompressionMode::Normal));
+        assert_eq!(got.expect("par"), expected, "{threads} threads");
+    }
+}
+
+#[test]
+fn encode_blocks_parallel_rejects_invalid_block_sizes() {
+    for block_size in [0usize, 65536] {
+        assert_eq!(
+            encode_blocks_parallel(b"data", block_size, CompressionMode::Normal),
+            Err(Error::InvalidParameter)
+        );
+    }
+}
+
+#[test]
+fn decode_blocks_parallel_matches_sequential() {
+    let input = pattern_data(1 << 20);
+    for block_size in [1usize, 100, 4096, 65535] {
+        let len = if block_size < 64 { 10_000 } else { input.len() };
+        let framed = encode_blocks_with_mode(&input[..len], block_size, CompressionMode::Normal)
+            .expect("encode");
+        assert_eq!(decode_blocks_parallel(&framed).expect("decode"), &input[..len]);
+    }
+    let checked = encode_blocks_checked(&input, 4096, CompressionMode::Normal).expect("encode");
+    assert_eq!(decode_blocks_parallel(&checked).expect("decode"), input);
+    assert_eq!(decode_blocks_parallel(&[]), Ok(Vec::new()));
+    assert_eq!(decode_blocks_parallel(&[0, b'x']), Ok(Vec::new()));
+}
+
+#[test]
+fn decode_blocks_parallel_matches_sequential_on_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/regression/framed");
+    for entry in fs::read_dir(&dir).expect("read_dir") {
+        let path = entry.expect("entry").path();
+        if path.extension().is_some_and(|ext| ext == "in") {
+            let input = fs::read(&path).expect("read fixture");
+            assert_eq!(decode_blocks_parallel(&input), decode_blocks(&input), "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn decode_blocks_parallel_reports_the_earliest_failing_block() {
+    let input = pattern_data(16 * 4096);
+    let framed = encode_blocks_checked(&input, 4096, CompressionMode::Normal).expect("encode");
+    let frames: Vec<_> = FrameIter::new(&framed).collect::<Result<_, _>>().expect("frames");
+    assert!(frames.iter().all(|frame| frame.is_compressed()));
+
+    // Block 3 fails its checksum, block 9 has a back-reference before the
+    // start of its output, and block 12 has a malformed header.
+    let mut corrupt = framed.clone();
+    corrupt[frames[3].end() - 1] ^= 0x10;
+    let payload = frames[9].payload_range.start;
+    corrupt[payload..payload + 3].copy_from_slice(&[0x00, b'x', 0x20]);
+    corrupt[payload + 3] = 0x05;
+    corrupt[frames[12].offset] = b'X';
+
+    for threads in [1, 4, 16] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("pool");
+        let mut data = corrupt.clone();
+        assert_eq!(pool.install(|| decode_blocks_parallel(&data)), decode_blocks(&data));
+        assert_eq!(decode_blocks(&data), Err(Error::ChecksumMismatch));
+
+        data[frames[3].end() - 1] ^= 0x10;
+        assert_eq!(pool.install(|| decode_blocks_parallel(&data)), Err(Error::InvalidData));
+        assert_eq!(decode_blocks(&data), Err(Error::InvalidData));
+
+        data[payload..payload + 4].copy_from_slice(&framed[payload..payload + 4]);
+        assert_eq!(pool.install(|| decode_blocks_parallel(&data)), Err(Error::InvalidHeader));
+        assert_eq!(decode_blocks(&data), Err(Error::InvalidHeader));
+    }
+}
+
+#[test]
+fn decode_blocks_parallel_fails_blocks_overstating_their_length() {
+    // Each block claims 65535 bytes but decodes a single literal; together
+    // they claim over 6 GiB from less than a megabyte of input.
+    let block = [b'Z', b'V', 1, 0, 2, 0xff, 0xff, 0x00, b'a'];
+    let framed = block.repeat(100_000);
+    for threads in [1, 4] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().expect("pool");
+        assert_eq!(pool.install(|| decode_blocks_parallel(&framed)), decode_blocks(&framed));
+        assert_eq!(decode_blocks(&framed), Err(Error::InvalidData));
+    }
+}
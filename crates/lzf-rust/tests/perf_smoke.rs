@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: ISC
+//! Throughput smoke test.
+//!
+//! This is not a benchmark: it only catches order-of-magnitude regressions
+//! (an accidental quadratic path, a debug assertion in a hot loop) by checking
+//! that throughput on the vendored corpus stays within `SLACK_FACTOR` of the
+//! loose floors recorded in `tests/data/perf_baseline.toml` for the current
+//! target architecture.
+//!
+//! Run it in release mode; debug builds skip the checks:
+//!
+//! ```text
+//! cargo test -p lzf-rust --release --test perf_smoke -- --ignored --nocapture
+//! ```
+//!
+//! To print a fresh baseline section for the current machine, set
+//! `LZF_PERF_RECORD=1` and paste the output into the baseline file.
+#![cfg(feature = "encoder")]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use lzf_rust::{
+    CompressionMode, compress_with_mode, decode_blocks, decompress, encode_blocks,
+    max_compressed_size,
+};
+
+/// Measured throughput may fall this many times below the recorded floor.
+const SLACK_FACTOR: f64 = 4.0;
+const RECORD_ENV: &str = "LZF_PERF_RECORD";
+const CORPUS: [&str; 4] = ["text.txt", "records.json", "sparse.bin", "random.bin"];
+const MIN_SAMPLE_TIME: Duration = Duration::from_millis(50);
+const ROUNDS: usize = 5;
+
+fn data_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data")
+}
+
+/// Parses the `[section]` / `"key" = number` subset of TOML used by the
+/// baseline file.
+fn load_baseline(path: &Path) -> BTreeMap<String, BTreeMap<String, f64>> {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| panic!("read {}: {e}", path.display()));
+    let mut sections = BTreeMap::<String, BTreeMap<String, f64>>::new();
+    let mut current = None;
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim().to_owned());
+            continue;
+        }
+        let parsed = line.split_once('=').and_then(|(key, value)| {
+            let key = key.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((key.to_owned(), value.trim().parse::<f64>().ok()?))
+        });
+        let (Some(section), Some((key, value))) = (&current, parsed) else {
+            panic!("{}:{}: expected `\"key\" = number` in a section", path.display(), lineno + 1);
+        };
+        sections.entry(section.clone()).or_default().insert(key, value);
+    }
+    sections
+}
+
+/// Returns the best observed throughput of `op` over `bytes` bytes, in MB/s.
+fn measure(bytes: usize, mut op: impl FnMut()) -> f64 {
+    let mut best = 0.0f64;
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        let mut iterations = 0u64;
+        while start.elapsed() < MIN_SAMPLE_TIME {
+            op();
+            iterations += 1;
+        }
+        let secs = start.elapsed().as_secs_f64();
+        best = best.max(bytes as f64 * iterations as f64 / secs / 1e6);
+    }
+    best
+}
+
+fn measure_corpus_file(name: &str, input: &[u8]) -> Vec<(String, f64)> {
+    let mut results = Vec::new();
+    let mut compressed = vec![0u8; max_compressed_size(input.len())];
+
+    for (mode, mode_name) in [(CompressionMode::Normal, "normal"), (CompressionMode::Best, "best")]
+    {
+        let mbps = measure(input.len(), || {
+            black_box(compress_with_mode(input, &mut compressed, mode).expect("compress"));
+        });
+        results.push((format!("compress_{mode_name}/{name}"), mbps));
+    }
+
+    let clen =
+        compress_with_mode(input, &mut compressed, CompressionMode::Normal).expect("compress");
+    compressed.truncate(clen);
+    let mut out = vec![0u8; input.len()];
+    let mbps = measure(input.len(), || {
+        black_box(decompress(&compressed, &mut out).expect("decompress"));
+    });
+    results.push((format!("decompress/{name}"), mbps));
+
+    let framed = encode_blocks(input, 4096).expect("encode_blocks");
+    let mbps = measure(input.len(), || {
+        black_box(encode_blocks(input, 4096).expect("encode_blocks"));
+    });
+    results.push((format!("encode_blocks/{name}"), mbps));
+    let mbps = measure(input.len(), || {
+        black_box(decode_blocks(&framed).expect("decode_blocks"));
+    });
+    results.push((format!("decode_blocks/{name}"), mbps));
+
+    results
+}
+
+#[test]
+#[ignore = "throughput check; run with --release -- --ignored"]
+fn throughput_within_baseline() {
+    if cfg!(debug_assertions) {
+        println!("perf_smoke: skipped in debug builds; rerun with --release");
+        return;
+    }
+
+    let baseline_path = data_dir().join("perf_baseline.toml");
+    let baselines = load_baseline(&baseline_path);
+    let arch = std::env::consts::ARCH;
+    let floors = baselines.get(arch).or_else(|| baselines.get("default"));
+    let record = std::env::var_os(RECORD_ENV).is_some();
+
+    let mut measured = Vec::new();
+    for name in CORPUS {
+        let input = fs::read(data_dir().join("corpus").join(name))
+            .unwrap_or_else(|e| panic!("read corpus {name}: {e}"));
+        measured.extend(measure_corpus_file(name, &input));
+    }
+
+    let mut failures = Vec::new();
+    println!("{:<32} {:>10} {:>10}", "operation/corpus", "MB/s", "floor");
+    for (key, mbps) in &measured {
+        let floor = floors.and_then(|f| f.get(key)).copied();
+        match floor {
+            Some(floor) => {
+                println!("{key:<32} {mbps:>10.1} {floor:>10.1}");
+                if *mbps * SLACK_FACTOR < floor {
+                    failures.push(format!("{key}: {mbps:.1} MB/s, floor {floor:.1} MB/s"));
+                }
+            }
+            None => println!("{key:<32} {mbps:>10.1} {:>10}", "-"),
+        }
+    }
+
+    if record || !failures.is_empty() {
+        println!("\n# Suggested baseline section (floors at half the measured throughput):");
+        println!("[{arch}]");
+        for (key, mbps) in &measured {
+            println!("\"{key}\" = {:.0}", (mbps / 2.0).floor());
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "throughput fell more than {SLACK_FACTOR}x below the recorded floor:\n  {}\n\
+         If the slowdown is expected, rerun with `{RECORD_ENV}=1 cargo test -p lzf-rust --release \
+         --test perf_smoke -- --ignored --nocapture` and update {}.",
+        failures.join("\n  "),
+        baseline_path.display()
+    );
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: ISC
+//! Compression-ratio regression harness.
+//!
+//! Every file in `tests/data/corpus` is compressed with each `CompressionMode`
+//! and the resulting size is compared with `tests/data/corpus/baselines.txt`.
+//! Sizes more than `TOLERANCE_PERCENT` above the recorded baseline fail the
+//! test; run with `--nocapture` to see the full table.
+//!
+//! To intentionally re-bless the baselines after an encoder change:
+//!
+//! ```text
+//! LZF_BLESS_RATIO=1 cargo test -p lzf-rust --test ratio
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use lzf_rust::{
+    CompressionMode, EncodeOptions, compress_with_mode, encode_blocks_with_options,
+    max_compressed_size,
+};
+
+const TOLERANCE_PERCENT: f64 = 0.5;
+const BLESS_ENV: &str = "LZF_BLESS_RATIO";
+const MODES: [(CompressionMode, &str); 2] =
+    [(CompressionMode::Normal, "normal"), (CompressionMode::Best, "best")];
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("corpus")
+}
+
+fn corpus_files(dir: &Path) -> Vec<String> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir).unwrap_or_else(|e| panic!("read_dir {}: {e}", dir.display())) {
+        let entry = entry.unwrap_or_else(|e| panic!("read_dir entry {}: {e}", dir.display()));
+        let name = entry.file_name().into_string().expect("non-UTF-8 corpus file name");
+        if name != "baselines.txt" {
+            names.push(name);
+        }
+    }
+    names.sort();
+    names
+}
+
+fn load_baselines(path: &Path) -> BTreeMap<(String, String), usize> {
+    let text = fs::read_to_string(path).unwrap_or_default();
+    let mut baselines = BTreeMap::new();
+    for (lineno, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [name, mode, size] = fields[..] else {
+            panic!("{}:{}: expected `<file> <mode> <size>`", path.display(), lineno + 1);
+        };
+        let size = size
+            .parse::<usize>()
+            .unwrap_or_else(|_| panic!("{}:{}: invalid size", path.display(), lineno + 1));
+        baselines.insert((name.to_owned(), mode.to_owned()), size);
+    }
+    baselines
+}
+
+fn write_baselines(path: &Path, sizes: &BTreeMap<(String, String), usize>) {
+    let mut text = String::from("# Compressed sizes recorded by tests/ratio.rs.\n");
+    text.push_str("# Re-bless with: LZF_BLESS_RATIO=1 cargo test -p lzf-rust --test ratio\n");
+    for ((name, mode), size) in sizes {
+        text.push_str(&format!("{name} {mode} {size}\n"));
+    }
+    fs::write(path, text).unwrap_or_else(|e| panic!("write {}: {e}", path.display()));
+}
+
+#[test]
+fn compression_ratio_matches_baselines() {
+    let dir = corpus_dir();
+    let baseline_path = dir.join("baselines.txt");
+    let baselines = load_baselines(&baseline_path);
+    let bless = std::env::var_os(BLESS_ENV).is_some();
+
+    let mut current = BTreeMap::new();
+    let mut failures = Vec::new();
+
+    println!(
+        "{:<16} {:<7} {:>8} {:>10} {:>10} {:>8}",
+        "file", "mode", "input", "baseline", "current", "delta%"
+    );
+    for name in corpus_files(&dir) {
+        let input = fs::read(dir.join(&name)).unwrap_or_else(|e| panic!("read {name}: {e}"));
+        for (mode, mode_name) in MODES {
+            let mut out = vec![0u8; max_compressed_size(input.len())];
+            let size = compress_with_mode(&input, &mut out, mode)
+                .unwrap_or_else(|e| panic!("compress {name} ({mode_name}): {e}"));
+
+            let key = (name.clone(), mode_name.to_owned());
+            match baselines.get(&key) {
+                Some(&baseline) => {
+                    let delta = (size as f64 - baseline as f64) * 100.0 / baseline.max(1) as f64;
+                    println!(
+                        "{name:<16} {mode_name:<7} {:>8} {baseline:>10} {size:>10} {delta:>+8.2}",
+                        input.len()
+                    );
+                    if delta > TOLERANCE_PERCENT {
+                        failures.push(format!("{name} ({mode_name}): {baseline} -> {size}"));
+                    }
+                }
+                None => {
+                    println!("{name:<16} {mode_name:<7} {:>8} {:>10} {size:>10}", input.len(), "-");
+                    failures.push(format!("{name} ({mode_name}): no baseline recorded"));
+                }
+            }
+            current.insert(key, size);
+        }
+    }
+
+    if bless {
+        write_baselines(&baseline_path, &current);
+        println!("baselines re-blessed in {}", baseline_path.display());
+        return;
+    }
+
+    assert!(
+        failures.is_empty(),
+        "compression ratio regressed beyond {TOLERANCE_PERCENT}%:\n  {}\n\
+         If this change is intentional, re-bless with `{BLESS_ENV}=1 cargo test -p lzf-rust --test ratio`.",
+        failures.join("\n  ")
+    );
+}
+
+#[test]
+fn skip_incompressible_keeps_corpus_ratio() {
+    let dir = corpus_dir();
+    let mut failures = Vec::new();
+    for name in corpus_files(&dir) {
+        let input = fs::read(dir.join(&name)).unwrap_or_else(|e| panic!("read {name}: {e}"));
+        for (mode, mode_name) in MODES {
+            let options = EncodeOptions::new().mode(mode);
+            let full = encode_blocks_with_options(&input, 4096, options).expect("encode").len();
+            let probed =
+                encode_blocks_with_options(&input, 4096, options.skip_incompressible(true))
+                    .expect("encode")
+                    .len();
+            let delta = (probed as f64 - full as f64) * 100.0 / full as f64;
+            println!("{name:<16} {mode_name:<7} {full:>10} {probed:>10} {delta:>+8.2}");
+            if delta > TOLERANCE_PERCENT {
+                failures.push(format!("{name} ({mode_name}): {full} -> {probed}"));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "skipping incompressible blocks cost more than {TOLERANCE_PERCENT}%:\n  {}",
+        failures.join("\n  ")
+    );
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: ISC
+use lzf_rust::{
+    Error, MAX_MATCH_LEN, MAX_OFFSET, RawDecoder, compress, decompress, decompress_partial,
+    decompress_partial_with_history, decompress_windowed, max_compressed_size,
+};
+
+fn mixed_data(size: usize) -> Vec<u8> {
+    let mut x = 0x1234_5678u32;
+    let mut out = Vec::with_capacity(size);
+    while out.len() < size {
+        x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+        let run = 1 + (x >> 26) as usize;
+        let byte = if x & 0x100 == 0 { b'a' + (x >> 9) as u8 % 4 } else { (x >> 16) as u8 };
+        out.extend(std::iter::repeat_n(byte, run));
+    }
+    out.truncate(size);
+    out
+}
+
+fn compress_vec(input: &[u8]) -> Vec<u8> {
+    let mut compressed = vec![0u8; max_compressed_size(input.len())];
+    let n = compress(input, &mut compressed).expect("compress");
+    compressed.truncate(n);
+    compressed
+}
+
+fn decode_in_chunks(stream: &[u8], chunk: usize) -> Result<Vec<u8>, Error> {
+    let mut decoder = RawDecoder::new();
+    let mut out = Vec::new();
+    for piece in stream.chunks(chunk.max(1)) {
+        assert_eq!(decoder.push(piece, &mut out)?, piece.len());
+    }
+    decoder.finish()?;
+    assert_eq!(decoder.total_out(), out.len());
+    Ok(out)
+}
+
+#[test]
+fn chunked_pushes_match_one_shot() {
+    for size in [0usize, 1, 100, 9000, 70_000] {
+        let input = mixed_data(size);
+        let compressed = compress_vec(&input);
+        for chunk in [1usize, 3, compressed.len()] {
+            let out = decode_in_chunks(&compressed, chunk).expect("decode");
+            assert_eq!(out, input, "size {size}, chunk {chunk}");
+        }
+    }
+}
+
+#[test]
+fn chunked_pushes_match_one_shot_errors() {
+    let cases: [&[u8]; 4] = [
+        // Back-reference before the start of the output.
+        &[0x00, b'a', 0x20, 0x01],
+        // Literal run cut short.
+        &[0x03, b'a', b'b'],
+        // Long back-reference missing its length byte.
+        &[0x00, b'a', 0xe0],
+        // Back-reference missing its offset byte.
+        &[0x00, b'a', 0x20],
+    ];
+
+    for stream in cases {
+        let mut out = vec![0u8; 1024];
+        let expected = decompress(stream, &mut out).expect_err("one-shot error");
+        for chunk in [1usize, 3, stream.len()] {
+            assert_eq!(decode_in_chunks(stream, chunk), Err(expected), "{stream:02x?} / {chunk}");
+        }
+    }
+}
+
+struct ByteAtATime(Vec<u8>);
+
+impl lzf_rust::Write for ByteAtATime {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        match buf.first() {
+            Some(&b) => {
+                self.0.push(b);
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+struct FailingWriter {
+    budget: usize,
+}
+
+impl lzf_rust::Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        if self.budget == 0 {
+            return Err(Error::Other);
+        }
+        let n = buf.len().min(self.budget);
+        self.budget -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn decompress_to_writer_matches_decompress() {
+    for size in [0usize, 1, 8191, 8193, 300_000] {
+        let input = mixed_data(size);
+        let compressed = compress_vec(&input);
+
+        let mut out = Vec::new();
+        let written = lzf_rust::decompress_to_writer(&compressed, &mut out).expect("decode");
+        assert_eq!(written, size as u64);
+        assert_eq!(out, input, "size {size}");
+
+        let mut slow = ByteAtATime(Vec::new());
+        lzf_rust::decompress_to_writer(&compressed, &mut slow).expect("decode");
+        assert_eq!(slow.0, input, "size {size}, byte-at-a-time");
+    }
+}
+
+#[test]
+fn decompress_to_writer_surfaces_errors() {
+    let input = mixed_data(200_000);
+    let compressed = compress_vec(&input);
+    let mut sink = FailingWriter { budget: 100_000 };
+    assert_eq!(lzf_rust::decompress_to_writer(&compressed, &mut sink), Err(Error::Other));
+
+    let mut out = Vec::new();
+    let err = lzf_rust::decompress_to_writer(&[0x00, b'a', 0x20, 0x01], &mut out);
+    assert_eq!(err, Err(Error::InvalidData));
+}
+
+#[test]
+fn decompress_partial_resumes_with_history() {
+    for size in [0usize, 1, 100, 9000, 70_000] {
+        let input = mixed_data(size);
+        let compressed = compress_vec(&input);
+        for buf_len in [MAX_MATCH_LEN, 1000, 20_000] {
+            let mut out = Vec::new();
+            let mut buf = vec![0u8; buf_len];
+            let mut rest = &compressed[..];
+            while !rest.is_empty() {
+                let history = &out[out.len().saturating_sub(MAX_OFFSET)..];
+                let (consumed, written) =
+                    decompress_partial_with_history(rest, history, &mut buf).unwrap();
+                assert!(consumed > 0, "no progress with a {buf_len}-byte buffer");
+                out.extend_from_slice(&buf[..written]);
+                rest = &rest[consumed..];
+            }
+            assert_eq!(out, input, "size {size}, buffer {buf_len}");
+        }
+    }
+}
+
+#[test]
+fn decompress_partial_stops_before_tokens_that_do_not_fit() {
+    // Literal "abc" followed by a back-reference of length 3.
+    let stream = [0x02, b'a', b'b', b'c', 0x20, 0x02];
+    let mut out = [0u8; 5];
+    assert_eq!(decompress_partial(&stream, &mut out), Ok((4, 3)));
+    assert_eq!(&out[..3], b"abc");
+    assert_eq!(decompress_partial(&stream, &mut out[..2]), Ok((0, 0)));
+
+    let mut full = [0u8; 6];
+    assert_eq!(decompress_partial(&stream, &mut full), Ok((6, 6)));
+    assert_eq!(&full, b"abcabc");
+
+    assert_eq!(decompress_partial(&[0x02, b'a'], &mut full), Err(Error::InvalidData));
+    assert_eq!(decompress_partial(&[0x00, b'a', 0x20, 0x05], &mut full), Err(Error::InvalidData));
+    let mut out = [0u8; 1];
+    assert_eq!(decompress_partial(&[0x00, b'a', 0x20, 0x05], &mut out), Ok((2, 1)));
+}
+
+#[test]
+fn decompress_windowed_matches_decompress() {
+    for size in [0usize, 1, 100, MAX_OFFSET, MAX_OFFSET + 1, 300_000] {
+        let input = mixed_data(size);
+        let compressed = compress_vec(&input);
+        let mut expected = vec![0u8; input.len()];
+        assert_eq!(decompress(&compressed, &mut expected), Ok(input.len()));
+
+        let mut window = [0u8; MAX_OFFSET];
+        let mut out = Vec::new();
+        let written = decompress_windowed(&compressed, &mut window, &mut out).unwrap();
+        assert_eq!(written, input.len() as u64);
+        assert_eq!(out, expected, "size {size}");
+    }
+}
+
+#[test]
+fn decompress_windowed_resolves_maximum_distance() {
+    // 8 KiB of distinct-ish bytes, then a back-reference reaching exactly
+    // `MAX_OFFSET` bytes back, repeated across several ring wraps.
+    let mut stream = Vec::new();
+    let mut expected = Vec::new();
+    for chunk in mixed_data(MAX_OFFSET).chunks(32) {
+        stream.push(chunk.len() as u8 - 1);
+        stream.extend_from_slice(chunk);
+        expected.extend_from_slice(chunk);
+    }
+    for _ in 0..200 {
+        stream.extend_from_slice(&[0xff, 0xff, 0xff]);
+        for _ in 0..264 {
+            expected.push(expected[expected.len() - MAX_OFFSET]);
+        }
+    }
+
+    let mut window = [0u8; MAX_OFFSET];
+    let mut out = Vec::new();
+    decompress_windowed(&stream, &mut window, &mut out).unwrap();
+    assert_eq!(out, expected);
+    let mut one_shot = vec![0u8; expected.len()];
+    assert_eq!(decompress(&stream, &mut one_shot), Ok(expected.len()));
+}
+
+#[test]
+fn decompress_windowed_surfaces_errors() {
+    let mut window = [0u8; MAX_OFFSET];
+    let mut out = Vec::new();
+    assert_eq!(
+        decompress_windowed(&[0x00, b'a', 0x20, 0x05], &mut window, &mut out),
+        Err(Error::InvalidData)
+    );
+    assert_eq!(decompress_windowed(&[0x02, b'a'], &mut window, &mut out), Err(Error::InvalidData));
+}
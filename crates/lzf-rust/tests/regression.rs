@@ -3,7 +3,10 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use lzf_rust::{Error, decode_blocks, decompress};
+use lzf_rust::{
+    DecodeErrorDetail, EofMarker, Error, LzfReader, Read, decode_blocks, decode_blocks_detailed,
+    decode_blocks_strict, decompress, decompress_detailed, validate,
+};
 
 fn regression_dir(kind: &str) -> PathBuf {
     Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data").join("regression").join(kind)
@@ -19,6 +22,8 @@ fn parse_expected_error(text: &str) -> Error {
         "InvalidData" => Error::InvalidData,
         "InvalidHeader" => Error::InvalidHeader,
         "InvalidParameter" => Error::InvalidParameter,
+        "LimitExceeded" => Error::LimitExceeded,
+        "ChecksumMismatch" => Error::ChecksumMismatch,
         "Other" => Error::Other,
         _ if trimmed.starts_with("UnknownBlockType:") => {
             let suffix = &trimmed["UnknownBlockType:".len()..];
@@ -78,6 +83,7 @@ fn regression_raw_fixtures() {
                 decompress(&input, &mut output).unwrap_or_else(|e| panic!("case {stem}: {e}"));
             assert_eq!(written, expected.len(), "case {stem}: output len");
             assert_eq!(output, expected, "case {stem}: output mismatch");
+            assert_eq!(validate(&input), Ok(expected.len()), "case {stem}: validate");
         } else {
             let expected_err = parse_expected_error(
                 &fs::read_to_string(&err_path)
@@ -87,6 +93,7 @@ fn regression_raw_fixtures() {
             let err =
                 decompress(&input, &mut output).expect_err(&format!("case {stem}: expected error"));
             assert_eq!(err, expected_err, "case {stem}: error mismatch");
+            assert_eq!(validate(&input), Err(expected_err), "case {stem}: validate");
         }
     }
 }
@@ -108,11 +115,12 @@ fn regression_framed_fixtures() {
         let has_err = err_path.exists();
         assert!(has_out ^ has_err, "case {stem}: expected exactly one of .out/.err");
 
-        if has_out {
+        let expected = if has_out {
             let expected =
                 fs::read(&out_path).unwrap_or_else(|e| panic!("read {}: {e}", out_path.display()));
             let got = decode_blocks(&input).unwrap_or_else(|e| panic!("case {stem}: {e}"));
             assert_eq!(got, expected, "case {stem}: output mismatch");
+            Ok(expected)
         } else {
             let expected_err = parse_expected_error(
                 &fs::read_to_string(&err_path)
@@ -120,6 +128,80 @@ fn regression_framed_fixtures() {
             );
             let err = decode_blocks(&input).expect_err(&format!("case {stem}: expected error"));
             assert_eq!(err, expected_err, "case {stem}: error mismatch");
+            let detail = decode_blocks_detailed(&input).expect_err("detailed");
+            assert_eq!(detail.kind, expected_err, "case {stem}: detailed kind mismatch");
+
+            // An optional `.detail` file holds the failing block position as
+            // `block=<index> offset=<header offset>`, which `LzfReader`
+            // reports too. Footer errors are at the block count and the
+            // footer's offset.
+            let detail_path = dir.join(format!("{stem}.detail"));
+            if let Ok(text) = fs::read_to_string(&detail_path) {
+                let expected = text.trim();
+                let got = format!("block={} offset={}", detail.block_index, detail.input_offset);
+                assert_eq!(got, expected, "case {stem}: detailed position mismatch");
+
+                let mut reader = LzfReader::new(input.as_slice());
+                reader.enable_footer_check();
+                let mut buf = [0u8; 64];
+                let read_err = loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) => panic!("case {stem}: reader succeeded"),
+                        Ok(_) => {}
+                        Err(err) => break err,
+                    }
+                };
+                let last = reader.last_error().expect("last_error");
+                assert_eq!(last.kind, read_err, "case {stem}: reader kind");
+                let got = format!("block={} offset={}", last.block_index, last.input_offset);
+                assert_eq!(got, expected, "case {stem}: reader position mismatch");
+            }
+            Err(expected_err)
+        };
+
+        // An optional `.strict` file holds the `decode_blocks_strict` result:
+        // `Ok` for the `.out` contents, or an error name.
+        let strict_path = dir.join(format!("{stem}.strict"));
+        if let Ok(text) = fs::read_to_string(&strict_path) {
+            let strict_expected = match text.trim() {
+                "Ok" => {
+                    Ok(expected.unwrap_or_else(|_| panic!("case {stem}: strict Ok needs .out")))
+                }
+                other => Err(parse_expected_error(other)),
+            };
+            let strict = decode_blocks_strict(&input, EofMarker::Optional);
+            assert_eq!(strict, strict_expected, "case {stem}: strict result mismatch");
         }
     }
 }
+
+#[test]
+fn decompress_detailed_reports_failure_offsets() {
+    // (stream, output size, error, input offset, output offset)
+    let cases: [(&[u8], usize, Error, usize, usize); 5] = [
+        // Truncated literal run after a complete one.
+        (&[0x01, b'a', b'b', 0x04, b'c', b'd'], 64, Error::InvalidData, 3, 2),
+        // Back-reference reaching before the start of the output.
+        (&[0x00, b'a', 0x20, 0x00, 0x20, 0x05], 64, Error::InvalidData, 4, 4),
+        // Long back-reference missing its length byte.
+        (&[0x02, b'a', b'b', b'c', 0xe0], 64, Error::InvalidData, 4, 3),
+        // Back-reference missing its offset byte.
+        (&[0x00, b'a', 0x20], 64, Error::InvalidData, 2, 1),
+        // Valid stream, output exhausted by the second literal run.
+        (&[0x01, b'a', b'b', 0x01, b'c', b'd'], 3, Error::OutputTooSmall, 3, 2),
+    ];
+    for (stream, out_len, kind, input_offset, output_offset) in cases {
+        let mut out = vec![0u8; out_len];
+        let detail = decompress_detailed(stream, &mut out).expect_err("expected failure");
+        assert_eq!(
+            detail,
+            DecodeErrorDetail { kind, input_offset, output_offset },
+            "{stream:02x?}"
+        );
+        assert_eq!(decompress(stream, &mut out), Err(kind));
+    }
+
+    let mut out = [0u8; 4];
+    assert_eq!(decompress_detailed(&[0x00, b'a', 0x20, 0x00], &mut out), Ok(4));
+    assert_eq!(&out, b"aaaa");
+}
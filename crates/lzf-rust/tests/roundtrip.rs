@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: ISC
 use lzf_rust::{
-    Error, compress, decode_blocks, decompress, decompress_into_vec, encode_blocks,
-    max_compressed_size,
+    Error, compress, decode_blocks, decompress, decompress_into_vec, decompress_to_vec,
+    decompress_to_vec_with_limit, decompressed_size, encode_blocks, max_compressed_size, validate,
 };
 
 fn lcg_data(size: usize) -> Vec<u8> {
@@ -76,3 +76,200 @@ fn too_small_output_fails() {
     let err = decompress(&compressed, &mut out).expect_err("expected output-too-small");
     assert_eq!(err, Error::OutputTooSmall);
 }
+
+#[test]
+fn too_small_output_on_literal_fails() {
+    // Incompressible input encodes as literal runs only.
+    let input = lcg_data(200);
+    let compressed = compress_vec(&input);
+    assert_eq!(compressed[0], 31, "expected a full literal run first");
+
+    for len in [0, 1, 31, 32, 33, input.len() - 1] {
+        let mut out = vec![0u8; len];
+        assert_eq!(decompress(&compressed, &mut out), Err(Error::OutputTooSmall), "len {len}");
+    }
+    assert_eq!(decompress(&[0x02, b'a', b'b', b'c'], &mut [0u8; 2]), Err(Error::OutputTooSmall));
+}
+
+#[test]
+fn truncated_literal_is_invalid_even_with_small_output() {
+    assert_eq!(decompress(&[0x02, b'a'], &mut [0u8; 16]), Err(Error::InvalidData));
+    assert_eq!(decompress(&[0x02, b'a'], &mut [0u8; 1]), Err(Error::InvalidData));
+}
+
+/// Encodes a back-reference token of `len` bytes reaching `dist` bytes back.
+fn backref_token(dist: usize, len: usize) -> Vec<u8> {
+    let (l, off) = (len - 2, dist - 1);
+    let hi = (off >> 8) as u8;
+    if l < 7 {
+        vec![(l as u8) << 5 | hi, off as u8]
+    } else {
+        vec![0xe0 | hi, (l - 7) as u8, off as u8]
+    }
+}
+
+#[test]
+fn overlapping_back_references_repeat_the_pattern() {
+    for dist in [1usize, 2, 3, 7, 8, 9, 31] {
+        for len in [3usize, 8, 9, 264] {
+            let seed: Vec<u8> = (0..dist as u8).map(|i| i.wrapping_mul(37) ^ 0x5a).collect();
+            let mut stream = vec![dist as u8 - 1];
+            stream.extend_from_slice(&seed);
+            stream.extend(backref_token(dist, len));
+
+            let mut expected = seed.clone();
+            for _ in 0..len {
+                expected.push(expected[expected.len() - dist]);
+            }
+
+            let mut out = vec![0u8; expected.len()];
+            assert_eq!(decompress(&stream, &mut out), Ok(expected.len()), "dist {dist} len {len}");
+            assert_eq!(out, expected, "dist {dist} len {len}");
+        }
+    }
+}
+
+fn compress_vec(input: &[u8]) -> Vec<u8> {
+    let mut compressed = vec![0u8; max_compressed_size(input.len())];
+    let compressed_len = compress(input, &mut compressed).expect("compress");
+    compressed.truncate(compressed_len);
+    compressed
+}
+
+#[test]
+fn decompress_to_vec_grows_output() {
+    let input = vec![b'z'; 1 << 20];
+    let compressed = compress_vec(&input);
+    assert!(compressed.len() * 50 < input.len());
+
+    assert_eq!(decompress_to_vec(&compressed).expect("decompress_to_vec"), input);
+    assert_eq!(decompress_to_vec(&[]).expect("empty"), Vec::<u8>::new());
+
+    let mixed = lcg_data(50_000);
+    assert_eq!(decompress_to_vec(&compress_vec(&mixed)).expect("decompress_to_vec"), mixed);
+}
+
+#[test]
+fn decompress_to_vec_with_limit_rejects_oversized_output() {
+    let input = vec![0u8; 100_000];
+    let compressed = compress_vec(&input);
+
+    let err = decompress_to_vec_with_limit(&compressed, 4096).expect_err("limit");
+    assert_eq!(err, Error::LimitExceeded);
+    let exact = decompress_to_vec_with_limit(&compressed, input.len()).expect("exact limit");
+    assert_eq!(exact, input);
+    assert_eq!(exact.capacity(), input.len());
+}
+
+#[test]
+fn decompress_to_vec_reports_corruption_not_sizing() {
+    // Valid prefix expanding well beyond 2x the input, then a back-reference
+    // reaching before the start of the output.
+    let mut stream = compress_vec(&[7u8; 600]);
+    stream.extend_from_slice(&[0b0011_1111, 0xff]);
+
+    let mut guessed = vec![0u8; stream.len() * 2];
+    assert_eq!(decompress(&stream, &mut guessed), Err(Error::OutputTooSmall));
+    assert_eq!(decompress_to_vec(&stream), Err(Error::InvalidData));
+}
+
+#[test]
+fn decompressed_size_agrees_with_decompress() {
+    let mut seed = 0x9e37_79b9u32;
+    for case in 0..200usize {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        let len = (seed >> 8) as usize % 20_000;
+        // Mix incompressible noise with runs drawn from a small alphabet.
+        let alphabet = 1 + (case % 7) as u32 * 40;
+        let mut input = Vec::with_capacity(len);
+        while input.len() < len {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let run = 1 + (seed >> 27) as usize;
+            input.extend(std::iter::repeat_n(((seed >> 16) % alphabet) as u8, run));
+        }
+        input.truncate(len);
+
+        let compressed = compress_vec(&input);
+        assert_eq!(decompressed_size(&compressed), Ok(input.len()), "case {case}");
+        let mut out = vec![0u8; input.len()];
+        assert_eq!(decompress(&compressed, &mut out), Ok(input.len()), "case {case}");
+    }
+}
+
+#[test]
+fn decompressed_size_rejects_truncated_tokens() {
+    assert_eq!(decompressed_size(&[]), Ok(0));
+    // Literal run of two bytes with only one present.
+    assert_eq!(decompressed_size(&[0x01, b'x']), Err(Error::InvalidData));
+    // Short back-reference missing its offset byte.
+    assert_eq!(decompressed_size(&[0x00, b'a', 0x20]), Err(Error::InvalidData));
+    // Long back-reference missing its length byte.
+    assert_eq!(decompressed_size(&[0x00, b'a', 0xe0]), Err(Error::InvalidData));
+    // Long back-reference missing its offset byte.
+    assert_eq!(decompressed_size(&[0x00, b'a', 0xe0, 0x05]), Err(Error::InvalidData));
+    assert_eq!(decompressed_size(&[0x00, b'a', 0xe0, 0x05, 0x00]), Ok(1 + 14));
+    // A back-reference before the start of the output is only caught by
+    // `validate`.
+    assert_eq!(decompressed_size(&[0x00, b'a', 0x20, 0x01]), Ok(1 + 3));
+    assert_eq!(validate(&[0x00, b'a', 0x20, 0x01]), Err(Error::InvalidData));
+}
+
+#[test]
+fn validate_agrees_with_decompress_on_mutated_streams() {
+    let mut seed = 0x2545_f491u32;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        seed >> 8
+    };
+
+    let base =
+        compress_vec(b"validate validate validate: the quick brown fox, the quick brown dog");
+    for case in 0..2000usize {
+        let mut stream = if case % 4 == 0 {
+            (0..next() % 64).map(|_| next() as u8).collect()
+        } else {
+            base.clone()
+        };
+        for _ in 0..1 + next() % 3 {
+            if !stream.is_empty() {
+                let at = next() as usize % stream.len();
+                stream[at] = next() as u8;
+            }
+        }
+        if case % 3 == 0 {
+            stream.truncate(next() as usize % (stream.len() + 1));
+        }
+
+        // Large enough that `OutputTooSmall` cannot occur.
+        let mut out = vec![0u8; stream.len() * 100 + 8];
+        assert_eq!(validate(&stream), decompress(&stream, &mut out), "case {case}: {stream:02x?}");
+    }
+}
+
+#[test]
+fn every_error_has_exactly_one_class() {
+    let all = [
+        Error::Eof,
+        Error::Interrupted,
+        Error::OutputTooSmall,
+        Error::WriteZero,
+        Error::InvalidData,
+        Error::InvalidHeader,
+        Error::UnknownBlockType(3),
+        Error::InvalidParameter,
+        Error::LimitExceeded,
+        Error::ChecksumMismatch,
+        Error::Other,
+    ];
+    for err in all {
+        let classes = [err.is_corruption(), err.is_io(), err.is_config()];
+        assert_eq!(classes.iter().filter(|&&c| c).count(), 1, "{err:?}");
+    }
+
+    assert!(Error::ChecksumMismatch.is_corruption());
+    assert!(Error::UnknownBlockType(0xff).is_corruption());
+    assert!(Error::Eof.is_io());
+    assert!(Error::WriteZero.is_io());
+    assert!(Error::OutputTooSmall.is_config());
+    assert!(Error::LimitExceeded.is_config());
+}
@@ -13,14 +13,7 @@ fn pattern_data(size: usize) -> Vec<u8> {
 
 fn read_all<R: Read>(reader: &mut R) -> Vec<u8> {
     let mut out = Vec::new();
-    let mut buf = [0u8; 4096];
-    loop {
-        let n = reader.read(&mut buf).expect("read");
-        if n == 0 {
-            break;
-        }
-        out.extend_from_slice(&buf[..n]);
-    }
+    Read::read_to_end(reader, &mut out).expect("read");
     out
 }
 
@@ -109,3 +102,1526 @@ fn auto_finish_flushes_on_drop() {
     let output = read_all(&mut reader);
     assert_eq!(output, input);
 }
+
+/// Sink that fails once when a write reaches `fail_at` bytes, and fails its
+/// first flush.
+#[derive(Debug)]
+struct Flaky {
+    out: Vec<u8>,
+    fail_at: Option<usize>,
+    flush_failed: bool,
+}
+
+impl Write for Flaky {
+    fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+        let room = match self.fail_at {
+            Some(at) if self.out.len() == at => {
+                self.fail_at = None;
+                return Err(lzf_rust::Error::Other);
+            }
+            Some(at) => at - self.out.len(),
+            None => buf.len(),
+        };
+        let n = room.min(buf.len());
+        self.out.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> lzf_rust::Result<()> {
+        if self.flush_failed {
+            Ok(())
+        } else {
+            self.flush_failed = true;
+            Err(lzf_rust::Error::Other)
+        }
+    }
+}
+
+#[test]
+fn try_finish_returns_the_writer_and_resumes_cut_off_frames() {
+    let input = pattern_data(3 * 4096 + 1000);
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_footer();
+    writer.write_all(&input).expect("write");
+    let expected = writer.finish().expect("finish");
+    let written_before_finish =
+        lzf_rust::encode_blocks(&input[..3 * 4096], 4096).expect("encode").len();
+
+    for fail_at in written_before_finish..expected.len() {
+        let sink = Flaky { out: Vec::new(), fail_at: Some(fail_at), flush_failed: false };
+        let mut writer = LzfWriter::new(sink, 4096).expect("writer");
+        writer.enable_footer();
+        writer.write_all(&input).expect("write");
+
+        let err = writer.try_finish().expect_err("write failure");
+        assert_eq!(err.error, lzf_rust::Error::Other);
+        let err = err.into_writer().try_finish().expect_err("flush failure");
+        assert_eq!(err.error, lzf_rust::Error::Other);
+        let sink = err.into_writer().try_finish().expect("retry");
+        assert_eq!(sink.out, expected, "fail_at {fail_at}");
+    }
+
+    // The failed sink can also just be taken back.
+    let sink = Flaky { out: Vec::new(), fail_at: None, flush_failed: false };
+    let mut writer = LzfWriter::new_with_eof_marker(sink, 4096).expect("writer");
+    writer.write_all(b"partial").expect("write");
+    let sink = writer.try_finish().expect_err("flush failure").into_writer().into_inner();
+    assert_eq!(lzf_rust::decode_blocks(&sink.out).expect("decode"), b"partial");
+}
+
+#[test]
+fn limited_writer_stops_lzf_writer_at_its_budget() {
+    let input = pattern_data(3 * 1024 + 300);
+    let mut clean = LzfWriter::new_with_eof_marker(Vec::new(), 1024).expect("writer");
+    clean.write_all(&input).expect("write");
+    let expected = clean.finish().expect("finish");
+    let first_frame = lzf_rust::encode_blocks(&input[..1024], 1024).expect("encode").len();
+
+    // In the first header, in the first payload, and on the end-of-stream marker.
+    for limit in [3, first_frame - 10, expected.len() - 1] {
+        let sink = lzf_rust::LimitedWriter::new(Vec::new(), limit as u64);
+        let mut writer = LzfWriter::new_with_eof_marker(sink, 1024).expect("writer");
+        let res = writer.write_all(&input).and_then(|()| writer.flush_block());
+        if limit < first_frame {
+            assert_eq!(res, Err(lzf_rust::Error::LimitExceeded), "limit {limit}");
+        }
+        let err = writer.try_finish().expect_err("over budget");
+        assert_eq!(err.error, lzf_rust::Error::LimitExceeded, "limit {limit}");
+
+        // The sink holds exactly the budgeted prefix; once the budget is
+        // raised, the rest of the stream follows.
+        let mut writer = err.into_writer();
+        assert_eq!(writer.inner().remaining(), 0);
+        assert_eq!(writer.inner().get_ref()[..], expected[..limit]);
+        if limit < first_frame {
+            continue;
+        }
+        writer.inner_mut().set_remaining(u64::MAX);
+        let sink = writer.try_finish().expect("finish");
+        assert_eq!(sink.into_inner(), expected, "limit {limit}");
+    }
+}
+
+#[test]
+fn writer_resumes_frames_after_a_failure_at_any_offset() {
+    let input = pattern_data(3 * 1024 + 300);
+    let mut clean = LzfWriter::new(Vec::new(), 1024).expect("writer");
+    clean.enable_footer();
+    clean.write_all(&input).expect("write");
+    let expected = clean.finish().expect("finish");
+
+    for fail_at in 0..expected.len() {
+        let sink = Flaky { out: Vec::new(), fail_at: Some(fail_at), flush_failed: true };
+        let mut writer = LzfWriter::new(sink, 1024).expect("writer");
+        writer.enable_footer();
+        let mut failures = 0;
+        let mut rest = input.as_slice();
+        while !rest.is_empty() {
+            // Chunks of 700 bytes let frames complete inside and across calls.
+            match writer.write(&rest[..rest.len().min(700)]) {
+                Ok(n) => rest = &rest[n..],
+                Err(err) => {
+                    assert_eq!(err, lzf_rust::Error::Other);
+                    failures += 1;
+                }
+            }
+        }
+        let sink = loop {
+            match writer.try_finish() {
+                Ok(sink) => break sink,
+                Err(err) => {
+                    failures += 1;
+                    writer = err.into_writer();
+                }
+            }
+        };
+        // A failure inside `write` is not reported when the retry of the
+        // cut-off frame succeeds.
+        assert!(failures <= 1, "fail_at {fail_at}");
+        assert_eq!(sink.out, expected, "fail_at {fail_at}");
+    }
+}
+
+#[test]
+fn write_block_reports_a_failed_write_and_keeps_the_record() {
+    let records = [pattern_data(700), pattern_data(2000), b"tail".to_vec()];
+    let buffered: [&[u8]; 3] = [b"buffered before the first record", b"and the second", b""];
+    let mut clean = LzfWriter::new(Vec::new(), 1024).expect("writer");
+    clean.enable_footer();
+    for (data, record) in buffered.iter().zip(&records) {
+        clean.write_all(data).expect("write");
+        clean.write_block(record).expect("record");
+    }
+    let expected = clean.finish().expect("finish");
+
+    // Each call writes the block buffered before it, if any, and its record.
+    let frames: Vec<_> =
+        lzf_rust::FrameIter::new(&expected).collect::<Result<_, _>>().expect("frames");
+    assert_eq!(frames.len(), 5);
+    let calls =
+        [0..frames[1].end(), frames[2].offset..frames[3].end(), frames[4].offset..frames[4].end()];
+
+    for fail_at in 0..expected.len() {
+        let sink = Flaky { out: Vec::new(), fail_at: Some(fail_at), flush_failed: true };
+        let mut writer = LzfWriter::new(sink, 1024).expect("writer");
+        writer.enable_footer();
+        for (i, (data, record)) in buffered.iter().zip(&records).enumerate() {
+            writer.write_all(data).expect("write");
+            // The failing call reports the error; its record is taken anyway
+            // and must not be written again.
+            let expect =
+                if calls[i].contains(&fail_at) { Err(lzf_rust::Error::Other) } else { Ok(()) };
+            assert_eq!(writer.write_block(record), expect, "fail_at {fail_at}, record {i}");
+        }
+        let sink = loop {
+            match writer.try_finish() {
+                Ok(sink) => break sink,
+                Err(err) => writer = err.into_writer(),
+            }
+        };
+        assert_eq!(sink.out, expected, "fail_at {fail_at}");
+    }
+}
+
+#[test]
+fn auto_finisher_can_finish_explicitly_or_be_disarmed() {
+    let input = pattern_data(10_000);
+    let expected = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer").auto_finish();
+    writer.write_all(&input).expect("write");
+    assert_eq!(writer.finish().expect("finish"), expected);
+
+    let sink = Flaky { out: Vec::new(), fail_at: None, flush_failed: false };
+    let mut writer = LzfWriter::new(sink, 4096).expect("writer").auto_finish();
+    writer.write_all(&input).expect("write");
+    assert_eq!(writer.finish().map(|_| ()), Err(lzf_rust::Error::Other));
+
+    let shared = Rc::new(RefCell::new(Vec::<u8>::new()));
+    let mut writer =
+        LzfWriter::new(SharedVecWriter(shared.clone()), 4096).expect("writer").auto_finish();
+    writer.write_all(&input).expect("write");
+    let writer = writer.into_inner();
+    let written = shared.borrow().len();
+    assert!(written < expected.len());
+    writer.finish().expect("finish");
+    assert_eq!(*shared.borrow(), expected);
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer").into_std().auto_finish();
+    std::io::Write::write_all(&mut *writer, &input).expect("write");
+    assert_eq!(writer.finish().expect("finish"), expected);
+}
+
+#[test]
+fn read_into_exact_decodes_aligned_blocks_in_place() {
+    let input = pattern_data(64 * 4096);
+    let encoded = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    let mut reader = LzfReader::new(encoded.as_slice());
+    let mut output = vec![0u8; input.len()];
+    for chunk in output.chunks_mut(4 * 4096) {
+        reader.read_into_exact(chunk).expect("read_into_exact");
+    }
+
+    assert_eq!(output, input);
+    assert!(reader.zero_copy_bytes() * 100 >= input.len() as u64 * 95);
+    assert_eq!(reader.read(&mut [0u8; 1]).expect("read at eof"), 0);
+}
+
+#[test]
+fn read_into_exact_copies_only_straddling_blocks() {
+    let input = pattern_data(50_000);
+    let encoded = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    let mut reader = LzfReader::new(encoded.as_slice());
+    let mut output = vec![0u8; input.len()];
+    for chunk in output.chunks_mut(10_000) {
+        reader.read_into_exact(chunk).expect("read_into_exact");
+    }
+    assert_eq!(output, input);
+
+    // Each 10_000-byte call straddles at most one 4096-byte block.
+    let copied = input.len() as u64 - reader.zero_copy_bytes();
+    assert!(copied <= 5 * 4096, "copied {copied} bytes");
+
+    let mut reader = LzfReader::new(encoded.as_slice());
+    let mut too_long = vec![0u8; input.len() + 1];
+    assert_eq!(reader.read_into_exact(&mut too_long), Err(lzf_rust::Error::Eof));
+}
+
+#[test]
+fn block_jobs_match_sequential_writer() {
+    fn assert_send<T: Send>() {}
+    assert_send::<lzf_rust::BlockJob>();
+    assert_send::<lzf_rust::CompressedFrame>();
+
+    let input = pattern_data(100_000);
+
+    let mut sequential = LzfWriter::new_with_eof_marker(Vec::new(), 4096).expect("writer");
+    sequential.write_all(&input).expect("write");
+    let expected = sequential.finish().expect("finish");
+
+    let mut writer = LzfWriter::new_with_eof_marker(Vec::new(), 4096).expect("writer");
+    writer.enable_block_jobs();
+    for chunk in input.chunks(10_000) {
+        writer.write_all(chunk).expect("write");
+        let frames: Vec<_> =
+            std::iter::from_fn(|| writer.take_block_job()).map(|job| job.run()).collect();
+        for frame in frames {
+            writer.accept_frame(frame).expect("accept_frame");
+        }
+    }
+    let encoded = writer.finish().expect("finish");
+
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn block_jobs_reorder_out_of_order_frames() {
+    let input = pattern_data(3 * 4096 + 100);
+
+    let mut sequential = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    sequential.write_all(&input).expect("write");
+    let expected = sequential.finish().expect("finish");
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_block_jobs();
+    writer.write_all(&input).expect("write");
+
+    let jobs: Vec<_> = std::iter::from_fn(|| writer.take_block_job()).collect();
+    assert_eq!(jobs.iter().map(|job| job.seq()).collect::<Vec<_>>(), [0, 1, 2]);
+    let mut frames: Vec<_> = jobs.into_iter().map(|job| job.run()).collect();
+
+    // Outstanding jobs block inline flushing.
+    assert_eq!(writer.flush(), Err(lzf_rust::Error::InvalidParameter));
+
+    let first = frames.remove(0);
+    for frame in frames.into_iter().rev() {
+        writer.accept_frame(frame).expect("accept_frame");
+        assert!(writer.inner().is_empty(), "frames written before their predecessor");
+    }
+    writer.accept_frame(first).expect("accept_frame");
+
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(encoded, expected);
+}
+
+#[test]
+fn block_jobs_reject_unknown_and_duplicate_frames() {
+    fn frames_of(input: &[u8]) -> Vec<lzf_rust::CompressedFrame> {
+        let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+        writer.enable_block_jobs();
+        writer.write_all(input).expect("write");
+        std::iter::from_fn(|| writer.take_block_job()).map(|job| job.run()).collect()
+    }
+
+    let input = pattern_data(2 * 4096);
+    let mut foreign = frames_of(&input);
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_block_jobs();
+    writer.write_all(&input).expect("write");
+    let job = writer.take_block_job().expect("job");
+
+    // Frame 1 belongs to a job that was never taken from `writer`.
+    let unknown = foreign.pop().expect("frame 1");
+    assert_eq!(writer.accept_frame(unknown), Err(lzf_rust::Error::InvalidParameter));
+
+    writer.accept_frame(job.run()).expect("accept_frame");
+    let duplicate = foreign.pop().expect("frame 0");
+    assert_eq!(writer.accept_frame(duplicate), Err(lzf_rust::Error::InvalidParameter));
+}
+
+#[test]
+fn block_jobs_reject_frames_of_another_writer() {
+    let input = pattern_data(2 * 4096);
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_block_jobs();
+    writer.write_all(&input).expect("write");
+    let mut other = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    other.enable_block_jobs();
+    other.write_all(&[b'A'; 4096]).expect("write");
+
+    let job = writer.take_block_job().expect("job");
+    let foreign = other.take_block_job().expect("job").run();
+    assert_eq!(foreign.seq(), job.seq());
+    assert_eq!(writer.accept_frame(foreign), Err(lzf_rust::Error::InvalidParameter));
+
+    writer.accept_frame(job.run()).expect("accept_frame");
+    while let Some(job) = writer.take_block_job() {
+        writer.accept_frame(job.run()).expect("accept_frame");
+    }
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), input);
+}
+
+#[test]
+fn block_jobs_taken_before_a_reset_are_refused() {
+    let mut writer = LzfWriter::new(Vec::new(), 4).expect("writer");
+    writer.enable_block_jobs();
+    writer.write_all(b"OLD!").expect("write");
+    let stale = writer.take_block_job().expect("job");
+
+    writer.reset(Vec::new());
+    writer.write_all(b"NEW!").expect("write");
+    let fresh = writer.take_block_job().expect("job");
+    assert_eq!(stale.seq(), fresh.seq());
+    assert_eq!(writer.accept_frame(stale.run()), Err(lzf_rust::Error::InvalidParameter));
+    writer.accept_frame(fresh.run()).expect("accept_frame");
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), b"NEW!");
+}
+
+#[test]
+fn writer_with_checksums_matches_encode_blocks_checked() {
+    let input = pattern_data(3 * 4096 + 100);
+    let expected = lzf_rust::encode_blocks_checked(&input, 4096, lzf_rust::CompressionMode::Normal)
+        .expect("encode");
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_checksums();
+    writer.write_all(&input[..1000]).expect("write 1");
+    writer.write_all(&input[1000..]).expect("write 2");
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(encoded, expected);
+
+    let mut reader = LzfReader::new(encoded.as_slice());
+    assert_eq!(read_all(&mut reader), input);
+
+    let mut corrupt = encoded;
+    *corrupt.last_mut().expect("payload") ^= 0x10;
+    let mut reader = LzfReader::new(corrupt.as_slice());
+    let mut out = vec![0u8; input.len()];
+    assert_eq!(reader.read_exact(&mut out), Err(lzf_rust::Error::ChecksumMismatch));
+    let mut reader = LzfReader::new(corrupt.as_slice());
+    assert_eq!(reader.read_into_exact(&mut out), Err(lzf_rust::Error::ChecksumMismatch));
+}
+
+#[test]
+fn writer_with_options_matches_encode_blocks_with_options() {
+    let mut x = 0x9e37_79b9u32;
+    let mut input: Vec<u8> = (0..3 * 4096)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        })
+        .collect();
+    input.extend_from_slice(&pattern_data(2 * 4096 + 100));
+    let options = lzf_rust::EncodeOptions::new()
+        .mode(lzf_rust::CompressionMode::Best)
+        .skip_incompressible(true)
+        .min_savings_per_mille(125);
+    let expected = lzf_rust::encode_blocks_with_options(&input, 4096, options).expect("encode");
+
+    let mut writer = LzfWriter::new_with_options(Vec::new(), 4096, options).expect("writer");
+    for chunk in input.chunks(3000) {
+        writer.write_all(chunk).expect("write");
+    }
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(encoded, expected);
+    assert_eq!(read_all(&mut LzfReader::new(encoded.as_slice())), input);
+}
+
+#[test]
+fn uncompressed_writer_stores_every_block() {
+    let input = pattern_data(3 * 4096 + 100);
+    let mut writer = LzfWriter::new_uncompressed(Vec::new(), 4096).expect("writer");
+    for chunk in input.chunks(1500) {
+        writer.write_all(chunk).expect("write");
+    }
+    let encoded = writer.finish().expect("finish");
+
+    let frames: Vec<_> =
+        lzf_rust::FrameIter::new(&encoded).collect::<Result<_, _>>().expect("frames");
+    assert_eq!(frames.len(), 4);
+    assert!(frames.iter().all(|frame| frame.block_type == lzf_rust::BlockType::Uncompressed));
+    assert_eq!(encoded.len(), input.len() + 4 * 5);
+    let options = lzf_rust::EncodeOptions::new().compress(false);
+    assert_eq!(
+        lzf_rust::encode_blocks_with_options(&input, 4096, options).expect("encode"),
+        encoded
+    );
+    assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), input);
+    assert_eq!(read_all(&mut LzfReader::new(encoded.as_slice())), input);
+}
+
+#[test]
+fn writer_builder_validates_the_block_size() {
+    for block_size in [0, 65536, usize::MAX] {
+        let result = lzf_rust::LzfWriterBuilder::new().block_size(block_size).build(Vec::new());
+        assert!(matches!(result, Err(lzf_rust::Error::InvalidParameter)), "{block_size}");
+    }
+    for block_size in [1, 65535] {
+        assert!(lzf_rust::LzfWriterBuilder::new().block_size(block_size).build(Vec::new()).is_ok());
+    }
+    assert!(lzf_rust::LzfWriterBuilder::default().build(Vec::new()).is_ok());
+}
+
+#[test]
+fn writer_builder_matches_legacy_constructors() {
+    use lzf_rust::{CompressionMode, LzfWriterBuilder};
+
+    fn encode(mut writer: LzfWriter<Vec<u8>>, input: &[u8]) -> Vec<u8> {
+        for chunk in input.chunks(3000) {
+            writer.write_all(chunk).expect("write");
+        }
+        writer.finish().expect("finish")
+    }
+
+    let input = pattern_data(5 * 4096 + 321);
+    let builder = LzfWriterBuilder::new().block_size(4096);
+    let cases = [
+        (LzfWriter::new(Vec::new(), 4096), builder),
+        (
+            LzfWriter::new_with_mode(Vec::new(), 4096, CompressionMode::Best),
+            builder.mode(CompressionMode::Best),
+        ),
+        (LzfWriter::new_with_eof_marker(Vec::new(), 4096), builder.eof_marker(true)),
+        (
+            LzfWriter::new_with_eof_marker_and_mode(Vec::new(), 4096, CompressionMode::Best),
+            builder.mode(CompressionMode::Best).eof_marker(true),
+        ),
+        (LzfWriter::new_uncompressed(Vec::new(), 4096), builder.compress(false)),
+    ];
+    for (legacy, builder) in cases {
+        let built = builder.build(Vec::new()).expect("builder");
+        assert_eq!(encode(built, &input), encode(legacy.expect("legacy"), &input), "{builder:?}");
+    }
+
+    let mut legacy = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    legacy.enable_checksums();
+    legacy.enable_footer();
+    let built = builder.checksums(true).footer(true).build(Vec::new()).expect("builder");
+    let encoded = encode(built, &input);
+    assert_eq!(encoded, encode(legacy, &input));
+
+    let mut reader = lzf_rust::LzfReaderBuilder::new()
+        .capacity(4096)
+        .limits(4096, input.len() as u64)
+        .footer_check(true)
+        .build(encoded.as_slice());
+    assert_eq!(read_all(&mut reader), input);
+    assert!(reader.buffered_capacity() <= 2 * 4096);
+
+    let mut reader = lzf_rust::LzfReaderBuilder::new()
+        .limits(4096, input.len() as u64 - 1)
+        .build(encoded.as_slice());
+    let mut out = vec![0u8; input.len()];
+    assert_eq!(reader.read_exact(&mut out), Err(lzf_rust::Error::LimitExceeded));
+}
+
+#[test]
+fn writer_min_savings_selects_block_types_per_block() {
+    // Each block is half random bytes and half a repeated byte, so it saves
+    // a little under half its length.
+    let mut x = 0x9e37_79b9u32;
+    let mut input = Vec::new();
+    for _ in 0..3 {
+        input.extend((0..2048).map(|_| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as u8
+        }));
+        input.extend_from_slice(&[b'r'; 2048]);
+    }
+    let mut compressed = vec![0u8; lzf_rust::max_compressed_size(4096)];
+    let savings = 4096 - lzf_rust::compress(&input[..4096], &mut compressed).expect("compress");
+    assert!(savings > 1000 && savings < 2048, "{savings}");
+
+    let block_types = |builder: lzf_rust::LzfWriterBuilder| {
+        let mut writer = builder.block_size(4096).build(Vec::new()).expect("writer");
+        writer.write_all(&input).expect("write");
+        let encoded = writer.finish().expect("finish");
+        assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), input);
+        lzf_rust::FrameIter::new(&encoded)
+            .map(|frame| frame.expect("frame").block_type)
+            .collect::<Vec<_>>()
+    };
+    let compressed = vec![lzf_rust::BlockType::Compressed; 3];
+    let stored = vec![lzf_rust::BlockType::Uncompressed; 3];
+    let builder = lzf_rust::LzfWriterBuilder::new();
+    assert_eq!(block_types(builder), compressed);
+    assert_eq!(block_types(builder.min_savings_bytes(savings)), compressed);
+    assert_eq!(block_types(builder.min_savings_bytes(savings + 1)), stored);
+
+    let per_mille = (savings * 1000 / 4096) as u16;
+    assert_eq!(block_types(builder.min_savings_per_mille(per_mille)), compressed);
+    assert_eq!(block_types(builder.min_savings_per_mille(per_mille + 1)), stored);
+}
+
+#[test]
+fn write_vectored_matches_sequential_write_all() {
+    let input = pattern_data(5 * 4096 + 777);
+    let mut expected_writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    expected_writer.enable_footer();
+    expected_writer.write_all(&input).expect("write");
+    let expected = expected_writer.finish().expect("finish");
+
+    for sizes in [
+        &[1usize, 4095, 1][..],
+        &[4096],
+        &[0, 4096, 0, 8192],
+        &[100, 5000, 3],
+        &[4097, 4095],
+        &[12_000, 1],
+        &[7, 0, 13, 4076],
+        &[input.len()],
+    ] {
+        let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+        writer.enable_footer();
+        let mut rest = input.as_slice();
+        while !rest.is_empty() {
+            let mut slices = Vec::new();
+            for &size in sizes {
+                let (head, tail) = rest.split_at(size.min(rest.len()));
+                slices.push(lzf_rust::IoSlice::new(head));
+                rest = tail;
+            }
+            let len: usize = slices.iter().map(|slice| slice.len()).sum();
+            assert_eq!(writer.write_vectored(&slices).expect("write_vectored"), len);
+        }
+        assert_eq!(writer.finish().expect("finish"), expected, "{sizes:?}");
+    }
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer").into_std();
+    let slices = [std::io::IoSlice::new(&input[..3000]), std::io::IoSlice::new(&input[3000..])];
+    assert_eq!(std::io::Write::write_vectored(&mut writer, &slices).expect("write"), input.len());
+    let framed = writer.finish().expect("finish");
+    assert_eq!(lzf_rust::decode_blocks(&framed).expect("decode"), input);
+}
+
+#[test]
+fn default_write_vectored_stops_at_short_writes() {
+    struct Limited(Vec<u8>, usize);
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> lzf_rust::Result<usize> {
+            if self.0.len() == self.1 {
+                return Err(lzf_rust::Error::WriteZero);
+            }
+            let n = buf.len().min(self.1 - self.0.len());
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> lzf_rust::Result<()> {
+            Ok(())
+        }
+    }
+
+    let slices = [
+        lzf_rust::IoSlice::new(b"abc"),
+        lzf_rust::IoSlice::new(b""),
+        lzf_rust::IoSlice::new(b"defg"),
+    ];
+    let mut sink = Limited(Vec::new(), 5);
+    assert_eq!(sink.write_vectored(&slices).expect("write"), 5);
+    assert_eq!(sink.0, b"abcde");
+    assert_eq!(sink.write_vectored(&slices), Err(lzf_rust::Error::WriteZero));
+}
+
+#[test]
+fn writer_reset_encodes_independent_streams() {
+    let inputs = [pattern_data(3 * 4096 + 5), b"short".to_vec(), Vec::new(), pattern_data(9000)];
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_footer();
+    let mut streams = Vec::new();
+    for input in &inputs {
+        writer.write_all(input).expect("write");
+        streams.push(writer.finish_and_reset(Vec::new()).expect("finish"));
+    }
+    for (input, stream) in inputs.iter().zip(&streams) {
+        let expected =
+            lzf_rust::encode_blocks_with_footer(input, 4096, lzf_rust::CompressionMode::Normal)
+                .expect("encode");
+        assert_eq!(stream, &expected);
+    }
+
+    // Unfinished input is dropped by `reset`; the settings are kept.
+    writer.write_all(b"abandoned").expect("write");
+    let abandoned = writer.reset(Vec::new());
+    assert!(abandoned.is_empty());
+    writer.write_all(&inputs[0]).expect("write");
+    assert_eq!(writer.finish().expect("finish"), streams[0]);
+
+    let mut writer = LzfWriter::new_with_eof_marker(Vec::new(), 4096).expect("writer");
+    writer.enable_block_jobs();
+    writer.write_all(&inputs[3]).expect("write");
+    let job = writer.take_block_job().expect("job");
+    let first = writer.reset(Vec::new());
+    assert!(first.is_empty());
+    assert!(matches!(writer.accept_frame(job.run()), Err(lzf_rust::Error::InvalidParameter)));
+    writer.write_all(&inputs[3]).expect("write");
+    while let Some(job) = writer.take_block_job() {
+        writer.accept_frame(job.run()).expect("accept");
+    }
+    let second = writer.finish().expect("finish");
+    assert_eq!(second.last(), Some(&0));
+    assert_eq!(lzf_rust::decode_blocks(&second).expect("decode"), inputs[3]);
+}
+
+#[test]
+fn set_mode_applies_from_the_next_emitted_block() {
+    use lzf_rust::CompressionMode::{Best, Normal};
+
+    let text: Vec<u8> = (0..6 * 4096)
+        .map(|i: usize| b"the quick brown fox jumps over the lazy dog "[(i * 7 / 5 + i / 91) % 44])
+        .collect();
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    assert_eq!(writer.mode(), Normal);
+    let modes = [Normal, Best, Normal, Best, Best, Normal];
+    for (block, &mode) in text.chunks(4096).zip(&modes) {
+        writer.set_mode(mode);
+        assert_eq!(writer.mode(), mode);
+        writer.write_all(block).expect("write");
+    }
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), text);
+
+    let frames: Vec<_> =
+        lzf_rust::FrameIter::new(&encoded).collect::<Result<_, _>>().expect("frames");
+    assert_eq!(frames.len(), modes.len());
+    for ((frame, block), &mode) in frames.iter().zip(text.chunks(4096)).zip(&modes) {
+        let single = lzf_rust::encode_blocks_with_mode(block, 4096, mode).expect("encode");
+        assert_eq!(&encoded[frame.offset..frame.end()], single, "{mode:?}");
+    }
+    let normal = lzf_rust::encode_blocks_with_mode(&text[..4096], 4096, Normal).expect("encode");
+    let best = lzf_rust::encode_blocks_with_mode(&text[..4096], 4096, Best).expect("encode");
+    assert!(best.len() < normal.len());
+
+    // The buffered partial block takes the mode in effect when it is emitted.
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.write_all(&text[..2000]).expect("write");
+    writer.set_mode(Best);
+    writer.write_all(&text[2000..4096]).expect("write");
+    assert_eq!(writer.finish().expect("finish"), best);
+}
+
+#[test]
+fn write_block_emits_one_block_per_record() {
+    let input = pattern_data(30_000);
+    let mut writer = LzfWriter::new_with_eof_marker(Vec::new(), 4096).expect("writer");
+    writer.write_all(&input[..5000]).expect("write");
+    writer.write_block(&input[5000..5300]).expect("record");
+    writer.write_block(&input[5300..15_300]).expect("record");
+    writer.write_block(b"").expect("empty record");
+    writer.write_all(&input[15_300..15_310]).expect("write");
+    writer.write_block(&input[15_310..30_000]).expect("record");
+    assert!(matches!(
+        writer.write_block(&vec![0u8; 65536]),
+        Err(lzf_rust::Error::InvalidParameter)
+    ));
+    let encoded = writer.finish().expect("finish");
+    assert_eq!(encoded.last(), Some(&0));
+    assert_eq!(lzf_rust::decode_blocks(&encoded).expect("decode"), input);
+
+    let lens: Vec<usize> = lzf_rust::FrameIter::new(&encoded)
+        .map(|frame| frame.expect("frame").uncompressed_len)
+        .collect();
+    assert_eq!(lens, [4096, 904, 300, 10_000, 0, 10, 14_690]);
+
+    let mut reader = LzfReader::new(encoded.as_slice());
+    let mut block = Vec::new();
+    let mut records = Vec::new();
+    while let Some(decoded) = reader.read_block(&mut block).expect("read_block") {
+        records.push(decoded.len);
+    }
+    assert_eq!(records, lens);
+}
+
+#[test]
+fn writer_footer_matches_encode_blocks_with_footer() {
+    let input = pattern_data(3 * 4096 + 77);
+    let expected =
+        lzf_rust::encode_blocks_with_footer(&input, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode");
+
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer");
+    writer.enable_footer();
+    for chunk in input.chunks(1000) {
+        writer.write_all(chunk).expect("write");
+    }
+    assert_eq!(writer.finish().expect("finish"), expected);
+}
+
+#[test]
+fn reader_footer_check_verifies_footers() {
+    let input = pattern_data(2 * 4096 + 10);
+    let framed =
+        lzf_rust::encode_blocks_with_footer(&input, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode");
+    let read_checked = |framed: &[u8]| {
+        let mut reader = LzfReader::new(framed);
+        reader.enable_footer_check();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).map(|_| out)
+    };
+
+    assert_eq!(read_checked(&framed), Ok(input.clone()));
+    let mut corrupt = framed.clone();
+    *corrupt.last_mut().expect("footer") ^= 1;
+    assert_eq!(read_checked(&corrupt), Err(lzf_rust::Error::ChecksumMismatch));
+    assert_eq!(read_checked(&framed[..framed.len() - 4]), Err(lzf_rust::Error::Eof));
+
+    // Without a footer the marker still ends the stream; other trailing data
+    // is rejected once footer checking is on.
+    let legacy = &framed[..framed.len() - 16];
+    assert_eq!(read_checked(legacy), Ok(input.clone()));
+    let mut trailing = legacy.to_vec();
+    trailing.extend_from_slice(b"not a footer....");
+    assert_eq!(read_checked(&trailing), Err(lzf_rust::Error::InvalidData));
+
+    // By default the footer is left unread.
+    let mut src: &[u8] = &corrupt;
+    assert_eq!(read_all(&mut LzfReader::new(&mut src)), input);
+    assert_eq!(src.len(), 16);
+
+    let mut into = vec![0u8; input.len()];
+    let mut reader = LzfReader::new(corrupt.as_slice());
+    reader.enable_footer_check();
+    reader.read_into_exact(&mut into).expect("blocks");
+    assert_eq!(reader.read(&mut [0u8; 1]), Err(lzf_rust::Error::ChecksumMismatch));
+}
+
+#[test]
+fn lenient_reader_skips_unknown_blocks() {
+    let input = pattern_data(2 * 4096 + 50);
+    let standard = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    let mut interleaved = Vec::new();
+    for frame in lzf_rust::FrameIter::new(&standard) {
+        let frame = frame.expect("frame");
+        interleaved.extend_from_slice(b"ZV\x90\x00\x04meta");
+        interleaved.extend_from_slice(&standard[frame.offset..frame.end()]);
+    }
+    interleaved.extend_from_slice(b"ZV\x91\x00\x00");
+
+    let mut reader = LzfReader::new(interleaved.as_slice());
+    let mut out = vec![0u8; input.len()];
+    assert_eq!(reader.read_exact(&mut out), Err(lzf_rust::Error::UnknownBlockType(0x90)));
+
+    let mut reader = LzfReader::new(interleaved.as_slice());
+    reader.enable_lenient();
+    assert_eq!(read_all(&mut reader), input);
+
+    let mut reader = LzfReader::new(interleaved.as_slice());
+    reader.enable_lenient();
+    reader.read_into_exact(&mut out).expect("read_into_exact");
+    assert_eq!(out, input);
+}
+
+#[test]
+fn recompress_blocks_preserves_content() {
+    use lzf_rust::{CompressionMode, decode_blocks, encode_blocks, recompress_blocks};
+
+    let input = pattern_data(300_000);
+    let framed = encode_blocks(&input, 4096).expect("encode");
+    for (block_size, mode) in [
+        (65535, CompressionMode::Best),
+        (65535, CompressionMode::Normal),
+        (1000, CompressionMode::Normal),
+        (4096, CompressionMode::Best),
+        (7, CompressionMode::Normal),
+    ] {
+        let mut out = Vec::new();
+        let stats = recompress_blocks(&mut framed.as_slice(), &mut out, block_size, mode)
+            .expect("recompress");
+        assert_eq!(decode_blocks(&out).expect("decode"), input, "{block_size} {mode:?}");
+        assert_eq!(
+            out,
+            lzf_rust::encode_blocks_with_mode(&input, block_size, mode).expect("encode"),
+            "{block_size} {mode:?}"
+        );
+        assert_eq!(stats.bytes_in, framed.len() as u64);
+        assert_eq!(stats.bytes_out, out.len() as u64);
+        assert_eq!(stats.decoded_len, input.len() as u64);
+        assert!(!stats.eof_marker);
+    }
+}
+
+#[test]
+fn recompress_blocks_preserves_eof_marker() {
+    use lzf_rust::{CompressionMode, encode_blocks, recompress_blocks};
+
+    let input = pattern_data(10_000);
+    let mut framed = encode_blocks(&input, 4096).expect("encode");
+    framed.push(0);
+    let marked_len = framed.len();
+    framed.extend_from_slice(b"trailing bytes");
+
+    let mut src = framed.as_slice();
+    let mut out = Vec::new();
+    let stats =
+        recompress_blocks(&mut src, &mut out, 1024, CompressionMode::Normal).expect("recompress");
+    assert!(stats.eof_marker);
+    assert_eq!(stats.bytes_in, marked_len as u64);
+    assert_eq!(src, b"trailing bytes");
+    assert_eq!(out.last(), Some(&0));
+    assert_eq!(lzf_rust::decode_blocks(&out).expect("decode"), input);
+
+    let mut out = Vec::new();
+    let stats = recompress_blocks(&mut [0u8].as_slice(), &mut out, 1024, CompressionMode::Normal)
+        .expect("marker only");
+    assert_eq!((stats.bytes_in, stats.bytes_out, stats.eof_marker), (1, 1, true));
+    assert_eq!(out, [0]);
+
+    assert_eq!(
+        recompress_blocks(&mut framed.as_slice(), &mut Vec::new(), 0, CompressionMode::Normal),
+        Err(lzf_rust::Error::InvalidParameter)
+    );
+}
+
+#[test]
+fn reader_reports_the_failing_block_position() {
+    let input = pattern_data(5 * 4096);
+    let mut framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    let frames: Vec<_> =
+        lzf_rust::FrameIter::new(&framed).map(|frame| frame.expect("frame").offset).collect();
+    framed[frames[3] + 2] = 0x42;
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    assert_eq!(reader.last_error(), None);
+    let mut out = vec![0u8; input.len()];
+    let err = reader.read_into_exact(&mut out).expect_err("unknown block type");
+    assert_eq!(
+        reader.last_error(),
+        Some(lzf_rust::FramedError { kind: err, block_index: 3, input_offset: frames[3] })
+    );
+    assert_eq!(
+        lzf_rust::decode_blocks_detailed(&framed).expect_err("detailed"),
+        reader.last_error().expect("last_error")
+    );
+    assert_eq!(
+        reader.last_error().expect("last_error").to_string(),
+        format!("unknown LZF block type: 66 in block 3 at input offset {}", frames[3])
+    );
+    let last = reader.last_error().expect("last_error");
+    let source = std::error::Error::source(&last).expect("source");
+    assert_eq!(source.downcast_ref::<lzf_rust::Error>(), Some(&err));
+}
+
+#[test]
+fn std_reader_works_with_io_copy_and_lines() {
+    let input = pattern_data(50_000);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    let mut reader = LzfReader::new(framed.as_slice()).into_std();
+    let mut out = Vec::new();
+    let copied = std::io::copy(&mut reader, &mut out).expect("copy");
+    assert_eq!(copied, input.len() as u64);
+    assert_eq!(out, input);
+
+    let text: String = (0..2000).map(|i| format!("line {i}\n")).collect();
+    let framed = lzf_rust::encode_blocks(text.as_bytes(), 1000).expect("encode");
+    let reader = std::io::BufReader::new(LzfReader::new(framed.as_slice()).into_std());
+    let lines: Vec<String> =
+        std::io::BufRead::lines(reader).collect::<Result<_, _>>().expect("lines");
+    assert_eq!(lines.len(), 2000);
+    assert_eq!(lines[1234], "line 1234");
+
+    // Directly as `BufRead`, across empty blocks too.
+    let mut framed = b"ZV\x00\x00\x00".to_vec();
+    framed.extend(lzf_rust::encode_blocks(text.as_bytes(), 1000).expect("encode"));
+    let reader = LzfReader::new(framed.as_slice()).into_std();
+    let lines = std::io::BufRead::lines(reader).count();
+    assert_eq!(lines, 2000);
+}
+
+#[test]
+fn std_reader_maps_errors_to_io_kinds() {
+    let framed = lzf_rust::encode_blocks_checked(
+        &pattern_data(5000),
+        4096,
+        lzf_rust::CompressionMode::Normal,
+    )
+    .expect("encode");
+    let mut corrupt = framed.clone();
+    *corrupt.last_mut().expect("payload") ^= 1;
+    let mut reader = LzfReader::new(corrupt.as_slice()).into_std();
+    let err = std::io::copy(&mut reader, &mut std::io::sink()).expect_err("corrupt");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(lzf_rust::Error::from(err), lzf_rust::Error::ChecksumMismatch);
+    assert_eq!(
+        reader.inner().last_error().map(|e| e.kind),
+        Some(lzf_rust::Error::ChecksumMismatch)
+    );
+
+    let mut reader = LzfReader::new(&framed[..framed.len() - 1]).into_std();
+    let err = std::io::Read::read_to_end(&mut reader, &mut Vec::new()).expect_err("truncated");
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+/// Reader yielding one byte per call and failing with `Interrupted` every
+/// `interrupt_every` calls.
+struct Trickle<'a> {
+    data: &'a [u8],
+    calls: usize,
+    interrupt_every: usize,
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> lzf_rust::Result<usize> {
+        self.calls += 1;
+        if self.calls % self.interrupt_every == 0 {
+            return Err(lzf_rust::Error::Interrupted);
+        }
+        let n = buf.len().min(self.data.len()).min(1);
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn read_to_end_handles_byte_at_a_time_and_interrupted_readers() {
+    let input = pattern_data(3 * 4096 + 5);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    let mut reader =
+        LzfReader::new(Trickle { data: &framed, calls: 0, interrupt_every: usize::MAX });
+    let mut out = b"prefix".to_vec();
+    assert_eq!(reader.read_to_end(&mut out), Ok(input.len()));
+    assert_eq!(&out[..6], b"prefix");
+    assert_eq!(out[6..], input[..]);
+
+    for interrupt_every in [2, 7] {
+        let mut trickle = Trickle { data: &framed, calls: 0, interrupt_every };
+        let mut out = vec![1, 2, 3];
+        assert_eq!(trickle.read_to_end(&mut out), Ok(framed.len()));
+        assert_eq!(out[..3], [1, 2, 3]);
+        assert_eq!(out[3..], framed[..]);
+    }
+}
+
+#[test]
+fn read_exact_or_eof_reports_partial_fills() {
+    let data = b"ZV\x01\x00\x10\x00\x20";
+    for len in [0, 1, 6, 7] {
+        let mut buf = [0u8; 7];
+        let mut trickle = Trickle { data: &data[..len], calls: 0, interrupt_every: 3 };
+        assert_eq!(trickle.read_exact_or_eof(&mut buf), Ok(len));
+        assert_eq!(buf[..len], data[..len]);
+
+        let mut source = &data[..len];
+        assert_eq!(source.read_exact_or_eof(&mut buf), Ok(len));
+        assert!(source.is_empty());
+    }
+
+    // Errors other than `Interrupted` are still errors.
+    let mut reader = LzfReader::new(&b"XV\x00\x00\x01x"[..]);
+    assert_eq!(reader.read_exact_or_eof(&mut [0u8; 4]), Err(lzf_rust::Error::InvalidHeader));
+}
+
+#[test]
+fn short_junk_after_a_stream_is_a_bad_header() {
+    let input = pattern_data(5000);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    for (tail, expected) in [
+        (&b""[..], Ok(input.len())),
+        (b"x", Err(lzf_rust::Error::InvalidHeader)),
+        (b"Zx", Err(lzf_rust::Error::InvalidHeader)),
+        (b"Z", Err(lzf_rust::Error::Eof)),
+        (b"ZV\x01\x00", Err(lzf_rust::Error::Eof)),
+        (b"ZV\x01\x00\x10\x00", Err(lzf_rust::Error::Eof)),
+        (b"\x00xyz", Ok(input.len())),
+    ] {
+        let mut stream = framed.clone();
+        stream.extend_from_slice(tail);
+        let mut src = stream.as_slice();
+        let mut reader = LzfReader::new(&mut src);
+        let mut out = Vec::new();
+        assert_eq!(reader.read_to_end(&mut out), expected, "{tail:?}");
+        assert_eq!(out, input);
+        if tail.first() == Some(&0) {
+            assert_eq!(src, b"xyz");
+        }
+    }
+}
+
+#[test]
+fn read_to_end_keeps_bytes_read_before_an_error() {
+    let input = pattern_data(2 * 4096);
+    let mut framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    framed.truncate(framed.len() - 1);
+    let mut reader = LzfReader::new(framed.as_slice());
+    let mut out = Vec::new();
+    assert_eq!(reader.read_to_end(&mut out), Err(lzf_rust::Error::Eof));
+    assert_eq!(out, input[..4096]);
+}
+
+#[test]
+fn reader_limits_reject_oversized_blocks_before_allocating() {
+    let mut framed = b"ZV\x00\xff\xff".to_vec();
+    framed.resize(5 + 65535, b'x');
+    let mut reader = LzfReader::with_limits(framed.as_slice(), 4096, u64::MAX);
+    assert_eq!(reader.read(&mut [0u8; 100]), Err(lzf_rust::Error::LimitExceeded));
+    assert_eq!(reader.buffered_capacity(), 0);
+    let err = reader.last_error().expect("last_error");
+    assert_eq!((err.block_index, err.input_offset), (0, 0));
+
+    // Compressed payloads count too, even when the decoded length is small.
+    let hostile = b"ZV\x01\xff\xff\x00\x10";
+    let mut reader = LzfReader::with_limits(&hostile[..], 4096, u64::MAX);
+    assert_eq!(reader.read(&mut [0u8; 16]), Err(lzf_rust::Error::LimitExceeded));
+    assert_eq!(reader.buffered_capacity(), 0);
+
+    let input = pattern_data(4 * 4096);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    let mut reader = LzfReader::with_limits(framed.as_slice(), 4096, u64::MAX);
+    assert_eq!(read_all(&mut reader), input);
+    assert!(reader.buffered_capacity() <= 2 * 4096);
+}
+
+#[test]
+fn reader_limits_cap_the_total_decoded_length() {
+    let input = pattern_data(3 * 4096);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    let mut reader = LzfReader::with_limits(framed.as_slice(), 65535, 2 * 4096 + 1);
+    let mut out = Vec::new();
+    assert_eq!(reader.read_to_end(&mut out), Err(lzf_rust::Error::LimitExceeded));
+    assert!(out.len() <= 2 * 4096 && input.starts_with(&out));
+    assert_eq!(reader.last_error().expect("last_error").block_index, 2);
+
+    let mut reader = LzfReader::with_limits(framed.as_slice(), 4096, input.len() as u64);
+    assert_eq!(read_all(&mut reader), input);
+}
+
+fn marker_member(data: &[u8]) -> Vec<u8> {
+    let mut writer = LzfWriter::new_with_eof_marker(Vec::new(), 4096).expect("writer");
+    writer.write_all(data).expect("write");
+    writer.finish().expect("finish")
+}
+
+#[test]
+fn concatenated_reader_decodes_every_member() {
+    let parts = [pattern_data(5000), b"second member".to_vec(), pattern_data(9000)];
+    let mut framed = Vec::new();
+    for part in &parts {
+        framed.extend(marker_member(part));
+    }
+    // Extra markers between members are empty members.
+    framed.extend_from_slice(&[0, 0]);
+    framed.extend(marker_member(b"after empty members"));
+    let expected = [&parts.concat()[..], b"after empty members"].concat();
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    assert_eq!(read_all(&mut reader), parts[0]);
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    assert_eq!(read_all(&mut reader), expected);
+    assert_eq!(reader.read(&mut [0u8; 8]), Ok(0));
+
+    // Garbage after the last member is an error, not a silent end.
+    framed.extend_from_slice(b"garbage");
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    let mut out = Vec::new();
+    assert_eq!(reader.read_to_end(&mut out), Err(lzf_rust::Error::InvalidHeader));
+    assert!(expected.starts_with(&out));
+}
+
+#[test]
+fn concatenated_reader_checks_each_member_footer() {
+    let first = pattern_data(6000);
+    let second = b"footer of the second member".to_vec();
+    let encode = |data: &[u8]| {
+        lzf_rust::encode_blocks_with_footer(data, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode")
+    };
+    let mut framed = encode(&first);
+    framed.extend(marker_member(b"no footer"));
+    framed.extend(encode(&second));
+    let expected = [&first[..], b"no footer", &second[..]].concat();
+    assert_eq!(lzf_rust::decode_blocks_multi(&framed).expect("multi"), expected);
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    reader.enable_footer_check();
+    assert_eq!(read_all(&mut reader), expected);
+
+    *framed.last_mut().expect("footer") ^= 1;
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    reader.enable_footer_check();
+    let mut out = Vec::new();
+    assert_eq!(reader.read_to_end(&mut out), Err(lzf_rust::Error::ChecksumMismatch));
+}
+
+#[test]
+fn concatenated_reader_skips_member_footers_without_checking_them() {
+    let encode = |data: &[u8]| {
+        lzf_rust::encode_blocks_with_footer(data, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode")
+    };
+    let mut framed = encode(b"first");
+    framed.extend(encode(b"second"));
+    assert_eq!(lzf_rust::decode_blocks_multi(&framed).expect("multi"), b"firstsecond");
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    assert_eq!(read_all(&mut reader), b"firstsecond");
+
+    // Unchecked footers are still parsed, so a cut-off one is an error.
+    framed.truncate(framed.len() - 3);
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.enable_concatenated();
+    let mut out = Vec::new();
+    assert_eq!(reader.read_to_end(&mut out), Err(lzf_rust::Error::Eof));
+    assert_eq!(out, b"firstsecond");
+}
+
+#[test]
+fn reader_counters_track_compressed_and_decoded_bytes() {
+    let input = pattern_data(10_000);
+    let mut framed = marker_member(&input);
+    framed.truncate(framed.len() - 1);
+    framed.extend(lzf_rust::encode_blocks(b"tail", 4096).expect("encode"));
+    framed.push(0);
+    let expected = [&input[..], b"tail"].concat();
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    let mut out = Vec::new();
+    // Odd-sized reads straddle every block boundary.
+    let mut buf = [0u8; 333];
+    loop {
+        let n = reader.read(&mut buf).expect("read");
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+        assert_eq!(reader.decompressed_bytes_read(), out.len() as u64);
+        assert!(reader.compressed_bytes_read() <= framed.len() as u64);
+    }
+    assert_eq!(out, expected);
+    assert_eq!(reader.compressed_bytes_read(), framed.len() as u64);
+    assert_eq!(reader.decompressed_bytes_read(), expected.len() as u64);
+    assert_eq!(reader.blocks_read(), 4);
+}
+
+#[test]
+fn skip_matches_reading_and_slicing() {
+    let input = pattern_data(50_000);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+
+    for (skip, keep) in [(0, 100), (1, 4095), (4096, 4096), (12_345, 20_000), (49_990, 10)] {
+        let mut reader = LzfReader::new(framed.as_slice());
+        assert_eq!(reader.read(&mut [0u8; 7]), Ok(7));
+        assert_eq!(reader.skip(skip), Ok(skip));
+        assert_eq!(reader.decompressed_bytes_read(), 7 + skip);
+        let start = 7 + skip as usize;
+        let end = (start + keep).min(input.len());
+        let mut out = vec![0u8; end - start];
+        reader.read_into_exact(&mut out).expect("read");
+        assert_eq!(out, &input[start..end]);
+    }
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    assert_eq!(reader.skip(u64::MAX), Ok(input.len() as u64));
+    assert_eq!(reader.skip(1), Ok(0));
+    assert_eq!(reader.read(&mut [0u8; 1]), Ok(0));
+}
+
+#[test]
+fn indexed_reader_seeks_to_random_offsets() {
+    use std::io::Cursor;
+
+    // Compressible halves around an incompressible middle give both block types.
+    let mut input = pattern_data(20_000);
+    let mut state = 0x9e37_79b9u32;
+    input.extend((0..9_000).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    }));
+    input.extend(pattern_data(11_111));
+    let mut framed = marker_member(&input);
+    framed.extend_from_slice(b"trailing data");
+
+    let mut reader = lzf_rust::LzfIndexedReader::open(Cursor::new(framed)).expect("open");
+    assert_eq!(reader.decoded_len(), input.len() as u64);
+    let index = reader.index().to_vec();
+    assert_eq!(index.len(), input.len().div_ceil(4096));
+    assert!(index.iter().all(|e| e.decoded_offset % 4096 == 0));
+    assert_eq!(read_all(&mut reader), input);
+
+    let mut offsets: Vec<u64> = index.iter().map(|e| e.decoded_offset).collect();
+    offsets.extend([1, 4095, 20_000, 24_576, input.len() as u64 - 1, input.len() as u64]);
+    for _ in 0..64 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        offsets.push(u64::from(state) % input.len() as u64);
+    }
+    for offset in offsets {
+        reader.seek_to(offset).expect("seek");
+        assert_eq!(reader.position(), offset);
+        let start = offset as usize;
+        let end = (start + 5_000).min(input.len());
+        let mut out = vec![0u8; end - start];
+        reader.read_exact(&mut out).expect("read");
+        assert_eq!(out, &input[start..end], "offset {offset}");
+        if end == input.len() {
+            assert_eq!(reader.read(&mut [0u8; 1]), Ok(0));
+        }
+    }
+
+    let position = reader.position();
+    assert_eq!(reader.seek_to(input.len() as u64 + 1), Err(lzf_rust::Error::InvalidParameter));
+    assert_eq!(reader.position(), position);
+}
+
+#[test]
+fn indexed_reader_rejects_truncated_streams() {
+    use std::io::Cursor;
+
+    let framed = lzf_rust::encode_blocks(&pattern_data(10_000), 4096).expect("encode");
+    let cut = framed[..framed.len() - 1].to_vec();
+    assert!(matches!(
+        lzf_rust::LzfIndexedReader::open(Cursor::new(cut)),
+        Err(lzf_rust::Error::Eof)
+    ));
+
+    let mut empty = lzf_rust::LzfIndexedReader::open(Cursor::new(Vec::new())).expect("open");
+    assert!(empty.index().is_empty());
+    empty.seek_to(0).expect("seek");
+    assert_eq!(empty.read(&mut [0u8; 1]), Ok(0));
+}
+
+#[test]
+fn reset_reuses_buffers_and_matches_fresh_reader() {
+    // Streams shrink, so the first one sizes the buffers for all of them.
+    let streams: Vec<Vec<u8>> = (0..20)
+        .map(|i| {
+            let data = pattern_data(4000 - i * 50);
+            lzf_rust::encode_blocks(&data, 4096).expect("encode")
+        })
+        .collect();
+    let mut reader = LzfReader::new(streams[0].as_slice());
+    let mut capacity = 0;
+    for (i, framed) in streams.iter().enumerate() {
+        reader.reset(framed.as_slice());
+        assert_eq!(read_all(&mut reader), pattern_data(4000 - i * 50));
+        assert_eq!(reader.compressed_bytes_read(), framed.len() as u64);
+        assert_eq!(reader.blocks_read(), 1);
+        if i == 0 {
+            capacity = reader.buffered_capacity();
+        }
+        assert_eq!(reader.buffered_capacity(), capacity);
+    }
+
+    // A reset after a failed, half-read stream behaves like a fresh reader.
+    let mut corrupt = streams[1].clone();
+    corrupt[0] = b'X';
+    reader.reset(corrupt.as_slice());
+    assert_eq!(reader.read(&mut [0u8; 10]), Err(lzf_rust::Error::InvalidHeader));
+    let rest = reader.reset(streams[2].as_slice());
+    assert_eq!(rest.len(), corrupt.len() - 5);
+    assert_eq!(reader.last_error(), None);
+    assert_eq!(read_all(&mut reader), pattern_data(3900));
+    assert_eq!(reader.decompressed_bytes_read(), 3900);
+}
+
+#[test]
+fn read_decodes_blocks_that_fit_straight_into_the_caller_buffer() {
+    let input = pattern_data(100_000);
+    for framed in [
+        lzf_rust::encode_blocks(&input, 4096).expect("encode"),
+        lzf_rust::encode_blocks_checked(&input, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode"),
+    ] {
+        let mut reader = LzfReader::new(framed.as_slice());
+        let mut out = vec![0u8; 1 << 20];
+        let n = reader.read(&mut out).expect("read");
+        assert_eq!(&out[..n], input);
+        assert_eq!(reader.zero_copy_bytes(), input.len() as u64);
+
+        // Buffers smaller than a block still go through the block buffer.
+        let mut reader = LzfReader::new(framed.as_slice());
+        assert_eq!(read_all(&mut reader), input);
+        let mut reader = LzfReader::new(framed.as_slice());
+        let mut small = [0u8; 1000];
+        let mut out = Vec::new();
+        loop {
+            match reader.read(&mut small).expect("read") {
+                0 => break,
+                n => out.extend_from_slice(&small[..n]),
+            }
+        }
+        assert_eq!(out, input);
+        assert_eq!(reader.zero_copy_bytes(), 0);
+    }
+}
+
+#[test]
+fn read_block_preserves_record_boundaries() {
+    use lzf_rust::{BlockType, CompressionMode, encode_block};
+
+    let records: Vec<Vec<u8>> =
+        [0usize, 1, 17, 300, 4096, 0, 65_535, 9].iter().map(|&len| pattern_data(len)).collect();
+    let mut framed = Vec::new();
+    for record in &records {
+        framed.extend(encode_block(record, CompressionMode::Normal).expect("encode"));
+    }
+    framed.push(0);
+
+    let frames: Vec<_> =
+        lzf_rust::FrameIter::new(&framed).collect::<Result<_, _>>().expect("frames");
+    assert!(frames.iter().any(|f| f.block_type == BlockType::Compressed));
+
+    let mut reader = LzfReader::new(framed.as_slice());
+    let mut out = vec![1u8; 10];
+    for (record, frame) in records.iter().zip(&frames) {
+        let block = reader.read_block(&mut out).expect("read_block").expect("block");
+        assert_eq!(out, *record);
+        assert_eq!(block.len, record.len());
+        assert_eq!(block.block_type, frame.block_type);
+        assert_eq!(block.compressed, frame.is_compressed());
+    }
+    assert_eq!(reader.read_block(&mut out), Ok(None));
+    assert!(out.is_empty());
+    assert_eq!(reader.blocks_read(), records.len() as u64);
+
+    // After a partial `read`, the rest of the current block comes first.
+    let mut reader = LzfReader::new(framed.as_slice());
+    reader.read_block(&mut out).expect("empty record");
+    reader.read_block(&mut out).expect("one byte record");
+    reader.read_block(&mut out).expect("17 byte record");
+    let mut head = [0u8; 100];
+    assert_eq!(reader.read(&mut head), Ok(100));
+    let block = reader.read_block(&mut out).expect("read_block").expect("block");
+    assert_eq!((block.len, block.compressed), (200, frames[3].is_compressed()));
+    assert_eq!(out, &records[3][100..]);
+    assert_eq!(reader.read_block(&mut out).expect("read_block").map(|b| b.len), Some(4096));
+}
+
+#[test]
+fn reader_retries_interrupted_reads_of_every_kind() {
+    let input = pattern_data(3 * 4096 + 5);
+    let mut framed = marker_member(&input);
+    framed.extend(
+        lzf_rust::encode_blocks_with_footer(&input, 4096, lzf_rust::CompressionMode::Normal)
+            .expect("encode"),
+    );
+
+    // Every successful one-byte read is preceded by an interrupted one, so
+    // header, payload, and footer reads all see `Interrupted`.
+    let mut reader = LzfReader::new(Trickle { data: &framed, calls: 0, interrupt_every: 2 });
+    reader.enable_concatenated();
+    reader.enable_footer_check();
+    assert_eq!(read_all(&mut reader), [&input[..], &input[..]].concat());
+}
+
+/// Reader that reports end of input once at `gap`, then continues.
+struct Gappy<'a> {
+    data: &'a [u8],
+    pos: usize,
+    gap: Option<usize>,
+}
+
+impl Read for Gappy<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> lzf_rust::Result<usize> {
+        if self.gap == Some(self.pos) {
+            self.gap = None;
+            return Ok(0);
+        }
+        let end = self.gap.filter(|&gap| gap > self.pos).unwrap_or(self.data.len());
+        let n = buf.len().min(end - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn reader_resumes_after_a_transient_end_of_input() {
+    let input = pattern_data(3 * 4096);
+    let framed = lzf_rust::encode_blocks(&input, 4096).expect("encode");
+    let second = lzf_rust::FrameIter::new(&framed).nth(1).expect("block").expect("frame");
+
+    let gappy = Gappy { data: &framed, pos: 0, gap: Some(second.offset) };
+    let mut reader = LzfReader::new(gappy);
+    let mut first = vec![0u8; 4096];
+    assert_eq!(reader.read(&mut first), Ok(4096));
+    assert_eq!(first, input[..4096]);
+    assert_eq!(reader.read(&mut first), Ok(0));
+    assert_eq!(read_all(&mut reader), input[4096..]);
+    assert_eq!(reader.read(&mut [0u8; 1]), Ok(0));
+
+    // An end-of-stream marker is final, however much input follows it.
+    let mut framed = lzf_rust::encode_blocks(&input[..100], 4096).expect("encode");
+    framed.push(0);
+    framed.extend_from_slice(&lzf_rust::encode_blocks(&input, 4096).expect("encode"));
+    let mut reader = LzfReader::new(framed.as_slice());
+    assert_eq!(read_all(&mut reader), input[..100]);
+    assert_eq!(read_all(&mut reader), []);
+}
+
+#[test]
+fn with_capacity_preallocates_for_blocks_up_to_the_hint() {
+    let input = pattern_data(100_000);
+    let framed = lzf_rust::encode_blocks(&input, 8192).expect("encode");
+
+    let mut reader = LzfReader::with_capacity(framed.as_slice(), 8192);
+    let capacity = reader.buffered_capacity();
+    assert!(capacity >= 2 * 8192);
+    let mut buf = [0u8; 1000];
+    let mut out = Vec::new();
+    loop {
+        match reader.read(&mut buf).expect("read") {
+            0 => break,
+            n => out.extend_from_slice(&buf[..n]),
+        }
+        assert_eq!(reader.buffered_capacity(), capacity);
+    }
+    assert_eq!(out, input);
+
+    assert_eq!(LzfReader::new(framed.as_slice()).buffered_capacity(), 0);
+    assert!(LzfReader::with_capacity(framed.as_slice(), usize::MAX).buffered_capacity() < 3 << 16);
+}
+
+#[test]
+fn std_writer_works_with_io_copy() {
+    let input = pattern_data(300_000);
+    let mut source = std::io::Cursor::new(input.clone());
+    let mut writer = LzfWriter::new(Vec::new(), 4096).expect("writer").into_std();
+    let copied = std::io::copy(&mut source, &mut writer).expect("copy");
+    assert_eq!(copied, input.len() as u64);
+    let framed = writer.finish().expect("finish");
+    assert_eq!(lzf_rust::decode_blocks(&framed).expect("decode"), input);
+
+    let sink = Rc::new(RefCell::new(Vec::new()));
+    {
+        let mut writer = LzfWriter::new(SharedVecWriter(sink.clone()), 4096)
+            .expect("writer")
+            .into_std()
+            .auto_finish();
+        std::io::Write::write_all(&mut *writer, b"finished on drop").expect("write");
+    }
+    assert_eq!(lzf_rust::decode_blocks(&sink.borrow()).expect("decode"), b"finished on drop");
+}
+
+#[test]
+fn flush_block_ends_blocks_at_record_boundaries() {
+    let records = [pattern_data(100), pattern_data(5000), Vec::new(), pattern_data(4096)];
+    let sink = Rc::new(RefCell::new(Vec::new()));
+    let mut writer = LzfWriter::new(SharedVecWriter(sink.clone()), 4096).expect("writer");
+    for record in &records {
+        writer.write_all(record).expect("write");
+        writer.flush_block().expect("flush_block");
+        writer.flush_block().expect("empty flush_block");
+    }
+    // Every block is complete in the sink before finishing.
+    let framed = sink.borrow().clone();
+    writer.finish().expect("finish");
+    assert_eq!(*sink.borrow(), framed);
+
+    let lens: Vec<usize> = lzf_rust::FrameIter::new(&framed)
+        .map(|frame| frame.expect("frame").uncompressed_len)
+        .collect();
+    assert_eq!(lens, [100, 4096, 904, 4096]);
+    assert_eq!(lzf_rust::decode_blocks(&framed).expect("decode"), records.concat());
+}
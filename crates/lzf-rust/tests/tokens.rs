@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: ISC
+use lzf_rust::{
+    CompressionMode, Error, MAX_OFFSET, Token, Tokens, compress_with_mode, decompress,
+    max_compressed_size,
+};
+
+fn lcg_data(size: usize) -> Vec<u8> {
+    let mut x = 0x2545_f491u32;
+    let mut out = Vec::with_capacity(size);
+    while out.len() < size {
+        x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+        let run = 1 + (x >> 27) as usize;
+        let byte = if x & 0x300 == 0 { (x >> 16) as u8 } else { b'a' + (x >> 10) as u8 % 3 };
+        out.extend(std::iter::repeat_n(byte, run));
+    }
+    out.truncate(size);
+    out
+}
+
+/// Rebuilds the decoded output from the token list alone.
+fn replay(stream: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    for token in Tokens::new(stream) {
+        match token? {
+            Token::Literal { input_offset, len } => {
+                out.extend_from_slice(&stream[input_offset..input_offset + len]);
+            }
+            Token::Backref { offset, len } => {
+                assert!((1..=MAX_OFFSET).contains(&offset));
+                for _ in 0..len {
+                    out.push(out[out.len() - offset]);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[test]
+fn tokens_reconstruct_decompress_output() {
+    for size in [0usize, 1, 50, 4096, 100_000] {
+        let input = lcg_data(size);
+        for mode in [CompressionMode::Normal, CompressionMode::Best] {
+            let mut compressed = vec![0u8; max_compressed_size(input.len())];
+            let n = compress_with_mode(&input, &mut compressed, mode).expect("compress");
+            compressed.truncate(n);
+
+            let total: usize =
+                Tokens::new(&compressed).map(|t| t.expect("token").decoded_len()).sum();
+            let mut out = vec![0u8; input.len()];
+            assert_eq!(decompress(&compressed, &mut out), Ok(total));
+            assert_eq!(replay(&compressed).expect("replay"), input);
+        }
+    }
+}
+
+#[test]
+fn malformed_streams_fail_at_the_offending_token() {
+    let cases: [(&[u8], usize); 5] = [
+        // Truncated literal run.
+        (&[0x00, b'a', 0x02, b'b'], 1),
+        // Back-reference before the start of the output.
+        (&[0x20, 0x00], 0),
+        (&[0x01, b'a', b'b', 0x20, 0x01, 0x20, 0x07], 2),
+        // Missing long-length byte.
+        (&[0x00, b'a', 0xe0], 1),
+        // Missing offset byte.
+        (&[0x00, b'a', 0x20], 1),
+    ];
+    for (stream, bad_index) in cases {
+        let items: Vec<_> = Tokens::new(stream).collect();
+        assert_eq!(items.len(), bad_index + 1, "{stream:02x?}");
+        assert!(items[..bad_index].iter().all(Result::is_ok), "{stream:02x?}");
+        assert_eq!(items[bad_index], Err(Error::InvalidData), "{stream:02x?}");
+        assert_eq!(decompress(stream, &mut [0u8; 64]), Err(Error::InvalidData));
+    }
+}
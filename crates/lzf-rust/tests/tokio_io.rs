@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: ISC
+#![cfg(feature = "tokio")]
+
+use lzf_rust::{
+    CompressionMode, EncodeOptions, LzfWriter, TokioLzfReader, TokioLzfWriter, Write, decode_blocks,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+
+fn pattern_data(size: usize) -> Vec<u8> {
+    let mut x = 0x70c1_0b0eu32;
+    (0..size)
+        .map(|i| {
+            x = x.wrapping_mul(1664525).wrapping_add(1013904223);
+            if x & 0x3000 == 0 { (x >> 24) as u8 } else { b"tokio"[i % 5] }
+        })
+        .collect()
+}
+
+fn sync_encode(input: &[u8], block_size: usize, options: EncodeOptions) -> Vec<u8> {
+    let mut writer = LzfWriter::new_with_options(Vec::new(), block_size, options).expect("writer");
+    writer.write_all(input).expect("write");
+    writer.finish().expect("finish")
+}
+
+#[tokio::test]
+async fn round_trip_through_a_small_duplex_pipe() {
+    let input = pattern_data(40_000);
+    for (block_size, pipe_len) in [(4096, 7), (1000, 64), (65_535, 1)] {
+        for options in [EncodeOptions::new(), EncodeOptions::new().checksums(true)] {
+            // Encode through one pipe, then decode the result through another.
+            let (client, server) = duplex(pipe_len);
+            let data = input.clone();
+            let writer = tokio::spawn(async move {
+                let mut writer =
+                    TokioLzfWriter::new_with_options(client, block_size, options).expect("writer");
+                for piece in data.chunks(333) {
+                    writer.write_all(piece).await.expect("write");
+                }
+                writer.shutdown().await.expect("shutdown");
+            });
+
+            let mut raw = Vec::new();
+            let mut server = server;
+            server.read_to_end(&mut raw).await.expect("read framed");
+            writer.await.expect("writer task");
+            assert_eq!(raw, sync_encode(&input, block_size, options));
+
+            let (mut client, server) = duplex(pipe_len);
+            let feeder = tokio::spawn(async move {
+                client.write_all(&raw).await.expect("feed");
+            });
+            let mut reader = TokioLzfReader::new(server);
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).await.expect("read");
+            feeder.await.expect("feeder task");
+            assert_eq!(out, input);
+        }
+    }
+}
+
+#[tokio::test]
+async fn shutdown_writes_the_pending_block_and_marker() {
+    let (client, mut server) = duplex(16);
+    let writer = tokio::spawn(async move {
+        let mut writer =
+            TokioLzfWriter::new_with_eof_marker_and_mode(client, 4096, CompressionMode::Best)
+                .expect("writer");
+        writer.write_all(b"pending block").await.expect("write");
+        writer.shutdown().await.expect("shutdown");
+        assert!(writer.write(b"late").await.is_err());
+    });
+    let mut framed = Vec::new();
+    server.read_to_end(&mut framed).await.expect("read");
+    writer.await.expect("writer task");
+    assert_eq!(framed.last(), Some(&0));
+    assert_eq!(decode_blocks(&framed).expect("decode"), b"pending block");
+
+    // The reader stops at the marker and leaves what follows unread.
+    framed.extend_from_slice(b"after");
+    let mut reader = TokioLzfReader::new(framed.as_slice());
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).await.expect("read");
+    assert_eq!(out, b"pending block");
+    assert_eq!(reader.into_inner(), b"after");
+}
+
+#[tokio::test]
+async fn flushed_frames_survive_dropping_the_writer() {
+    let (client, mut server) = duplex(1 << 16);
+    let mut writer = TokioLzfWriter::new(client, 4096).expect("writer");
+    writer.write_all(&pattern_data(10_000)).await.expect("write");
+    writer.flush().await.expect("flush");
+    writer.write_all(b"never flushed").await.expect("write");
+    drop(writer);
+
+    let mut framed = Vec::new();
+    server.read_to_end(&mut framed).await.expect("read");
+    assert_eq!(decode_blocks(&framed).expect("decode"), pattern_data(10_000));
+}
+
+#[tokio::test]
+async fn reader_errors_map_to_io_error_kinds() {
+    let framed = sync_encode(&pattern_data(10_000), 4096, EncodeOptions::new());
+    let mut reader = TokioLzfReader::new(&framed[..framed.len() - 1]);
+    let err = reader.read_to_end(&mut Vec::new()).await.expect_err("truncated");
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}